@@ -1,14 +1,21 @@
 //! Contains the core code for translating WebAssembly to Rust.
 
+mod call_graph;
 mod const_expr;
 mod data_segment;
 mod display;
+mod dylink;
 mod export;
 mod function;
 mod function_types;
 mod global;
 mod import;
+mod linker;
 mod memory;
+mod pre_init;
+mod snapshot;
+
+pub use linker::Linker;
 
 #[derive(Default)]
 struct GeneratedLines {
@@ -82,7 +89,21 @@ pub struct Translation<'a> {
     data_segment_writer: DataSegmentWriter<'a>,
     wasm_features: &'a wasmparser::WasmFeatures,
     emit_stack_overflow_checks: bool,
+    emit_call_depth_checks: bool,
+    stack_usage_analysis: bool,
+    import_caller: bool,
+    bound_imports: Vec<(&'a str, &'a str, &'a str)>,
+    dynamic_api: bool,
+    func_refs: bool,
+    runtime_linker: bool,
+    fuel_metering: bool,
+    epoch_interruption: bool,
+    exclusive_access: bool,
+    thread_safe: bool,
+    snapshots: bool,
+    pre_initialize: Option<&'a str>,
     debug_info: DebugInfo,
+    async_imports: &'a [(&'a str, &'a str)],
     buffer_pool: Option<&'a crate::buffer::Pool>,
     func_validator_allocation_pool: Option<&'a crate::FuncValidatorAllocationPool>,
 }
@@ -126,7 +147,21 @@ impl<'a> Translation<'a> {
             data_segment_writer: &|_, _| Ok(None),
             wasm_features: &Self::DEFAULT_SUPPORTED_FEATURES,
             emit_stack_overflow_checks: false,
+            emit_call_depth_checks: false,
+            stack_usage_analysis: false,
+            import_caller: false,
+            bound_imports: Vec::new(),
+            dynamic_api: false,
+            func_refs: false,
+            runtime_linker: false,
+            fuel_metering: false,
+            epoch_interruption: false,
+            exclusive_access: false,
+            thread_safe: false,
+            snapshots: false,
+            pre_initialize: None,
             debug_info: Default::default(),
+            async_imports: &[],
             buffer_pool: None,
             func_validator_allocation_pool: None,
         }
@@ -203,6 +238,266 @@ impl<'a> Translation<'a> {
         self
     }
 
+    /// Allows enabling or disabling the emission of call depth checks. Defaults to `false`.
+    ///
+    /// When enabled, every function increments a call depth counter provided by the embedder when
+    /// it is called, [trapping] if a configurable limit is exceeded. Unlike
+    /// [stack overflow checks], this produces identical results on every host, and also works in
+    /// `no_std` environments.
+    ///
+    /// The embedder state must store the `wasm2rs_rt::embedder::ExecutionLimits`. See the
+    /// documentation for `wasm2rs_rt::stack::enter_call()` for more information.
+    ///
+    /// [trapping]: https://webassembly.github.io/spec/core/intro/overview.html#trap
+    /// [stack overflow checks]: Translation::emit_stack_overflow_checks()
+    pub fn emit_call_depth_checks(&mut self, enabled: bool) -> &mut Self {
+        self.emit_call_depth_checks = enabled;
+        self
+    }
+
+    /// Allows enabling or disabling static analysis of stack usage. Defaults to `false`.
+    ///
+    /// When enabled, the module's call graph is used to compute an estimate of the worst-case
+    /// stack space used by each exported function, which is emitted as a constant named
+    /// `MAX_STACK_BYTES_<export>` of type `Option<usize>`. Embedders can use these constants as a
+    /// starting point when choosing thread stack sizes.
+    ///
+    /// The estimate is `None` if the function can reach a recursive call, an indirect call, or an
+    /// imported function.
+    ///
+    /// **This is not a guaranteed bound.** The estimate adds up the same per-function frame sizes
+    /// used by [stack overflow checks], which are approximated from the number of locals and the
+    /// depth of the operand stack. The actual size of each frame is chosen by the Rust compiler,
+    /// and depends on the target, optimization level, and inlining decisions. Embedders should
+    /// leave a safety margin, and should not use these constants to justify disabling
+    /// [stack overflow checks].
+    ///
+    /// [stack overflow checks]: Translation::emit_stack_overflow_checks()
+    pub fn stack_usage_analysis(&mut self, enabled: bool) -> &mut Self {
+        self.stack_usage_analysis = enabled;
+        self
+    }
+
+    /// Sets the imported functions that are async, given as pairs of module and function names.
+    /// Defaults to an empty slice.
+    ///
+    /// An async import is called by `.await`ing the [`Future`] it returns. Every function that
+    /// can reach an async import, including exported functions, is translated as an `async fn`.
+    /// If the start function can reach an async import, then the generated `instantiate`
+    /// function is also `async`.
+    ///
+    /// Calls to recursive `async fn`s box the resulting future, which requires the `alloc`
+    /// feature of `wasm2rs_rt`. See the documentation for `wasm2rs_rt::future` for more
+    /// information.
+    ///
+    /// Async imports can also be used to suspend a call into the WebAssembly module, allowing the
+    /// embedder to resume it later. See the documentation for `wasm2rs_rt::coroutine` for more
+    /// information.
+    ///
+    /// [`Future`]: std::future::Future
+    pub fn async_imports(&mut self, imports: &'a [(&'a str, &'a str)]) -> &mut Self {
+        self.async_imports = imports;
+        self
+    }
+
+    /// Allows enabling or disabling passing the calling instance to imported functions. Defaults
+    /// to `false`.
+    ///
+    /// When enabled, every imported function is called with a reference to the calling
+    /// `Instance` as its first argument, allowing it to access the instance's memories and
+    /// exports, and to call back into the instance. The generated `Instance` implements the
+    /// `wasm2rs_rt::embedder::Caller` trait, which can be used to write imported functions that
+    /// work with any module.
+    pub fn import_caller(&mut self, enabled: bool) -> &mut Self {
+        self.import_caller = enabled;
+        self
+    }
+
+    /// Binds an imported function to a Rust function `path`, such as `crate::math::fmod`.
+    ///
+    /// Rather than calling the imported function through the embedder's imports at run time, the
+    /// generated code calls the function at the given `path` directly, allowing it to be inlined.
+    /// The bound function is called with the same arguments as the imported function, including
+    /// the calling instance if [`import_caller`] is enabled, and it is not included in the
+    /// generated import traits.
+    ///
+    /// Only imported functions can be bound, translation fails if the import with the given
+    /// `module` and `name` is a memory or a global, or if the module has no such import.
+    ///
+    /// [`import_caller`]: Translation::import_caller()
+    pub fn bind_import(&mut self, module: &'a str, name: &'a str, path: &'a str) -> &mut Self {
+        self.bound_imports.push((module, name, path));
+        self
+    }
+
+    /// Allows enabling or disabling the generation of methods to access exports by name. Defaults
+    /// to `false`.
+    ///
+    /// When enabled, the generated `Instance` has an `exports()` function listing each export's
+    /// name and kind, a `call_dynamic()` method to call an exported function with a slice of
+    /// `wasm2rs_rt::dynamic::Val`ues, and a `global_dynamic()` method to read an exported global.
+    /// The `call_dynamic()` method requires the `alloc` feature of `wasm2rs_rt`.
+    ///
+    /// See the documentation for `wasm2rs_rt::dynamic` for more information.
+    pub fn dynamic_api(&mut self, enabled: bool) -> &mut Self {
+        self.dynamic_api = enabled;
+        self
+    }
+
+    /// Allows enabling or disabling the generation of function references to exported functions.
+    /// Defaults to `false`.
+    ///
+    /// When enabled, the generated `Instance` has a `func_ref()` method that returns a
+    /// `wasm2rs_rt::func_ref::FuncRef` borrowing the instance, which calls the exported function
+    /// with the given name. Exported functions that can reach an [async import] or that have a
+    /// `funcref` parameter are not included. Translation fails if [exclusive access] is enabled.
+    ///
+    /// Regardless of this option, a `FuncRef` created by the host can be passed to a function
+    /// with a `funcref` parameter, and the module can pass it on to imported functions. Since
+    /// tables are not yet supported, host `FuncRef`s can't be called with `call_indirect`, and
+    /// translation fails if the module has a function with a `funcref` result or a global of
+    /// type `funcref`.
+    ///
+    /// [async import]: Translation::async_imports()
+    /// [exclusive access]: Translation::exclusive_access()
+    pub fn func_refs(&mut self, enabled: bool) -> &mut Self {
+        self.func_refs = enabled;
+        self
+    }
+
+    /// Allows enabling or disabling looking up imported functions and globals by name at run time.
+    /// Defaults to `false`.
+    ///
+    /// When enabled, the embedder's imports must be a `wasm2rs_rt::linker::Linker`, or implement
+    /// [`Borrow`] for one. Each imported function and global is looked up by its module and field
+    /// name when the module is instantiated, and instantiation fails with a link error if it is
+    /// missing or has the wrong signature or type.
+    ///
+    /// Only imported functions that are not [bound to a path] are looked up in the linker.
+    /// Translation fails if the module imports memories or mutable globals, or if
+    /// [async imports] or [passing the caller] are used. See the documentation for
+    /// `wasm2rs_rt::linker` for more information.
+    ///
+    /// [`Borrow`]: std::borrow::Borrow
+    /// [bound to a path]: Translation::bind_import()
+    /// [async imports]: Translation::async_imports()
+    /// [passing the caller]: Translation::import_caller()
+    pub fn runtime_linker(&mut self, enabled: bool) -> &mut Self {
+        self.runtime_linker = enabled;
+        self
+    }
+
+    /// Allows enabling or disabling instruction fuel metering. Defaults to `false`.
+    ///
+    /// When enabled, the generated code charges the cost of each basic block against a fuel
+    /// counter provided by the embedder at function entries, block entries, and loop headers,
+    /// [trapping] when the fuel runs out. This places a deterministic limit on the amount of work
+    /// that a call into the WebAssembly module can perform.
+    ///
+    /// The embedder state must store the `wasm2rs_rt::embedder::ExecutionLimits`. See the
+    /// documentation for `wasm2rs_rt::fuel` for more information.
+    ///
+    /// [trapping]: https://webassembly.github.io/spec/core/intro/overview.html#trap
+    pub fn fuel_metering(&mut self, enabled: bool) -> &mut Self {
+        self.fuel_metering = enabled;
+        self
+    }
+
+    /// Allows enabling or disabling epoch-based interruption. Defaults to `false`.
+    ///
+    /// When enabled, the generated code checks an epoch counter provided by the embedder at
+    /// function entries and loop headers, [trapping] once the embedder's deadline is reached. The
+    /// counter can be incremented from another thread, allowing long-running calls to be
+    /// interrupted by a watchdog. This is cheaper than [fuel metering], but not deterministic.
+    ///
+    /// The embedder state must store the `wasm2rs_rt::embedder::ExecutionLimits`. See the
+    /// documentation for `wasm2rs_rt::epoch` for more information.
+    ///
+    /// [trapping]: https://webassembly.github.io/spec/core/intro/overview.html#trap
+    /// [fuel metering]: Translation::fuel_metering()
+    pub fn epoch_interruption(&mut self, enabled: bool) -> &mut Self {
+        self.epoch_interruption = enabled;
+        self
+    }
+
+    /// Allows enabling or disabling the generation of code that requires exclusive access to the
+    /// instance. Defaults to `false`.
+    ///
+    /// When enabled, the generated functions, including exported functions, take `&mut self`
+    /// instead of `&self`. Loads and stores to memories defined by the module access their
+    /// contents directly as a slice, and mutable globals defined by the module are stored as plain
+    /// fields rather than in a `wasm2rs_rt::global::Global`. This avoids the overhead of interior
+    /// mutability, at the cost of no longer being able to call into the instance while it is
+    /// already running, such as from an imported function.
+    ///
+    /// The memories provided by the embedder must implement the `wasm2rs_rt::memory::Memory32Mut`
+    /// trait. Imported memories and globals are still accessed through shared references.
+    /// Translation fails if the [dynamic API] or [function references] are also enabled.
+    ///
+    /// [dynamic API]: Translation::dynamic_api()
+    /// [function references]: Translation::func_refs()
+    pub fn exclusive_access(&mut self, enabled: bool) -> &mut Self {
+        self.exclusive_access = enabled;
+        self
+    }
+
+    /// Allows enabling or disabling the generation of instances that can be shared between
+    /// threads. Defaults to `false`.
+    ///
+    /// When enabled, mutable globals are stored in a `wasm2rs_rt::global::AtomicGlobal` rather than
+    /// a `wasm2rs_rt::global::Global`, including the mutable globals provided by the embedder's
+    /// imports. The generated `Instance` is then [`Send`] and [`Sync`] as long as the embedder's
+    /// state and memories are, such as when the main memory is a
+    /// `wasm2rs_rt::memory::SyncHeapMemory32`.
+    ///
+    /// Since the `wasm2rs_rt::embedder::ExecutionLimits` used by [fuel metering],
+    /// [call depth checks], and [epoch interruption] are not [`Sync`], translation fails if any of
+    /// them are also enabled.
+    ///
+    /// [fuel metering]: Translation::fuel_metering()
+    /// [call depth checks]: Translation::emit_call_depth_checks()
+    /// [epoch interruption]: Translation::epoch_interruption()
+    pub fn thread_safe(&mut self, enabled: bool) -> &mut Self {
+        self.thread_safe = enabled;
+        self
+    }
+
+    /// Allows enabling or disabling the generation of methods to save and restore the state of an
+    /// instance. Defaults to `false`.
+    ///
+    /// When enabled, the generated `Instance` has a `snapshot()` method that serializes the
+    /// contents of the memories and mutable globals defined by the module, and a `restore()`
+    /// method that overwrites them with the contents of a snapshot. Snapshots contain a
+    /// fingerprint of the WebAssembly module, which is checked when they are restored. The
+    /// `snapshot()` method requires the `alloc` feature of `wasm2rs_rt`.
+    ///
+    /// Snapshots should only be restored into newly instantiated instances, since the instance may
+    /// be left partially restored if an error occurs. Translation fails if the module has tables,
+    /// since their contents can't be saved yet, or if it defines a mutable global with a reference
+    /// type. See the documentation for `wasm2rs_rt::snapshot` for more information.
+    pub fn snapshots(&mut self, enabled: bool) -> &mut Self {
+        self.snapshots = enabled;
+        self
+    }
+
+    /// Allows specifying the name of an exported function that is run at translation time to
+    /// initialize the module. Defaults to `None`.
+    ///
+    /// When specified, the module is instantiated by an interpreter, which runs the start function
+    /// followed by the exported function. The resulting contents of the memories and values of the
+    /// globals defined by the module are then emitted in place of the module's data segments and
+    /// global initializers, and the start function is not called by the generated `instantiate`
+    /// function. The exported function is still included in the generated `Instance`.
+    ///
+    /// Translation fails if the exported function does not exist, has parameters, or traps. Since
+    /// the embedder is not available at translation time, translation also fails if the
+    /// initialization calls an imported function or reads an imported global, or if the module
+    /// imports a memory or is dynamically linked.
+    pub fn pre_initialize(&mut self, export_name: Option<&'a str>) -> &mut Self {
+        self.pre_initialize = export_name;
+        self
+    }
+
     /// Allows specifying what debug information is included in the generated Rust code.
     ///
     /// Currently, debug information is only used in building stack traces for WebAssembly
@@ -216,6 +511,7 @@ impl<'a> Translation<'a> {
 }
 
 enum KnownSection<'a> {
+    Dylink(wasmparser::Dylink0SectionReader<'a>),
     Import(wasmparser::ImportSectionReader<'a>),
     Function,
     Memory(wasmparser::MemorySectionReader<'a>),
@@ -253,6 +549,30 @@ struct ModuleContents<'a> {
     start_function: Option<u32>,
 }
 
+/// Returns an error if any function has a `funcref` result or any global has a `funcref` type,
+/// since `funcref` values can currently only be passed into a module as parameters.
+fn check_func_ref_types(types: &wasmparser::types::Types) -> crate::Result<()> {
+    for index in 0..types.core_function_count() {
+        let signature =
+            function::get_function_type(types.get(types.core_function_at(index)).unwrap());
+
+        if signature.results().contains(&wasmparser::ValType::FUNCREF) {
+            anyhow::bail!(
+                "function #{index} has a funcref result, but returning funcref values is not yet \
+                supported"
+            );
+        }
+    }
+
+    for index in 0..types.global_count() {
+        if types.global_at(index).content_type == wasmparser::ValType::FUNCREF {
+            anyhow::bail!("global #{index} is a funcref, but funcref values are not yet supported");
+        }
+    }
+
+    Ok(())
+}
+
 fn parse_wasm_sections<'a>(
     wasm: &'a [u8],
     features: &wasmparser::WasmFeatures,
@@ -337,6 +657,12 @@ fn parse_wasm_sections<'a>(
                 validator: validator.code_section_entry(&body)?,
                 body,
             }),
+            Payload::CustomSection(section) if section.name() == "dylink.0" => {
+                sections.push(KnownSection::Dylink(wasmparser::Dylink0SectionReader::new(
+                    section.data(),
+                    section.data_offset(),
+                )));
+            }
             Payload::CustomSection(_section) => {
                 // Handling of custom `name`, 'producers' and DWARF sections is not yet implemented.
             }
@@ -346,6 +672,8 @@ fn parse_wasm_sections<'a>(
                 }
 
                 let types = validator.end(offset)?;
+                check_func_ref_types(&types)?;
+
                 return Ok(ModuleContents {
                     sections,
                     functions,
@@ -409,16 +737,48 @@ impl Translation<'_> {
         &self,
         wasm: &[u8],
         output: &mut dyn std::io::Write,
+    ) -> crate::Result<()> {
+        self.translate_with_macro_name(wasm, self.generated_macro_name, output)
+    }
+
+    /// Translates a WebAssembly module, using the given name for the generated Rust macro instead
+    /// of the [`generated_macro_name`].
+    ///
+    /// [`generated_macro_name`]: Translation::generated_macro_name()
+    pub(crate) fn translate_with_macro_name(
+        &self,
+        wasm: &[u8],
+        generated_macro_name: crate::rust::SafeIdent<'_>,
+        output: &mut dyn std::io::Write,
     ) -> crate::Result<()> {
         use anyhow::Context;
         use rayon::prelude::*;
 
+        if self.exclusive_access && self.dynamic_api {
+            anyhow::bail!("the dynamic API is not supported with exclusive access to the instance");
+        }
+
+        if self.exclusive_access && self.func_refs {
+            anyhow::bail!(
+                "function references are not supported with exclusive access to the instance"
+            );
+        }
+
+        if self.thread_safe
+            && (self.fuel_metering || self.emit_call_depth_checks || self.epoch_interruption)
+        {
+            anyhow::bail!(
+                "fuel metering, call depth checks, and epoch interruption are not supported with \
+                thread-safe instances"
+            );
+        }
+
         let ModuleContents {
             sections,
             functions,
             types,
             import_counts,
-            start_function,
+            mut start_function,
         } = parse_wasm_sections(wasm, self.wasm_features)?;
 
         let new_func_validator_allocation_pool;
@@ -439,13 +799,106 @@ impl Translation<'_> {
             }
         };
 
+        let is_dylink = sections
+            .iter()
+            .any(|section| matches!(section, KnownSection::Dylink(_)));
+
+        // Globals whose values are provided by the `Linking` passed to `instantiate`
+        let mut linked_globals = if is_dylink {
+            let mut linked_globals = Vec::new();
+            for section in sections.iter() {
+                if let KnownSection::Import(imports) = section {
+                    linked_globals = dylink::linked_globals(imports)?;
+                }
+            }
+
+            linked_globals
+        } else {
+            Vec::new()
+        };
+
+        let import_options = import::Options {
+            debug_info: self.debug_info,
+            async_imports: self.async_imports,
+            import_caller: self.import_caller,
+            bound_imports: &self.bound_imports,
+            dylink: is_dylink,
+            runtime_linker: self.runtime_linker,
+            thread_safe: self.thread_safe,
+        };
+
+        import::check_bound_imports(
+            sections.iter().find_map(|section| match section {
+                KnownSection::Import(imports) => Some(imports),
+                _ => None,
+            }),
+            &self.bound_imports,
+        )?;
+
+        // Imported functions and globals stored in the `Instance`
+        let mut linker_imports = import::LinkerImports::default();
+        if self.runtime_linker {
+            for section in sections.iter() {
+                if let KnownSection::Import(imports) = section {
+                    linker_imports = import::linker_imports(imports, &import_options)?;
+                }
+            }
+
+            linked_globals.extend_from_slice(&linker_imports.globals);
+            linked_globals.sort_unstable();
+        }
+
+        let const_globals = const_expr::Globals {
+            imported_count: import_counts.globals,
+            linked: &linked_globals,
+        };
+
+        let async_functions = if self.async_imports.is_empty() {
+            None
+        } else {
+            let mut async_imports = Vec::new();
+            for section in sections.iter() {
+                if let KnownSection::Import(imports) = section {
+                    for result in imports.clone() {
+                        let import = result?;
+                        if let wasmparser::TypeRef::Func(_) = import.ty {
+                            async_imports
+                                .push(self.async_imports.contains(&(import.module, import.name)));
+                        }
+                    }
+                }
+            }
+
+            let callees = functions
+                .par_iter()
+                .map(|func| call_graph::read_callees(&func.body))
+                .collect::<crate::Result<Vec<_>>>()?;
+
+            Some(call_graph::AsyncFunctions::new(&callees, &async_imports))
+        };
+
         // Generate Rust code for the functions
         let options = function::Options {
             emit_stack_overflow_checks: self.emit_stack_overflow_checks,
+            emit_call_depth_checks: self.emit_call_depth_checks,
+            fuel_metering: self.fuel_metering,
+            epoch_interruption: self.epoch_interruption,
+            exclusive_access: self.exclusive_access,
+            thread_safe: self.thread_safe,
             debug_info: self.debug_info,
+            async_functions: async_functions.as_ref(),
         };
 
-        let function_decls = functions
+        let imported_function_count =
+            types.core_function_count() - u32::try_from(functions.len()).unwrap_or(u32::MAX);
+
+        let function_bodies = if self.pre_initialize.is_some() {
+            functions.iter().map(|func| func.body.clone()).collect()
+        } else {
+            Vec::new()
+        };
+
+        let (function_decls, stack_usages): (Vec<_>, Vec<_>) = functions
             .into_par_iter()
             .map(|func| {
                 let mut out = crate::buffer::Writer::new(buffer_pool);
@@ -455,7 +908,7 @@ impl Translation<'_> {
 
                 let index = validator.index();
 
-                function::write_definition(
+                let stack_usage = function::write_definition(
                     &mut out,
                     &mut validator,
                     &func.body,
@@ -466,9 +919,40 @@ impl Translation<'_> {
                 .with_context(|| format!("failed to translate function #{index}"))?;
 
                 func_validator_allocation_pool.return_allocations(validator.into_allocations());
-                Ok(out.finish())
+                Ok((out.finish(), stack_usage))
             })
-            .collect::<crate::Result<Vec<_>>>()?;
+            .collect::<crate::Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
+
+        // Function bodies have been validated, so the module can now be run
+        let initialized = match self.pre_initialize {
+            Some(name) => {
+                let initialized = pre_init::run(
+                    &sections,
+                    &function_bodies,
+                    &types,
+                    &import_counts,
+                    start_function,
+                    name,
+                )
+                .context("pre-initialization failed")?;
+
+                // The start function was already run
+                start_function = None;
+                Some(initialized)
+            }
+            None => None,
+        };
+
+        let max_stack_bytes = if self.stack_usage_analysis {
+            Some(call_graph::max_stack_bytes(
+                &stack_usages,
+                imported_function_count,
+            ))
+        } else {
+            None
+        };
 
         // Generate globals, exports, memories, tables, and other things
         let mut item_lines = Vec::new();
@@ -483,25 +967,78 @@ impl Translation<'_> {
                 .into_par_iter()
                 .map(|section| match section {
                     KnownSection::Import(imports) => {
-                        import::write(buffer_pool, imports, &types, self.debug_info)
+                        import::write(buffer_pool, imports, &types, import_options)
                     }
+                    KnownSection::Dylink(dylink) => dylink::write(buffer_pool, dylink),
                     KnownSection::Function if self.debug_info.include_symbols() => {
                         Ok(function_types::write(buffer_pool, &types))
                     }
                     KnownSection::Function => Ok(Default::default()),
-                    KnownSection::Memory(memories) => {
-                        memory::write(buffer_pool, memories, import_counts.memories)
-                    }
-                    KnownSection::Global(globals) => {
-                        global::write(buffer_pool, globals, import_counts.globals)
-                    }
-                    KnownSection::Export(Some(exports)) => {
-                        export::write(buffer_pool, exports, &types, self.debug_info)
-                    }
-                    KnownSection::Export(None) => Ok(export::write_empty(buffer_pool, &types)),
-                    KnownSection::Data(data) => {
-                        data_segment::write(buffer_pool, data, self.data_segment_writer)
+                    KnownSection::Memory(memories) => memory::write(
+                        buffer_pool,
+                        memories,
+                        import_counts.memories,
+                        initialized.as_ref(),
+                    ),
+                    KnownSection::Global(globals) => global::write(
+                        buffer_pool,
+                        globals,
+                        import_counts.globals,
+                        &linked_globals,
+                        self.exclusive_access,
+                        self.thread_safe,
+                        initialized.as_ref(),
+                    ),
+                    KnownSection::Export(exports) => {
+                        let mut lines = match exports.clone() {
+                            Some(exports) => export::write(
+                                buffer_pool,
+                                exports,
+                                &types,
+                                &export::Options {
+                                    debug_info: self.debug_info,
+                                    max_stack_bytes: max_stack_bytes.as_deref(),
+                                    async_functions: async_functions.as_ref(),
+                                    exclusive_access: self.exclusive_access,
+                                    thread_safe: self.thread_safe,
+                                },
+                            )?,
+                            None => export::write_empty(buffer_pool, &types),
+                        };
+
+                        if self.dynamic_api {
+                            let mut dynamic = export::write_dynamic(
+                                buffer_pool,
+                                exports.clone(),
+                                &types,
+                                async_functions.as_ref(),
+                                self.thread_safe,
+                            )?;
+
+                            lines.impls.append(&mut dynamic.impls);
+                        }
+
+                        if self.func_refs {
+                            let mut func_refs = export::write_func_refs(
+                                buffer_pool,
+                                exports,
+                                &types,
+                                async_functions.as_ref(),
+                            )?;
+
+                            lines.impls.append(&mut func_refs.impls);
+                        }
+
+                        Ok(lines)
                     }
+                    KnownSection::Data(data) => data_segment::write(
+                        buffer_pool,
+                        data,
+                        self.data_segment_writer,
+                        &import_counts,
+                        const_globals,
+                        initialized.is_some(),
+                    ),
                 })
                 .collect::<Vec<crate::Result<_>>>();
 
@@ -518,6 +1055,27 @@ impl Translation<'_> {
             impl_line_groups.push(impl_lines);
         }
 
+        if let Some(initialized) = &initialized {
+            let mut lines =
+                pre_init::write_data(buffer_pool, initialized, self.data_segment_writer)?;
+
+            item_lines.append(&mut lines.items);
+            init_lines.append(&mut lines.inits);
+        }
+
+        if self.snapshots {
+            let lines = snapshot::write(
+                buffer_pool,
+                wasm,
+                &types,
+                &import_counts,
+                self.exclusive_access,
+                self.thread_safe,
+            )?;
+
+            impl_line_groups.push(lines.impls);
+        }
+
         let item_lines = item_lines;
         let field_lines = field_lines;
         let init_lines = init_lines;
@@ -527,7 +1085,7 @@ impl Translation<'_> {
         writeln!(
             output,
             "// automatically generated by wasm2rs\nmacro_rules! {} {{",
-            self.generated_macro_name
+            generated_macro_name
         )?;
 
         output.write_all(
@@ -535,6 +1093,12 @@ impl Translation<'_> {
                 "    ($vis:vis mod $module:ident use $(:: $embedder_start:ident ::)? $($embedder_more:ident)::+) => {\n",
                 // Names might be mangled
                 "#[allow(non_snake_case)]\n",
+                // Stack usage constants are named after exports
+                "#[allow(non_upper_case_globals)]\n",
+                // Import traits are named after the import module namespaces
+                "#[allow(non_camel_case_types)]\n",
+                // Import traits may contain async imports
+                "#[allow(async_fn_in_trait)]\n",
                 // Some functions may not be called
                 "#[allow(dead_code)]\n",
                 // Some branches may not be taken (e.g. infinite loops detected by `rustc`)
@@ -589,46 +1153,114 @@ impl Translation<'_> {
         // 4. Write data segments to the memories.
         //
         // [specification]: https://webassembly.github.io/spec/core/exec/modules.html#instantiation
-        output.write_all(
-            b"    $vis fn instantiate(embedder: embedder::State) -> embedder::Result<Self> {\n",
+        let is_async_start = start_function.is_some_and(|start_index| {
+            async_functions
+                .as_ref()
+                .is_some_and(|async_functions| async_functions.is_async(start_index))
+        });
+
+        // Dynamically linked modules are given the locations of their memory and table space
+        writeln!(
+            output,
+            "    $vis {}fn instantiate(embedder: embedder::State{}) -> ::core::result::Result<Self, embedder::rt::linker::InstantiationError> {{",
+            if is_async_start { "async " } else { "" },
+            if is_dylink {
+                ", #[allow(unused_variables)] linking: embedder::rt::dylink::Linking<'_>"
+            } else {
+                ""
+            }
         )?;
         crate::buffer::write_all_vectored(output, &init_lines, &mut io_buffers)?;
-        writeln!(output, "      let instantiated = Self {{")?;
+        writeln!(
+            output,
+            "      let {}instantiated = Self {{",
+            if self.exclusive_access && start_function.is_some() {
+                "mut "
+            } else {
+                ""
+            }
+        )?;
+
+        for i in linker_imports.functions {
+            writeln!(output, "        {},", display::FuncRefImportId(i))?;
+        }
 
         for i in import_counts.memories..types.memory_count() {
             writeln!(output, "        {},", display::MemId(i))?;
         }
 
-        for i in import_counts.globals..types.global_count() {
+        for i in linked_globals
+            .iter()
+            .copied()
+            .chain(import_counts.globals..types.global_count())
+        {
             writeln!(output, "        {},", display::GlobalId(i))?;
         }
 
         writeln!(output, "        embedder,\n      }};\n")?;
 
         if let Some(start_index) = start_function {
-            writeln!(
-                output,
-                "      instantiated.{}()?;",
-                display::FuncId(start_index)
-            )?;
+            let mut call = String::from("      ");
+            function::write_call_start(
+                &mut call,
+                "instantiated",
+                start_index,
+                async_functions.as_ref(),
+            );
+            function::write_call_end(&mut call, start_index, async_functions.as_ref());
+            call.push_str("?;\n");
+            output.write_all(call.as_bytes())?;
         } else {
             output.write_all(b"      // No start function\n")?;
         }
 
-        output.write_all(b"\n      Ok(instantiated)\n    }\n  }\n}\n")?; // impl Instance
+        output.write_all(b"\n      Ok(instantiated)\n    }\n  }\n")?; // impl Instance
+
+        // Write the implementation used to give imported functions access to the instance
+        if self.import_caller {
+            output.write_all(
+                concat!(
+                    "\n  impl embedder::rt::embedder::Caller for Instance {\n",
+                    "    type State = embedder::State;\n\n",
+                    "    fn embedder(&self) -> &embedder::State { &self.embedder }\n\n",
+                    "    fn memory(&self, index: u32) -> ::core::option::Option<&dyn embedder::rt::memory::Memory32> {\n",
+                    "      match index {\n",
+                )
+                .as_bytes(),
+            )?;
+
+            for i in 0..types.memory_count() {
+                let (reference, call) = if import_counts.is_memory_import(i) {
+                    ("", "()")
+                } else {
+                    ("&", "")
+                };
+
+                writeln!(
+                    output,
+                    "        {i} => ::core::option::Option::Some({reference}self.{}{call}),",
+                    display::MemId(i)
+                )?;
+            }
+
+            output
+                .write_all(b"        _ => ::core::option::Option::None,\n      }\n    }\n  }\n")?;
+        }
+
+        output.write_all(b"}\n")?;
 
         // Other macro cases
         output.write_all(b"    };\n    ($vis:vis mod $module:ident) => {\n")?;
         writeln!(
             output,
             "        {}!{{$vis mod $module use ::wasm2rs_rt::embedder}}\n    }};",
-            self.generated_macro_name
+            generated_macro_name
         )?;
 
         writeln!(
             output,
             "    (use $(:: $embedder_start:ident ::)? $($embedder_more:ident)::+) => {{ {}!{{mod wasm use $embedder}} }};\n}}",
-            self.generated_macro_name
+            generated_macro_name
         )?;
 
         output.flush()?;
@@ -654,7 +1286,21 @@ impl std::fmt::Debug for Translation<'_> {
                 "emit_stack_overflow_checks",
                 &self.emit_stack_overflow_checks,
             )
+            .field("emit_call_depth_checks", &self.emit_call_depth_checks)
+            .field("stack_usage_analysis", &self.stack_usage_analysis)
+            .field("import_caller", &self.import_caller)
+            .field("bound_imports", &self.bound_imports)
+            .field("dynamic_api", &self.dynamic_api)
+            .field("func_refs", &self.func_refs)
+            .field("runtime_linker", &self.runtime_linker)
+            .field("fuel_metering", &self.fuel_metering)
+            .field("epoch_interruption", &self.epoch_interruption)
+            .field("exclusive_access", &self.exclusive_access)
+            .field("thread_safe", &self.thread_safe)
+            .field("snapshots", &self.snapshots)
+            .field("pre_initialize", &self.pre_initialize)
             .field("debug_info", &self.debug_info)
+            .field("async_imports", &self.async_imports)
             .finish_non_exhaustive()
     }
 }
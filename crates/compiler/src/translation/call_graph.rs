@@ -0,0 +1,272 @@
+//! Static analysis of the module's call graph, used to estimate worst-case stack usage and to
+//! determine which functions are translated as `async fn`s.
+
+use crate::translation::function::StackUsage;
+
+#[derive(Clone, Copy)]
+enum Visit {
+    NotVisited,
+    InProgress,
+    Done(Option<u32>),
+}
+
+/// Estimates the worst-case number of bytes of stack space used by each defined function,
+/// including the space used by all of its callees.
+///
+/// The result is the largest sum of estimated [`frame_size`]s along any path in the call graph. It
+/// is only an upper bound if every frame size is, which is not guaranteed since the layout of the
+/// generated Rust functions' stack frames is decided by the Rust compiler.
+///
+/// A function has no estimate (`None`) if it can reach a cycle in the call graph (recursion), an
+/// indirect call, or an imported function, since the stack usage of host code is unknown.
+///
+/// The returned estimates are indexed by function index, with imported functions having no
+/// estimate.
+///
+/// [`frame_size`]: StackUsage::frame_size
+pub(in crate::translation) fn max_stack_bytes(
+    functions: &[StackUsage],
+    imported_function_count: u32,
+) -> Vec<Option<u32>> {
+    struct Frame {
+        index: usize,
+        next_callee: usize,
+        max_callee: Option<u32>,
+    }
+
+    let mut visits = vec![Visit::NotVisited; functions.len()];
+    let mut stack = Vec::<Frame>::new();
+
+    for root in 0..functions.len() {
+        if !matches!(visits[root], Visit::NotVisited) {
+            continue;
+        }
+
+        visits[root] = Visit::InProgress;
+        stack.push(Frame {
+            index: root,
+            next_callee: 0,
+            max_callee: Some(0),
+        });
+
+        while let Some(frame) = stack.last_mut() {
+            let usage = &functions[frame.index];
+
+            if let Some(&callee) = usage.callees.get(frame.next_callee) {
+                frame.next_callee += 1;
+
+                let Some(callee) = callee.checked_sub(imported_function_count) else {
+                    frame.max_callee = None;
+                    continue;
+                };
+
+                let callee = callee as usize;
+                let callee_bound = match visits[callee] {
+                    Visit::NotVisited => {
+                        visits[callee] = Visit::InProgress;
+                        stack.push(Frame {
+                            index: callee,
+                            next_callee: 0,
+                            max_callee: Some(0),
+                        });
+                        continue;
+                    }
+                    Visit::InProgress => None,
+                    Visit::Done(bound) => bound,
+                };
+
+                frame.max_callee = frame.max_callee.zip(callee_bound).map(|(a, b)| a.max(b));
+                continue;
+            }
+
+            let bound = if usage.calls_indirect {
+                None
+            } else {
+                frame
+                    .max_callee
+                    .map(|callee| usage.frame_size.saturating_add(callee))
+            };
+
+            visits[frame.index] = Visit::Done(bound);
+            stack.pop();
+
+            if let Some(caller) = stack.last_mut() {
+                caller.max_callee = caller.max_callee.zip(bound).map(|(a, b)| a.max(b));
+            }
+        }
+    }
+
+    let mut bounds = vec![None; imported_function_count as usize];
+    bounds.extend(visits.into_iter().map(|visit| match visit {
+        Visit::Done(bound) => bound,
+        Visit::NotVisited | Visit::InProgress => unreachable!(),
+    }));
+    bounds
+}
+
+/// Reads the indices of the functions that are directly called by a function body.
+pub(in crate::translation) fn read_callees(
+    body: &wasmparser::FunctionBody,
+) -> crate::Result<Vec<u32>> {
+    let mut callees = Vec::new();
+    let mut operators = body.get_operators_reader()?;
+    while !operators.eof() {
+        if let wasmparser::Operator::Call { function_index } = operators.read()? {
+            callees.push(function_index);
+        }
+    }
+
+    callees.sort_unstable();
+    callees.dedup();
+    Ok(callees)
+}
+
+/// Describes which functions are translated as `async fn`s.
+pub(in crate::translation) struct AsyncFunctions {
+    is_async: Vec<bool>,
+    is_recursive: Vec<bool>,
+}
+
+impl AsyncFunctions {
+    /// Determines which functions can reach an async import.
+    ///
+    /// The `callees` are those of each defined function, while `async_imports` indicates which
+    /// imported functions are async.
+    pub(in crate::translation) fn new(callees: &[Vec<u32>], async_imports: &[bool]) -> Self {
+        let imported_function_count = async_imports.len();
+        let function_count = imported_function_count + callees.len();
+
+        let mut callers = vec![Vec::new(); function_count];
+        for (caller, callees) in callees.iter().enumerate() {
+            for &callee in callees {
+                callers[callee as usize].push(caller + imported_function_count);
+            }
+        }
+
+        let mut is_async = async_imports.to_vec();
+        is_async.resize(function_count, false);
+
+        let mut worklist = (0..imported_function_count)
+            .filter(|&import| async_imports[import])
+            .collect::<Vec<_>>();
+
+        while let Some(callee) = worklist.pop() {
+            for &caller in callers[callee].iter() {
+                if !is_async[caller] {
+                    is_async[caller] = true;
+                    worklist.push(caller);
+                }
+            }
+        }
+
+        Self {
+            is_recursive: recursive_functions(callees, imported_function_count),
+            is_async,
+        }
+    }
+
+    /// Returns `true` if the function is an `async fn`, or is an async import.
+    pub(in crate::translation) fn is_async(&self, function_index: u32) -> bool {
+        self.is_async[function_index as usize]
+    }
+
+    /// Returns `true` if calls to the function must box the resulting future, since the function
+    /// is an `async fn` that is part of a cycle in the call graph.
+    pub(in crate::translation) fn is_boxed(&self, function_index: u32) -> bool {
+        self.is_async(function_index) && self.is_recursive[function_index as usize]
+    }
+}
+
+/// Finds the defined functions that are part of a cycle in the call graph, using Tarjan's strongly
+/// connected components algorithm.
+///
+/// The returned flags are indexed by function index.
+fn recursive_functions(callees: &[Vec<u32>], imported_function_count: usize) -> Vec<bool> {
+    struct Frame {
+        function: usize,
+        next_callee: usize,
+    }
+
+    let mut is_recursive = vec![false; imported_function_count + callees.len()];
+    let mut order = vec![None::<usize>; callees.len()];
+    let mut low_link = vec![0usize; callees.len()];
+    let mut on_stack = vec![false; callees.len()];
+    let mut component = Vec::new();
+    let mut stack = Vec::<Frame>::new();
+    let mut next_order = 0;
+
+    for root in 0..callees.len() {
+        if order[root].is_some() {
+            continue;
+        }
+
+        order[root] = Some(next_order);
+        low_link[root] = next_order;
+        next_order += 1;
+        on_stack[root] = true;
+        component.push(root);
+        stack.push(Frame {
+            function: root,
+            next_callee: 0,
+        });
+
+        while let Some(frame) = stack.last_mut() {
+            let function = frame.function;
+
+            if let Some(&callee) = callees[function].get(frame.next_callee) {
+                frame.next_callee += 1;
+
+                let Some(callee) = (callee as usize).checked_sub(imported_function_count) else {
+                    continue;
+                };
+
+                if callee == function {
+                    is_recursive[function + imported_function_count] = true;
+                }
+
+                match order[callee] {
+                    None => {
+                        order[callee] = Some(next_order);
+                        low_link[callee] = next_order;
+                        next_order += 1;
+                        on_stack[callee] = true;
+                        component.push(callee);
+                        stack.push(Frame {
+                            function: callee,
+                            next_callee: 0,
+                        });
+                    }
+                    Some(callee_order) if on_stack[callee] => {
+                        low_link[function] = low_link[function].min(callee_order);
+                    }
+                    Some(_) => (),
+                }
+
+                continue;
+            }
+
+            stack.pop();
+
+            if let Some(caller) = stack.last() {
+                low_link[caller.function] = low_link[caller.function].min(low_link[function]);
+            }
+
+            if Some(low_link[function]) == order[function] {
+                let start = component
+                    .iter()
+                    .rposition(|&member| member == function)
+                    .unwrap();
+
+                let is_cycle = component.len() - start > 1;
+                for member in component.drain(start..) {
+                    on_stack[member] = false;
+                    if is_cycle {
+                        is_recursive[member + imported_function_count] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    is_recursive
+}
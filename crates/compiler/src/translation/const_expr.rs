@@ -1,9 +1,25 @@
 use std::fmt::Write;
 
+/// Describes which globals are available as local variables during instantiation.
+#[derive(Clone, Copy)]
+pub(in crate::translation) struct Globals<'a> {
+    pub imported_count: u32,
+    /// The imported globals whose values are provided when the module is instantiated, either by
+    /// the `Linking` of a dynamically linked module or by the runtime linker, in ascending order.
+    pub linked: &'a [u32],
+}
+
+impl Globals<'_> {
+    fn is_initialized(&self, index: u32) -> bool {
+        index >= self.imported_count || self.linked.binary_search(&index).is_ok()
+    }
+}
+
 /// Generates a Rust expression from a constant WebAssembly expression.
 pub(in crate::translation) fn write(
     out: &mut crate::buffer::Writer,
     expr: &wasmparser::ConstExpr,
+    globals: Globals<'_>,
 ) -> crate::Result<()> {
     use wasmparser::Operator;
 
@@ -14,10 +30,18 @@ pub(in crate::translation) fn write(
     loop {
         match ops.read()? {
             Operator::Nop => (),
-            // Operator::GlobalGet { global_index } => {
-            //     let _ = writeln!(out, "let s_{stack_height} = self.{}.;", crate::translation::display::GlobalId(global_index));
-            //     stack_height += 1;
-            // },
+            Operator::GlobalGet { global_index } => {
+                if !globals.is_initialized(global_index) {
+                    anyhow::bail!("reading imported global #{global_index} in a constant expression is not yet supported");
+                }
+
+                let _ = write!(
+                    out,
+                    "let s_{stack_height} = {}; ",
+                    crate::translation::display::GlobalId(global_index)
+                );
+                stack_height += 1;
+            }
             Operator::I32Const { value } => {
                 let _ = write!(out, "let s_{stack_height} = {value}i32; ");
                 stack_height += 1;
@@ -26,10 +50,35 @@ pub(in crate::translation) fn write(
                 let _ = write!(out, "let s_{stack_height} = {value}i64; ");
                 stack_height += 1;
             }
+            Operator::F32Const { value } => {
+                let _ = write!(
+                    out,
+                    "let s_{stack_height} = f32::from_bits({:#010X}u32); ",
+                    value.bits()
+                );
+                stack_height += 1;
+            }
+            Operator::F64Const { value } => {
+                let _ = write!(
+                    out,
+                    "let s_{stack_height} = f64::from_bits({:#018X}u64); ",
+                    value.bits()
+                );
+                stack_height += 1;
+            }
+            Operator::RefNull {
+                hty: wasmparser::HeapType::Extern,
+            } => {
+                let _ = write!(
+                    out,
+                    "let s_{stack_height} = embedder::rt::extern_ref::ExternRef::NULL; "
+                );
+                stack_height += 1;
+            }
             Operator::I32Add => {
                 let c_2 = stack_height - 1;
                 let c_1 = stack_height - 2;
-                let _ = write!(out, "let s_{c_1} = i32::wrapping_add({c_1}, {c_2}); ",);
+                let _ = write!(out, "let s_{c_1} = i32::wrapping_add(s_{c_1}, s_{c_2}); ",);
                 stack_height -= 1;
             }
             Operator::End => {
@@ -11,10 +11,35 @@ fn write_data_literal(out: &mut crate::buffer::Writer, data: &[u8]) {
     out.write_str("\"");
 }
 
+/// Writes the constant containing the contents of a data segment.
+pub(in crate::translation) fn write_item(
+    out: &mut crate::buffer::Writer,
+    index: u32,
+    data: &[u8],
+    writer: crate::DataSegmentWriter,
+) -> crate::Result<()> {
+    let id = crate::translation::display::DataId(index);
+    let _ = write!(out, "  const {id}: &[u8] = ");
+
+    if data.len() <= PREFER_LITERAL_LENGTH {
+        write_data_literal(out, data);
+    } else if let Some(path) = writer(index, data)? {
+        let _ = write!(out, "::core::include_bytes!({});", path.escape_default());
+    } else {
+        write_data_literal(out, data);
+    }
+
+    out.write_str(";\n");
+    Ok(())
+}
+
 pub fn write(
     buffer_pool: &crate::buffer::Pool,
     section: wasmparser::DataSectionReader,
     writer: crate::DataSegmentWriter,
+    import_counts: &crate::translation::ImportCounts,
+    globals: crate::translation::const_expr::Globals,
+    pre_initialized: bool,
 ) -> crate::Result<crate::translation::GeneratedLines> {
     let mut item_out = crate::buffer::Writer::new(buffer_pool);
     let mut init_out = crate::buffer::Writer::new(buffer_pool);
@@ -25,36 +50,31 @@ pub fn write(
         let data = result?;
 
         let id = crate::translation::display::DataId(index);
-        let _ = write!(item_out, "  const {id}: &[u8] = ");
-
-        if data.data.len() <= PREFER_LITERAL_LENGTH {
-            write_data_literal(&mut item_out, data.data);
-        } else if let Some(path) = writer(index, data.data)? {
-            let _ = write!(
-                item_out,
-                "::core::include_bytes!({});",
-                path.escape_default()
-            );
-        } else {
-            write_data_literal(&mut item_out, data.data);
-        }
-
-        item_out.write_str(";\n");
+        write_item(&mut item_out, index, data.data, writer)?;
 
         match data.kind {
+            // Pre-initialized memories already contain the contents of active data segments
+            DataKind::Active { .. } if pre_initialized => (),
             DataKind::Active {
                 memory_index,
                 offset_expr,
             } => {
+                // Imported memories are already references
                 let _ = write!(
                     init_out,
-                    "      embedder::rt::memory::init::<{memory_index}, _, _>(&{}, {id}, ",
+                    "      embedder::rt::memory::init::<{memory_index}, _, _>({}{}, {id}, ",
+                    if import_counts.is_memory_import(memory_index) {
+                        ""
+                    } else {
+                        "&"
+                    },
                     crate::translation::display::MemId(memory_index)
                 );
 
-                crate::translation::const_expr::write(&mut init_out, &offset_expr).with_context(
-                    || format!("could not translate offset for data segment #{index}"),
-                )?;
+                crate::translation::const_expr::write(&mut init_out, &offset_expr, globals)
+                    .with_context(|| {
+                        format!("could not translate offset for data segment #{index}")
+                    })?;
 
                 let _ = writeln!(init_out, ", 0, {}i32, &embedder)?;", data.data.len());
             }
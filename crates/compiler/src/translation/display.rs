@@ -16,6 +16,10 @@ impl std::fmt::Display for ValType {
             wasmparser::ValType::I64 => f.write_str("i64"),
             wasmparser::ValType::F32 => f.write_str("f32"),
             wasmparser::ValType::F64 => f.write_str("f64"),
+            wasmparser::ValType::EXTERNREF => f.write_str("embedder::rt::extern_ref::ExternRef"),
+            wasmparser::ValType::FUNCREF => {
+                f.write_str("embedder::rt::func_ref::FuncRef<'_, embedder::rt::trap::TrapValue>")
+            }
             other => todo!("how to write {other}?"),
         }
     }
@@ -41,6 +45,26 @@ impl std::fmt::Display for FuncId {
     }
 }
 
+#[derive(Clone, Copy, Debug)]
+#[repr(transparent)]
+pub(in crate::translation) struct FuncRefImportId(pub(in crate::translation) u32);
+
+impl std::fmt::Display for FuncRefImportId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "_fref_{}", self.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+#[repr(transparent)]
+pub(in crate::translation) struct FuncRefVTable(pub(in crate::translation) u32);
+
+impl std::fmt::Display for FuncRefVTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "_F{}_FUNC_REF", self.0)
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 #[repr(transparent)]
 pub(in crate::translation) struct FuncSymbol(pub(in crate::translation) u32);
@@ -100,3 +124,19 @@ impl std::fmt::Display for DataId {
         write!(f, "_DATA_{}", self.0)
     }
 }
+
+#[derive(Clone, Copy, Debug)]
+#[repr(transparent)]
+pub(in crate::translation) struct MaxStackBytes<'a>(pub(in crate::translation) &'a str);
+
+impl std::fmt::Display for MaxStackBytes<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The `r#` prefix of escaped identifiers is not needed after the `MAX_STACK_BYTES_` prefix
+        let ident = crate::rust::SafeIdent::from(self.0).to_string();
+        write!(
+            f,
+            "MAX_STACK_BYTES_{}",
+            ident.strip_prefix("r#").unwrap_or(&ident)
+        )
+    }
+}
@@ -0,0 +1,129 @@
+//! Support for [dynamically linked] modules, which contain a `dylink.0` custom section.
+//!
+//! [dynamically linked]: https://github.com/WebAssembly/tool-conventions/blob/main/DynamicLinking.md
+
+use std::fmt::Write;
+
+/// An imported global whose value is provided by the `wasm2rs_rt::dylink::Linking` passed to
+/// the generated `instantiate` function, rather than by the embedder's imports.
+#[derive(Clone, Copy)]
+pub(in crate::translation) enum LinkedGlobal<'a> {
+    MemoryBase,
+    TableBase,
+    GotMem(&'a str),
+    GotFunc(&'a str),
+}
+
+impl<'a> LinkedGlobal<'a> {
+    pub(in crate::translation) fn from_import(module: &str, name: &'a str) -> Option<Self> {
+        match (module, name) {
+            ("env", "__memory_base") => Some(Self::MemoryBase),
+            ("env", "__table_base") => Some(Self::TableBase),
+            ("GOT.mem", _) => Some(Self::GotMem(name)),
+            ("GOT.func", _) => Some(Self::GotFunc(name)),
+            _ => None,
+        }
+    }
+
+    /// Writes an expression that evaluates to the value of the global during instantiation.
+    pub(in crate::translation) fn write_value(&self, out: &mut crate::buffer::Writer) {
+        match self {
+            Self::MemoryBase => out.write_str("linking.memory_base as i32"),
+            Self::TableBase => out.write_str("linking.table_base as i32"),
+            Self::GotMem(name) => {
+                let _ = write!(
+                    out,
+                    "embedder::rt::dylink::resolve_memory_address(&linking, {name:?})?"
+                );
+            }
+            Self::GotFunc(name) => {
+                let _ = write!(
+                    out,
+                    "embedder::rt::dylink::resolve_table_index(&linking, {name:?})?"
+                );
+            }
+        }
+    }
+}
+
+/// Gets the indices of the imported globals that are [`LinkedGlobal`]s, in ascending order.
+pub(in crate::translation) fn linked_globals(
+    imports: &wasmparser::ImportSectionReader,
+) -> crate::Result<Vec<u32>> {
+    let mut linked = Vec::new();
+    let mut global_index = 0u32;
+    for result in imports.clone() {
+        let import = result?;
+        if let wasmparser::TypeRef::Global(_) = import.ty {
+            if LinkedGlobal::from_import(import.module, import.name).is_some() {
+                linked.push(global_index);
+            }
+
+            global_index += 1;
+        }
+    }
+
+    Ok(linked)
+}
+
+/// Writes constants describing the memory and table space required by the module, and the
+/// names of the other dynamically linked modules that it depends on.
+pub(in crate::translation) fn write(
+    buffer_pool: &crate::buffer::Pool,
+    section: wasmparser::Dylink0SectionReader,
+) -> crate::Result<crate::translation::GeneratedLines> {
+    use wasmparser::Dylink0Subsection;
+
+    let mut mem_info = None;
+    let mut needed = Vec::new();
+    for result in section {
+        match result? {
+            Dylink0Subsection::MemInfo(info) => mem_info = Some(info),
+            Dylink0Subsection::Needed(names) => needed.extend(names),
+            // Symbol flags are only used by loaders that look up symbols by name
+            Dylink0Subsection::ExportInfo(_)
+            | Dylink0Subsection::ImportInfo(_)
+            | Dylink0Subsection::Unknown { .. } => (),
+        }
+    }
+
+    let mut out = crate::buffer::Writer::new(buffer_pool);
+    let mem_info = mem_info.unwrap_or(wasmparser::MemInfo {
+        memory_size: 0,
+        memory_alignment: 0,
+        table_size: 0,
+        table_alignment: 0,
+    });
+
+    let _ = writeln!(
+        out,
+        "\n  /// The memory and table space required by this dynamically linked module.\n  \
+        $vis const DYLINK_MEM_INFO: embedder::rt::dylink::MemInfo = \
+        embedder::rt::dylink::MemInfo {{ memory_size: {}, memory_alignment: {}, table_size: {}, \
+        table_alignment: {} }};",
+        mem_info.memory_size,
+        mem_info.memory_alignment,
+        mem_info.table_size,
+        mem_info.table_alignment
+    );
+
+    out.write_str(
+        "\n  /// The names of the dynamically linked modules that this module depends on.\n  \
+        $vis const DYLINK_NEEDED: &[&str] = &[",
+    );
+
+    for (i, name) in needed.into_iter().enumerate() {
+        if i > 0 {
+            out.write_str(", ");
+        }
+
+        let _ = write!(out, "{name:?}");
+    }
+
+    out.write_str("];\n");
+
+    Ok(crate::translation::GeneratedLines {
+        items: out.finish(),
+        ..Default::default()
+    })
+}
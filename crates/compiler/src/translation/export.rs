@@ -16,12 +16,15 @@ fn write_function_export(
     out: &mut crate::buffer::Writer,
     index: crate::translation::display::FuncId,
     types: &wasmparser::types::Types,
+    async_functions: Option<&crate::translation::call_graph::AsyncFunctions>,
+    exclusive_access: bool,
 ) {
     let func_type = crate::translation::function::get_function_type(
         types.get(types.core_function_at(index.0)).unwrap(),
     );
-    crate::translation::function::write_definition_signature(out, func_type);
-    let _ = write!(out, " {{ self.{index}(");
+    crate::translation::function::write_definition_signature(out, func_type, exclusive_access);
+    out.write_str(" { ");
+    crate::translation::function::write_call_start(out, "self", index.0, async_functions);
 
     let param_count = u32::try_from(func_type.params().len()).unwrap();
     for i in 0..param_count {
@@ -32,7 +35,8 @@ fn write_function_export(
         let _ = write!(out, "{}", crate::translation::display::LocalId(i));
     }
 
-    out.write_str(") }\n");
+    crate::translation::function::write_call_end(out, index.0, async_functions);
+    out.write_str(" }\n");
 }
 
 pub(in crate::translation) fn write_empty(
@@ -55,12 +59,29 @@ pub(in crate::translation) fn write_empty(
     }
 }
 
+#[derive(Clone, Copy)]
+pub(in crate::translation) struct Options<'a> {
+    pub debug_info: crate::DebugInfo,
+    pub max_stack_bytes: Option<&'a [Option<u32>]>,
+    pub async_functions: Option<&'a crate::translation::call_graph::AsyncFunctions>,
+    pub exclusive_access: bool,
+    pub thread_safe: bool,
+}
+
 pub(in crate::translation) fn write<'a>(
     buffer_pool: &crate::buffer::Pool,
     section: wasmparser::ExportSectionReader<'a>,
     types: &wasmparser::types::Types,
-    debug_level: crate::DebugInfo,
+    options: &Options,
 ) -> crate::Result<crate::translation::GeneratedLines> {
+    let Options {
+        debug_info: debug_level,
+        max_stack_bytes,
+        async_functions,
+        exclusive_access,
+        thread_safe,
+    } = *options;
+
     let mut impl_out = crate::buffer::Writer::new(buffer_pool);
 
     impl_out.write_str("    // Exports\n");
@@ -73,9 +94,14 @@ pub(in crate::translation) fn write<'a>(
         use wasmparser::ExternalKind;
 
         let export = result?;
+        let is_async = export.kind == ExternalKind::Func
+            && async_functions
+                .is_some_and(|async_functions| async_functions.is_async(export.index));
+
         let _ = write!(
             impl_out,
-            "    $vis fn {}",
+            "    $vis {}fn {}",
+            if is_async { "async " } else { "" },
             crate::rust::SafeIdent::from(export.name),
         );
 
@@ -85,8 +111,25 @@ pub(in crate::translation) fn write<'a>(
                     &mut impl_out,
                     crate::translation::display::FuncId(export.index),
                     types,
+                    async_functions,
+                    exclusive_access,
                 );
 
+                if let Some(max_stack_bytes) = max_stack_bytes {
+                    let _ = write!(
+                        impl_out,
+                        "    $vis const {}: ::core::option::Option<usize> = ",
+                        crate::translation::display::MaxStackBytes(export.name),
+                    );
+
+                    match max_stack_bytes[export.index as usize] {
+                        Some(bound) => {
+                            let _ = writeln!(impl_out, "::core::option::Option::Some({bound});");
+                        }
+                        None => impl_out.write_str("::core::option::Option::None;\n"),
+                    }
+                }
+
                 if debug_level.include_symbols() {
                     func_export_symbols
                         .entry(export.index)
@@ -108,15 +151,29 @@ pub(in crate::translation) fn write<'a>(
                 let value_type = crate::translation::display::ValType(global_type.content_type);
 
                 let _ = write!(impl_out, "(&self) -> &");
-                if global_type.mutable {
-                    let _ = write!(impl_out, "embedder::rt::global::Global<{value_type}>");
+                if global_type.mutable && !exclusive_access {
+                    let _ = write!(
+                        impl_out,
+                        "{}<{value_type}>",
+                        crate::translation::global::cell_type(thread_safe)
+                    );
                 } else {
                     let _ = write!(impl_out, "{value_type}");
                 }
 
                 let _ = writeln!(impl_out, "  {{ &self.{index} }}");
             }
-            _ => todo!("unsupported export: {export:?}"),
+            ExternalKind::Table | ExternalKind::Tag => {
+                anyhow::bail!(
+                    "cannot export {:?}, exporting a {} is not yet supported",
+                    export.name,
+                    if export.kind == ExternalKind::Table {
+                        "table"
+                    } else {
+                        "tag"
+                    }
+                );
+            }
         }
     }
 
@@ -156,3 +213,414 @@ pub(in crate::translation) fn write<'a>(
         ..Default::default()
     })
 }
+
+const DYNAMIC: &str = "embedder::rt::dynamic";
+
+/// Gets the name of the `Val` case used to pass values of the given type to and from
+/// `call_dynamic()`.
+fn dynamic_val_case(ty: wasmparser::ValType) -> Option<&'static str> {
+    match ty {
+        wasmparser::ValType::I32 => Some("I32"),
+        wasmparser::ValType::I64 => Some("I64"),
+        wasmparser::ValType::F32 => Some("F32"),
+        wasmparser::ValType::F64 => Some("F64"),
+        wasmparser::ValType::EXTERNREF => Some("ExternRef"),
+        // Functions with these types can't be translated yet
+        wasmparser::ValType::V128 | wasmparser::ValType::FUNCREF | wasmparser::ValType::Ref(_) => {
+            None
+        }
+    }
+}
+
+/// Returns an error if a value of the given type can't be passed to or returned from
+/// `call_dynamic()`.
+fn check_dynamic_val_type(
+    ty: wasmparser::ValType,
+    export: &wasmparser::Export,
+) -> crate::Result<()> {
+    match dynamic_val_case(ty) {
+        Some(_) => Ok(()),
+        None => anyhow::bail!(
+            "cannot generate dynamic API for export {:?}, {ty} values are not yet supported",
+            export.name
+        ),
+    }
+}
+
+fn write_dynamic_val_pattern(out: &mut crate::buffer::Writer, ty: wasmparser::ValType, id: &str) {
+    let case = dynamic_val_case(ty).expect("types were checked");
+    let _ = write!(out, "{DYNAMIC}::Val::{case}({id})");
+}
+
+/// Writes the `func_ref()` method, along with the trampolines used to call exported functions
+/// through a `FuncRef`.
+///
+/// The `RawFuncRefData` of these function references is a pointer to the `Instance`, so the
+/// returned `FuncRef` borrows it.
+pub(in crate::translation) fn write_func_refs(
+    buffer_pool: &crate::buffer::Pool,
+    section: Option<wasmparser::ExportSectionReader>,
+    types: &wasmparser::types::Types,
+    async_functions: Option<&crate::translation::call_graph::AsyncFunctions>,
+) -> crate::Result<crate::translation::GeneratedLines> {
+    use crate::translation::display::{FuncId, FuncRefVTable, LocalId, ValType};
+
+    let exports = match section {
+        Some(section) => section
+            .into_iter()
+            .collect::<wasmparser::Result<Vec<_>>>()?,
+        None => Vec::new(),
+    };
+
+    let mut impl_out = crate::buffer::Writer::new(buffer_pool);
+
+    const FUNC_REF: &str = "embedder::rt::func_ref";
+
+    // Async functions can't be called through a `FuncRef`, and neither can functions with
+    // `funcref` parameters, since the `call_` helpers of `FuncRef` require `'static` arguments
+    let functions = exports.iter().filter(|export| {
+        export.kind == wasmparser::ExternalKind::Func
+            && !async_functions
+                .is_some_and(|async_functions| async_functions.is_async(export.index))
+            && !crate::translation::function::get_function_type(
+                types.get(types.core_function_at(export.index)).unwrap(),
+            )
+            .params()
+            .contains(&wasmparser::ValType::FUNCREF)
+    });
+
+    let mut written = std::collections::HashSet::new();
+    for export in functions.clone() {
+        if !written.insert(export.index) {
+            continue;
+        }
+
+        let signature = crate::translation::function::get_function_type(
+            types.get(types.core_function_at(export.index)).unwrap(),
+        );
+
+        // The function pointer type used to check the signature when the `FuncRef` is called
+        let write_func_ptr = |out: &mut crate::buffer::Writer| {
+            let _ = write!(out, "unsafe fn(&{FUNC_REF}::RawFuncRefData");
+            for ty in signature.params() {
+                let _ = write!(out, ", {}", ValType(*ty));
+            }
+
+            out.write_str(") -> ");
+            crate::translation::function::write_result_type(out, signature);
+        };
+
+        let vtable = FuncRefVTable(export.index);
+        let _ = write!(
+            impl_out,
+            "    const {vtable}: {FUNC_REF}::RawFuncRefVTable = {FUNC_REF}::RawFuncRefVTable::new(\n      \
+            {{\n        let invoke: "
+        );
+
+        write_func_ptr(&mut impl_out);
+        impl_out.write_str(" = |data");
+        for i in 0..u32::try_from(signature.params().len()).unwrap() {
+            let _ = write!(impl_out, ", {}", LocalId(i));
+        }
+
+        let _ = write!(
+            impl_out,
+            "| {{\n          // SAFETY: `data` is the pointer to the `Instance` written by `func_ref()`\n          \
+            let instance = unsafe {{ &*(data.pointer as *const Self) }};\n          \
+            instance.{}(",
+            FuncId(export.index)
+        );
+
+        for i in 0..u32::try_from(signature.params().len()).unwrap() {
+            if i > 0 {
+                impl_out.write_str(", ");
+            }
+
+            let _ = write!(impl_out, "{}", LocalId(i));
+        }
+
+        let _ = write!(
+            impl_out,
+            ")\n        }};\n        invoke as *const ()\n      }},\n      \
+            &{FUNC_REF}::FuncRefSignature::of::<"
+        );
+
+        write_func_ptr(&mut impl_out);
+        let _ = writeln!(
+            impl_out,
+            ">(),\n      |data| {FUNC_REF}::RawFuncRef::new(*data, &Self::{vtable}),\n      \
+            |_| (),\n      \
+            |_| &{:?},\n    );\n",
+            export.name
+        );
+    }
+
+    let _ = writeln!(
+        impl_out,
+        "    $vis fn func_ref(&self, name: &str) -> ::core::option::Option<{FUNC_REF}::FuncRef<'_, embedder::rt::trap::TrapValue>> {{\n      \
+        let vtable = match name {{"
+    );
+
+    for export in functions {
+        let _ = writeln!(
+            impl_out,
+            "        {:?} => &Self::{},",
+            export.name,
+            FuncRefVTable(export.index)
+        );
+    }
+
+    let _ = writeln!(
+        impl_out,
+        "        _ => return ::core::option::Option::None,\n      }};\n\n      \
+        let data = {FUNC_REF}::RawFuncRefData {{ pointer: self as *const Self as *const () }};\n      \
+        // SAFETY: the trampolines only use the `data` to access `self`, which the `FuncRef` borrows\n      \
+        ::core::option::Option::Some(unsafe {{ {FUNC_REF}::FuncRef::from_raw({FUNC_REF}::RawFuncRef::new(data, vtable)) }})\n    }}\n"
+    );
+
+    Ok(crate::translation::GeneratedLines {
+        impls: impl_out.finish(),
+        ..Default::default()
+    })
+}
+
+/// Writes the `exports()`, `call_dynamic()`, and `global_dynamic()` methods used to access the
+/// exports by name.
+pub(in crate::translation) fn write_dynamic(
+    buffer_pool: &crate::buffer::Pool,
+    section: Option<wasmparser::ExportSectionReader>,
+    types: &wasmparser::types::Types,
+    async_functions: Option<&crate::translation::call_graph::AsyncFunctions>,
+    thread_safe: bool,
+) -> crate::Result<crate::translation::GeneratedLines> {
+    let exports = match section {
+        Some(section) => section
+            .into_iter()
+            .collect::<wasmparser::Result<Vec<_>>>()?,
+        None => Vec::new(),
+    };
+
+    let mut impl_out = crate::buffer::Writer::new(buffer_pool);
+
+    impl_out.write_str("    // Dynamic API\n");
+
+    let _ = writeln!(
+        impl_out,
+        "    const DYNAMIC_EXPORTS: &'static [{DYNAMIC}::Export] = &["
+    );
+
+    for export in exports.iter() {
+        use wasmparser::ExternalKind;
+
+        let _ = write!(
+            impl_out,
+            "        {DYNAMIC}::Export::new({:?}, ",
+            export.name
+        );
+        match export.kind {
+            ExternalKind::Func => {
+                let signature = crate::translation::function::get_function_type(
+                    types.get(types.core_function_at(export.index)).unwrap(),
+                );
+
+                for ty in signature.params().iter().chain(signature.results()) {
+                    check_dynamic_val_type(*ty, export)?;
+                }
+
+                let _ = write!(impl_out, "{DYNAMIC}::ExportKind::Function(&");
+                crate::translation::function_types::write_signature(&mut impl_out, signature);
+                impl_out.write_str(")");
+            }
+            ExternalKind::Memory => {
+                let _ = write!(impl_out, "{DYNAMIC}::ExportKind::Memory");
+            }
+            ExternalKind::Global => {
+                let global_type = types.global_at(export.index);
+                check_dynamic_val_type(global_type.content_type, export)?;
+
+                let _ = write!(impl_out, "{DYNAMIC}::ExportKind::Global {{ ty: ");
+                crate::translation::function_types::write_val_type(
+                    &mut impl_out,
+                    global_type.content_type,
+                );
+                let _ = write!(impl_out, ", mutable: {} }}", global_type.mutable);
+            }
+            ExternalKind::Table | ExternalKind::Tag => {
+                anyhow::bail!(
+                    "cannot generate dynamic API for export {:?}, exporting a {} is not yet \
+                    supported",
+                    export.name,
+                    if export.kind == ExternalKind::Table {
+                        "table"
+                    } else {
+                        "tag"
+                    }
+                );
+            }
+        }
+
+        impl_out.write_str("),\n");
+    }
+
+    let _ = writeln!(
+        impl_out,
+        "    ];\n\n    $vis fn exports() -> &'static [{DYNAMIC}::Export] {{ Self::DYNAMIC_EXPORTS }}\n"
+    );
+
+    // Calls to exported functions
+    let _ = writeln!(
+        impl_out,
+        "    $vis fn call_dynamic(&self, name: &str, arguments: &[{DYNAMIC}::Val<'_>]) -> \
+        ::core::result::Result<{DYNAMIC}::Vals<'_>, {DYNAMIC}::CallError> {{\n      match name {{"
+    );
+
+    for export in exports.iter() {
+        let _ = write!(impl_out, "        {:?} => ", export.name);
+
+        if export.kind != wasmparser::ExternalKind::Func {
+            let _ = writeln!(
+                impl_out,
+                "::core::result::Result::Err({DYNAMIC}::CallError::NotAFunction),"
+            );
+            continue;
+        }
+
+        if async_functions.is_some_and(|async_functions| async_functions.is_async(export.index)) {
+            let _ = writeln!(
+                impl_out,
+                "::core::result::Result::Err({DYNAMIC}::CallError::Async),"
+            );
+            continue;
+        }
+
+        let signature = crate::translation::function::get_function_type(
+            types.get(types.core_function_at(export.index)).unwrap(),
+        );
+
+        impl_out.write_str("match arguments {\n          [");
+        for (i, ty) in (0u32..).zip(signature.params()) {
+            if i > 0 {
+                impl_out.write_str(", ");
+            }
+
+            write_dynamic_val_pattern(
+                &mut impl_out,
+                *ty,
+                &crate::translation::display::LocalId(i).to_string(),
+            );
+        }
+
+        impl_out.write_str("] => {\n            let ");
+        let results = signature.results();
+        if results.len() != 1 {
+            impl_out.write_str("(");
+        }
+
+        for i in 0..results.len() {
+            if i > 0 {
+                impl_out.write_str(", ");
+            }
+
+            let _ = write!(impl_out, "r_{i}");
+        }
+
+        if results.len() != 1 {
+            impl_out.write_str(")");
+        }
+
+        let _ = write!(
+            impl_out,
+            " = self.{}(",
+            crate::rust::SafeIdent::from(export.name)
+        );
+
+        for (i, ty) in (0u32..).zip(signature.params()) {
+            if i > 0 {
+                impl_out.write_str(", ");
+            }
+
+            let id = crate::translation::display::LocalId(i);
+            if ty.is_reference_type() {
+                let _ = write!(impl_out, "{id}.clone()");
+            } else {
+                let _ = write!(impl_out, "*{id}");
+            }
+        }
+
+        impl_out
+            .write_str(")?;\n            ::core::result::Result::Ok(::core::convert::From::from([");
+
+        for i in 0..results.len() {
+            if i > 0 {
+                impl_out.write_str(", ");
+            }
+
+            let _ = write!(impl_out, "{DYNAMIC}::Val::from(r_{i})");
+        }
+
+        let _ = write!(
+            impl_out,
+            "]))\n          }}\n          _ => ::core::result::Result::Err(\
+            {DYNAMIC}::CallError::ArgumentMismatch {{ expected: &"
+        );
+
+        crate::translation::function_types::write_signature(&mut impl_out, signature);
+        impl_out.write_str(" }),\n        },\n");
+    }
+
+    let _ = writeln!(
+        impl_out,
+        "        _ => ::core::result::Result::Err({DYNAMIC}::CallError::NotFound),\n      }}\n    }}\n"
+    );
+
+    // Reads of exported globals
+    let _ = writeln!(
+        impl_out,
+        "    $vis fn global_dynamic(&self, name: &str) -> ::core::option::Option<{DYNAMIC}::Val<'_>> {{\n      match name {{"
+    );
+
+    for export in exports.iter() {
+        if export.kind != wasmparser::ExternalKind::Global {
+            continue;
+        }
+
+        let name = crate::rust::SafeIdent::from(export.name);
+        let global_type = types.global_at(export.index);
+        let is_reference = global_type.content_type.is_reference_type();
+        if global_type.mutable {
+            let _ = writeln!(
+                impl_out,
+                "        {:?} => ::core::option::Option::Some({DYNAMIC}::Val::from(\
+                {}::{}(self.{name}(){}))),",
+                export.name,
+                crate::translation::global::cell_type(thread_safe),
+                if is_reference { "get_with" } else { "get" },
+                if is_reference {
+                    ", ::core::clone::Clone::clone"
+                } else {
+                    ""
+                },
+            );
+        } else if is_reference {
+            let _ = writeln!(
+                impl_out,
+                "        {:?} => ::core::option::Option::Some({DYNAMIC}::Val::from(\
+                ::core::clone::Clone::clone(self.{name}()))),",
+                export.name
+            );
+        } else {
+            let _ = writeln!(
+                impl_out,
+                "        {:?} => ::core::option::Option::Some({DYNAMIC}::Val::from(*self.{name}())),",
+                export.name
+            );
+        }
+    }
+
+    impl_out.write_str("        _ => ::core::option::Option::None,\n      }\n    }\n\n");
+
+    Ok(crate::translation::GeneratedLines {
+        impls: impl_out.finish(),
+        ..Default::default()
+    })
+}
@@ -2,14 +2,16 @@ use crate::translation::display::{LocalId, ValType};
 use anyhow::Context;
 use std::fmt::Write;
 
-pub(in crate::translation) const TRAP_TRAIT: &str = "embedder::rt::trap::Trap";
-pub(in crate::translation) const TRAP_CODE: &str = "embedder::rt::trap::TrapCode";
-
 pub(in crate::translation) fn write_definition_signature(
     out: &mut crate::buffer::Writer<'_>,
     sig: &wasmparser::FuncType,
+    exclusive_access: bool,
 ) {
-    out.write_str("(&self");
+    out.write_str(if exclusive_access {
+        "(&mut self"
+    } else {
+        "(&self"
+    });
 
     // Write the parameter types
     for (i, ty) in sig.params().iter().enumerate() {
@@ -21,7 +23,16 @@ pub(in crate::translation) fn write_definition_signature(
         );
     }
 
-    out.write_str(") -> embedder::Result<");
+    out.write_str(") -> ");
+    write_result_type(out, sig);
+}
+
+/// Writes the `embedder::Result<...>` type returned by a function with the given signature.
+pub(in crate::translation) fn write_result_type(
+    out: &mut crate::buffer::Writer<'_>,
+    sig: &wasmparser::FuncType,
+) {
+    out.write_str("embedder::Result<");
     let results = sig.results();
 
     if results.len() != 1 {
@@ -92,6 +103,8 @@ fn write_local_variables(
         let default_value = match ty {
             ValType::I32 | ValType::I64 => "0",
             ValType::F32 | ValType::F64 => "0.0",
+            ValType::EXTERNREF => "embedder::rt::extern_ref::ExternRef::NULL",
+            ValType::FUNCREF => "embedder::rt::func_ref::FuncRef::NULL",
             _ => "::core::todo!(\"embedder must provide cloning for references\")",
         };
 
@@ -116,7 +129,7 @@ pub(in crate::translation) fn get_function_type(ty: &wasmparser::SubType) -> &wa
     }
 }
 
-fn get_block_type<'a>(
+pub(in crate::translation) fn get_block_type<'a>(
     types: &'a wasmparser::types::Types,
     ty: &'a wasmparser::BlockType,
 ) -> (&'a [wasmparser::ValType], &'a [wasmparser::ValType]) {
@@ -187,6 +200,175 @@ impl std::fmt::Display for PoppedValue {
     }
 }
 
+/// Upper bound for an unsigned `i32` value on the operand stack.
+#[derive(Clone, Copy, Debug)]
+struct Bound {
+    max: u32,
+    /// If `true`, the value is known to be exactly `max`.
+    exact: bool,
+}
+
+impl Bound {
+    const fn constant(value: u32) -> Self {
+        Self {
+            max: value,
+            exact: true,
+        }
+    }
+
+    const fn at_most(max: u32) -> Self {
+        Self { max, exact: false }
+    }
+}
+
+/// Tracks upper bounds for the `i32` values on the operand stack.
+///
+/// This is used to eliminate bounds checks for memory accesses whose address is known to be
+/// within the minimum size of the memory, such as constant addresses, or addresses that are masked
+/// with **`i32.and`**.
+#[derive(Default)]
+struct OperandBounds {
+    /// The bound for each [`StackValue`], indexed by the height of the operand stack.
+    values: Vec<Option<Bound>>,
+}
+
+impl OperandBounds {
+    fn get(&self, value: PoppedValue) -> Option<Bound> {
+        match value {
+            PoppedValue::Pop(StackValue(height)) => {
+                self.values.get(height as usize).copied().flatten()
+            }
+            PoppedValue::Underflow => None,
+        }
+    }
+
+    fn operand(&self, height_before: u32, depth: u32) -> Option<Bound> {
+        let height = height_before.checked_sub(depth + 1)?;
+        self.values.get(height as usize).copied().flatten()
+    }
+
+    /// Calculates the bound for the result of a numeric instruction, given the bounds of its
+    /// operands.
+    fn result(&self, op: &wasmparser::Operator, height_before: u32) -> Option<Bound> {
+        use wasmparser::Operator;
+
+        let unary = || self.operand(height_before, 0);
+        let lhs = || self.operand(height_before, 1);
+        let rhs = || self.operand(height_before, 0);
+
+        match op {
+            Operator::I32Const { value } => Some(Bound::constant(*value as u32)),
+            Operator::I32And => match (lhs(), rhs()) {
+                (Some(a), Some(b)) if a.exact && b.exact => Some(Bound::constant(a.max & b.max)),
+                (Some(a), Some(b)) => Some(Bound::at_most(a.max.min(b.max))),
+                (Some(bound), None) | (None, Some(bound)) => Some(Bound::at_most(bound.max)),
+                (None, None) => None,
+            },
+            Operator::I32Add => {
+                let (a, b) = (lhs()?, rhs()?);
+                Some(Bound {
+                    max: a.max.checked_add(b.max)?,
+                    exact: a.exact && b.exact,
+                })
+            }
+            Operator::I32Mul => {
+                let (a, b) = (lhs()?, rhs()?);
+                Some(Bound {
+                    max: a.max.checked_mul(b.max)?,
+                    exact: a.exact && b.exact,
+                })
+            }
+            Operator::I32Shl => {
+                let (a, b) = (lhs()?, rhs()?);
+                if !b.exact {
+                    return None;
+                }
+
+                // Only the low 5 bits of the shift amount are used.
+                let shifted = u64::from(a.max) << (b.max % 32);
+                Some(Bound {
+                    max: u32::try_from(shifted).ok()?,
+                    exact: a.exact,
+                })
+            }
+            Operator::I32ShrU => match (lhs(), rhs()) {
+                (a, Some(b)) if b.exact => Some(Bound {
+                    max: a.map_or(u32::MAX, |a| a.max) >> (b.max % 32),
+                    exact: a.is_some_and(|a| a.exact),
+                }),
+                (Some(a), _) => Some(Bound::at_most(a.max)),
+                (None, _) => None,
+            },
+            // The result is less than the divisor, which is not zero if no trap occurs.
+            Operator::I32RemU => {
+                let divisor = rhs()?.max.checked_sub(1)?;
+                Some(Bound::at_most(
+                    lhs().map_or(divisor, |dividend| dividend.max.min(divisor)),
+                ))
+            }
+            Operator::I32DivU => lhs().map(|dividend| Bound::at_most(dividend.max)),
+            Operator::I32Load8U { .. } => Some(Bound::at_most(u32::from(u8::MAX))),
+            Operator::I32Load16U { .. } => Some(Bound::at_most(u32::from(u16::MAX))),
+            Operator::I32Eqz
+            | Operator::I32Eq
+            | Operator::I32Ne
+            | Operator::I32LtS
+            | Operator::I32LtU
+            | Operator::I32GtS
+            | Operator::I32GtU
+            | Operator::I32LeS
+            | Operator::I32LeU
+            | Operator::I32GeS
+            | Operator::I32GeU
+            | Operator::I64Eqz
+            | Operator::I64Eq
+            | Operator::I64Ne
+            | Operator::I64LtS
+            | Operator::I64LtU
+            | Operator::I64GtS
+            | Operator::I64GtU
+            | Operator::I64LeS
+            | Operator::I64LeU
+            | Operator::I64GeS
+            | Operator::I64GeU => Some(Bound::at_most(1)),
+            Operator::LocalTee { .. } => unary(),
+            _ => None,
+        }
+    }
+
+    /// Updates the known bounds after the operator `op` is translated.
+    fn update(&mut self, op: &wasmparser::Operator, height_before: u32, height_after: u32) {
+        use wasmparser::Operator;
+
+        let result = self.result(op, height_before);
+
+        match op {
+            // Values are not tracked across control flow.
+            Operator::Unreachable
+            | Operator::Block { .. }
+            | Operator::Loop { .. }
+            | Operator::If { .. }
+            | Operator::Else
+            | Operator::End
+            | Operator::Br { .. }
+            | Operator::BrIf { .. }
+            | Operator::BrTable { .. }
+            | Operator::Return
+            | Operator::Call { .. }
+            | Operator::CallIndirect { .. } => self.values.clear(),
+            // Other instructions push at most one value, so the values below it are unchanged.
+            _ => self
+                .values
+                .truncate(height_before.min(height_after.saturating_sub(1)) as usize),
+        }
+
+        self.values.resize(height_after as usize, None);
+        if let (Some(bound), Some(last)) = (result, self.values.last_mut()) {
+            *last = Some(bound);
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 enum BranchKind {
     ExplicitReturn,
@@ -342,6 +524,7 @@ fn write_block_start(
 
 mod paths {
     pub(super) const MEMORY: &str = "embedder::rt::memory";
+    pub(super) const EXCLUSIVE_MEMORY: &str = "embedder::rt::memory::exclusive";
 }
 
 macro_rules! access_structs {
@@ -382,6 +565,47 @@ access_structs! {
     MemAccess(crate::translation::display::MemId) | is_memory_import;
 }
 
+/// The memory accessed by a load or store instruction.
+///
+/// With exclusive access to the instance, memories defined by the module are accessed through a
+/// mutable reference using the helpers in `embedder::rt::memory::exclusive`.
+struct LoadStoreAccess {
+    memory: MemAccess,
+    exclusive: bool,
+}
+
+impl LoadStoreAccess {
+    fn new(
+        index: u32,
+        import_counts: &crate::translation::ImportCounts,
+        exclusive_access: bool,
+    ) -> Self {
+        Self {
+            memory: MemAccess::new(index, import_counts),
+            exclusive: exclusive_access && !import_counts.is_memory_import(index),
+        }
+    }
+
+    /// Gets the path to the module containing the load and store helper functions.
+    fn helpers(&self) -> &'static str {
+        if self.exclusive {
+            paths::EXCLUSIVE_MEMORY
+        } else {
+            paths::MEMORY
+        }
+    }
+}
+
+impl std::fmt::Display for LoadStoreAccess {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.exclusive {
+            write!(f, "&mut self.{}", self.memory.id)
+        } else {
+            std::fmt::Display::fmt(&self.memory, f)
+        }
+    }
+}
+
 enum Signedness {
     Signed,
     Unsigned,
@@ -394,17 +618,19 @@ fn write_i8_load(
     signed: Signedness,
     destination: ValType,
     import_counts: &crate::translation::ImportCounts,
+    exclusive_access: bool,
 ) {
     let address = PoppedValue::pop(validator, 0);
+    let mem = LoadStoreAccess::new(memarg.memory, import_counts, exclusive_access);
     let _ = write!(
         out,
         "let {}: {destination} = {}::i8_load::<{}, {}, embedder::Memory{}, embedder::State>({}, {address}, &self.embedder)?",
         StackValue(validator.operand_stack_height() - 1),
-        paths::MEMORY,
+        mem.helpers(),
         memarg.offset,
         memarg.memory,
         memarg.memory,
-        MemAccess::new(memarg.memory, import_counts),
+        mem,
     );
 
     if let Signedness::Unsigned = signed {
@@ -421,18 +647,20 @@ fn write_i16_load(
     signed: Signedness,
     destination: ValType,
     import_counts: &crate::translation::ImportCounts,
+    exclusive_access: bool,
 ) {
     let address = PoppedValue::pop(validator, 0);
+    let mem = LoadStoreAccess::new(memarg.memory, import_counts, exclusive_access);
     let _ = write!(
         out,
         "let {}: {destination} = {}::i16_load::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, &self.embedder)?",
         StackValue(validator.operand_stack_height() - 1),
-        paths::MEMORY,
+        mem.helpers(),
         memarg.offset,
         memarg.align,
         memarg.memory,
         memarg.memory,
-        MemAccess::new(memarg.memory, import_counts),
+        mem,
     );
 
     if let Signedness::Unsigned = signed {
@@ -442,6 +670,276 @@ fn write_i16_load(
     let _ = writeln!(out, " as {destination};");
 }
 
+/// The size, in bytes, of a WebAssembly linear memory page.
+const PAGE_SIZE: u64 = 65536;
+
+/// Gets an upper bound for the [*effective address*] of a memory access of `size` bytes, if one is
+/// known and the access is within the minimum size of the memory.
+///
+/// Since memories can never shrink, such accesses are always in bounds. This also covers memories
+/// with an equal minimum and maximum size, since their size never changes.
+///
+/// [*effective address*]: https://webassembly.github.io/spec/core/syntax/instructions.html#memory-instructions
+fn in_bounds_address(
+    types: &wasmparser::types::Types,
+    memarg: &wasmparser::MemArg,
+    address: Option<Bound>,
+    size: u64,
+) -> Option<(u32, u32)> {
+    let memory = types.memory_at(memarg.memory);
+    if memory.memory64 {
+        return None;
+    }
+
+    let effective = u64::from(address?.max).checked_add(memarg.offset)?;
+    let minimum_size = memory.initial.checked_mul(PAGE_SIZE)?;
+
+    if effective.checked_add(size)? <= minimum_size {
+        Some((
+            u32::try_from(effective).ok()?,
+            u32::try_from(memory.initial).ok()?,
+        ))
+    } else {
+        None
+    }
+}
+
+/// Generates a Rust statement for a memory load or store at an address that is known to be in
+/// bounds, returning `false` if the bounds check could not be eliminated.
+fn write_in_bounds_access(
+    out: &mut crate::buffer::Writer<'_>,
+    validator: &Validator,
+    op: &wasmparser::Operator,
+    bounds: &OperandBounds,
+    types: &wasmparser::types::Types,
+    import_counts: &crate::translation::ImportCounts,
+    exclusive_access: bool,
+) -> bool {
+    use wasmparser::Operator;
+
+    enum Access {
+        Load {
+            destination: &'static str,
+            prefix: &'static str,
+            suffix: &'static str,
+        },
+        Store {
+            suffix: &'static str,
+        },
+    }
+
+    const I8: (&str, u64) = ("i8", 1);
+    const I16: (&str, u64) = ("i16", 2);
+    const I32: (&str, u64) = ("i32", 4);
+    const I64: (&str, u64) = ("i64", 8);
+
+    let (memarg, int, access) = match op {
+        Operator::I32Load { memarg } => (
+            memarg,
+            I32,
+            Access::Load {
+                destination: "i32",
+                prefix: "",
+                suffix: "",
+            },
+        ),
+        Operator::I64Load { memarg } => (
+            memarg,
+            I64,
+            Access::Load {
+                destination: "i64",
+                prefix: "",
+                suffix: "",
+            },
+        ),
+        Operator::F32Load { memarg } => (
+            memarg,
+            I32,
+            Access::Load {
+                destination: "f32",
+                prefix: "f32::from_bits(",
+                suffix: " as u32)",
+            },
+        ),
+        Operator::F64Load { memarg } => (
+            memarg,
+            I64,
+            Access::Load {
+                destination: "f64",
+                prefix: "f64::from_bits(",
+                suffix: " as u64)",
+            },
+        ),
+        Operator::I32Load8S { memarg } => (
+            memarg,
+            I8,
+            Access::Load {
+                destination: "i32",
+                prefix: "",
+                suffix: " as i32",
+            },
+        ),
+        Operator::I32Load8U { memarg } => (
+            memarg,
+            I8,
+            Access::Load {
+                destination: "i32",
+                prefix: "",
+                suffix: " as u8 as i32",
+            },
+        ),
+        Operator::I32Load16S { memarg } => (
+            memarg,
+            I16,
+            Access::Load {
+                destination: "i32",
+                prefix: "",
+                suffix: " as i32",
+            },
+        ),
+        Operator::I32Load16U { memarg } => (
+            memarg,
+            I16,
+            Access::Load {
+                destination: "i32",
+                prefix: "",
+                suffix: " as u16 as i32",
+            },
+        ),
+        Operator::I64Load8S { memarg } => (
+            memarg,
+            I8,
+            Access::Load {
+                destination: "i64",
+                prefix: "",
+                suffix: " as i64",
+            },
+        ),
+        Operator::I64Load8U { memarg } => (
+            memarg,
+            I8,
+            Access::Load {
+                destination: "i64",
+                prefix: "",
+                suffix: " as u8 as i64",
+            },
+        ),
+        Operator::I64Load16S { memarg } => (
+            memarg,
+            I16,
+            Access::Load {
+                destination: "i64",
+                prefix: "",
+                suffix: " as i64",
+            },
+        ),
+        Operator::I64Load16U { memarg } => (
+            memarg,
+            I16,
+            Access::Load {
+                destination: "i64",
+                prefix: "",
+                suffix: " as u16 as i64",
+            },
+        ),
+        Operator::I64Load32S { memarg } => (
+            memarg,
+            I32,
+            Access::Load {
+                destination: "i64",
+                prefix: "",
+                suffix: " as i64",
+            },
+        ),
+        Operator::I64Load32U { memarg } => (
+            memarg,
+            I32,
+            Access::Load {
+                destination: "i64",
+                prefix: "",
+                suffix: " as u32 as i64",
+            },
+        ),
+        Operator::I32Store { memarg } | Operator::I64Store { memarg } => (
+            memarg,
+            if matches!(op, Operator::I32Store { .. }) {
+                I32
+            } else {
+                I64
+            },
+            Access::Store { suffix: "" },
+        ),
+        Operator::F32Store { memarg } => (
+            memarg,
+            I32,
+            Access::Store {
+                suffix: ".to_bits() as i32",
+            },
+        ),
+        Operator::F64Store { memarg } => (
+            memarg,
+            I64,
+            Access::Store {
+                suffix: ".to_bits() as i64",
+            },
+        ),
+        Operator::I32Store8 { memarg } | Operator::I64Store8 { memarg } => {
+            (memarg, I8, Access::Store { suffix: " as i8" })
+        }
+        Operator::I32Store16 { memarg } | Operator::I64Store16 { memarg } => {
+            (memarg, I16, Access::Store { suffix: " as i16" })
+        }
+        Operator::I64Store32 { memarg } => (memarg, I32, Access::Store { suffix: " as i32" }),
+        _ => return false,
+    };
+
+    let (int, size) = int;
+
+    let address_depth = match access {
+        Access::Load { .. } => 0,
+        Access::Store { .. } => 1,
+    };
+
+    let address = PoppedValue::pop(validator, address_depth);
+    let Some((max_address, minimum)) = in_bounds_address(types, memarg, bounds.get(address), size)
+    else {
+        return false;
+    };
+
+    let mem = LoadStoreAccess::new(memarg.memory, import_counts, exclusive_access);
+    let helper = format_args!(
+        "{}::{int}_{}_in_bounds::<{}, {max_address}, {minimum}, {}, embedder::Memory{}, embedder::State>",
+        mem.helpers(),
+        if address_depth == 0 { "load" } else { "store" },
+        memarg.offset,
+        memarg.memory,
+        memarg.memory,
+    );
+
+    match access {
+        Access::Load {
+            destination,
+            prefix,
+            suffix,
+        } => {
+            let _ = writeln!(
+                out,
+                "let {}: {destination} = {prefix}{helper}({mem}, {address}, &self.embedder)?{suffix};",
+                StackValue(validator.operand_stack_height() - 1),
+            );
+        }
+        Access::Store { suffix } => {
+            let _ = writeln!(
+                out,
+                "{helper}({mem}, {address}, {}{suffix}, &self.embedder)?;",
+                PoppedValue::pop(validator, 0)
+            );
+        }
+    }
+
+    true
+}
+
 /// Generates a Rust statement corresponding to a WebAssembly branch instruction.
 ///
 /// The `relative_depth` is the [WebAssembly label] that specifies the target block to jump to.
@@ -536,10 +1034,112 @@ fn write_stack_trace_frame(
     }
 }
 
+const EPOCH_CHECK: &str = "embedder::rt::epoch::check(&self.embedder)?;\n";
+
+/// Returns `true` if the operator ends a basic block.
+fn is_basic_block_end(op: &wasmparser::Operator) -> bool {
+    use wasmparser::Operator;
+
+    matches!(
+        op,
+        Operator::Unreachable
+            | Operator::Block { .. }
+            | Operator::Loop { .. }
+            | Operator::If { .. }
+            | Operator::Else
+            | Operator::End
+            | Operator::Br { .. }
+            | Operator::BrIf { .. }
+            | Operator::BrTable { .. }
+            | Operator::Return
+    )
+}
+
+/// Generates code to charge fuel for the basic block starting at the current position of the
+/// `operators`.
+///
+/// The cost of a basic block is the number of instructions up to and including the instruction
+/// that ends it.
+fn write_fuel_check(
+    out: &mut crate::buffer::Writer<'_>,
+    operators: &wasmparser::OperatorsReader<'_>,
+) -> crate::Result<()> {
+    let mut lookahead = operators.clone();
+    let mut cost = 0u64;
+    while !lookahead.eof() {
+        let op = lookahead.read()?;
+        cost += 1;
+
+        if is_basic_block_end(&op) {
+            break;
+        }
+    }
+
+    if cost > 0 {
+        let _ = writeln!(out, "embedder::rt::fuel::consume({cost}, &self.embedder)?;");
+    }
+
+    Ok(())
+}
+
+/// Information about a function's use of the stack, used to build the module's call graph.
+pub(in crate::translation) struct StackUsage {
+    /// The estimated size of the function's own stack frame, in bytes.
+    pub frame_size: u32,
+    /// Indices of the functions that are directly called.
+    pub callees: Vec<u32>,
+    /// Set if the function calls other functions indirectly, meaning its callees are unknown.
+    pub calls_indirect: bool,
+}
+
+/// Writes the start of a call to a function, up to the opening parenthesis of the argument list.
+pub(in crate::translation) fn write_call_start(
+    out: &mut impl Write,
+    receiver: &str,
+    function_index: u32,
+    async_functions: Option<&crate::translation::call_graph::AsyncFunctions>,
+) {
+    if async_functions.is_some_and(|async_functions| async_functions.is_boxed(function_index)) {
+        let _ = out.write_str("embedder::rt::future::boxed(");
+    }
+
+    let _ = write!(
+        out,
+        "{receiver}.{}(",
+        crate::translation::display::FuncId(function_index)
+    );
+}
+
+/// Writes the end of a call to a function started by [`write_call_start()`], `.await`ing the
+/// result if the function is `async`.
+pub(in crate::translation) fn write_call_end(
+    out: &mut impl Write,
+    function_index: u32,
+    async_functions: Option<&crate::translation::call_graph::AsyncFunctions>,
+) {
+    let _ = out.write_str(")");
+
+    if let Some(async_functions) = async_functions {
+        if async_functions.is_boxed(function_index) {
+            let _ = out.write_str(")");
+        }
+
+        if async_functions.is_async(function_index) {
+            let _ = out.write_str(".await");
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
-pub(in crate::translation) struct Options {
+pub(in crate::translation) struct Options<'a> {
     pub emit_stack_overflow_checks: bool,
+    pub emit_call_depth_checks: bool,
+    pub fuel_metering: bool,
+    pub epoch_interruption: bool,
+    pub exclusive_access: bool,
+    pub thread_safe: bool,
     pub debug_info: crate::DebugInfo,
+    pub async_functions: Option<&'a crate::translation::call_graph::AsyncFunctions>,
 }
 
 /// Generates a [Rust function] definition corresponding to a [WebAssembly function body].
@@ -552,8 +1152,8 @@ pub(in crate::translation) fn write_definition(
     body: &wasmparser::FunctionBody,
     types: &wasmparser::types::Types, // TODO: Remove types parameter, see if validator by itself can be used
     import_counts: &crate::translation::ImportCounts,
-    options: Options,
-) -> crate::Result<()> {
+    options: Options<'_>,
+) -> crate::Result<StackUsage> {
     let func_idx = validator.index();
     let func_type =
         wasmparser::WasmModuleResources::type_of_function(validator.resources(), func_idx)
@@ -582,13 +1182,18 @@ pub(in crate::translation) fn write_definition(
         );
     }
 
+    let is_async = options
+        .async_functions
+        .is_some_and(|async_functions| async_functions.is_async(func_idx));
+
     let _ = write!(
         out,
-        "\n    fn {}",
+        "\n    {}fn {}",
+        if is_async { "async " } else { "" },
         crate::translation::display::FuncId(func_idx)
     );
 
-    write_definition_signature(out, func_type);
+    write_definition_signature(out, func_type, options.exclusive_access);
     out.write_str(" {\n");
 
     // TODO: Make a crate::buffer::IndentedWriter or something
@@ -603,6 +1208,28 @@ pub(in crate::translation) fn write_definition(
         );
     }
 
+    // With exclusive access, a `CallDepthGuard` would keep the embedder borrowed while the function
+    // calls other functions, so the body is wrapped instead, and the call depth is decremented
+    // after it returns.
+    let wrap_body = options.emit_call_depth_checks && options.exclusive_access;
+    if wrap_body {
+        out.write_str(
+            "      embedder::rt::stack::enter_call_unguarded(&self.embedder)?;\n      let _result: ",
+        );
+        write_result_type(out, func_type);
+        if is_async {
+            out.write_str(" = async {\n");
+        } else {
+            out.write_str(" = {\n      let mut _body = || -> ");
+            write_result_type(out, func_type);
+            out.write_str(" {\n");
+        }
+    } else if options.emit_call_depth_checks {
+        out.write_str(
+            "      let _call_depth = embedder::rt::stack::enter_call(&self.embedder)?;\n",
+        );
+    }
+
     let mut local_stack_space = LocalStackSpace { space: 0 };
 
     for ty in func_type.params() {
@@ -619,6 +1246,17 @@ pub(in crate::translation) fn write_definition(
 
     let mut operators = body.get_operators_reader()?;
     let mut max_operand_stack_size = 0u32;
+    let mut bounds = OperandBounds::default();
+    let mut callees = Vec::new();
+    let mut calls_indirect = false;
+
+    if options.epoch_interruption {
+        out.write_str(EPOCH_CHECK);
+    }
+
+    if options.fuel_metering {
+        write_fuel_check(out, &operators)?;
+    }
     while !operators.eof() {
         use wasmparser::Operator;
 
@@ -635,6 +1273,33 @@ pub(in crate::translation) fn write_definition(
             continue;
         }
 
+        let height_before = validator.operand_stack_height();
+
+        if matches!(
+            op,
+            Operator::CallIndirect { .. }
+                | Operator::ReturnCallIndirect { .. }
+                | Operator::CallRef { .. }
+                | Operator::ReturnCallRef { .. }
+        ) {
+            calls_indirect = true;
+        }
+
+        if write_in_bounds_access(
+            out,
+            validator,
+            &op,
+            &bounds,
+            types,
+            import_counts,
+            options.exclusive_access,
+        ) {
+            // Loads and stores never increase the height of the operand stack.
+            validator.op(op_offset, &op)?;
+            bounds.update(&op, height_before, validator.operand_stack_height());
+            continue;
+        }
+
         const MEMORY: &str = paths::MEMORY;
         const MATH: &str = "embedder::rt::math";
 
@@ -695,6 +1360,10 @@ pub(in crate::translation) fn write_definition(
                     let operand = StackValue(i + result_start_height);
                     let _ = writeln!(out, "let {operand} = _b_{}{operand};", label);
                 }
+
+                if options.epoch_interruption {
+                    out.write_str(EPOCH_CHECK);
+                }
             }
             Operator::If { blockty } => {
                 write_block_start(
@@ -806,6 +1475,8 @@ pub(in crate::translation) fn write_definition(
                 )
                 .expect("could not get callee type");
 
+                callees.push(function_index);
+
                 let result_count = u32::try_from(signature.results().len()).unwrap_or(u32::MAX);
                 let param_count = u32::try_from(signature.params().len()).unwrap_or(u32::MAX);
 
@@ -833,11 +1504,7 @@ pub(in crate::translation) fn write_definition(
                     out.write_str(" = ");
                 }
 
-                let _ = write!(
-                    out,
-                    "self.{}(",
-                    crate::translation::display::FuncId(function_index)
-                );
+                write_call_start(out, "self", function_index, options.async_functions);
 
                 // Writes the parameters, the first (the leftmost) parameter is popped last.
                 for depth in (0..param_count).rev() {
@@ -848,7 +1515,8 @@ pub(in crate::translation) fn write_definition(
                     let _ = write!(out, "{}", PoppedValue::pop(validator, depth));
                 }
 
-                out.write_str(")?;\n");
+                write_call_end(out, function_index, options.async_functions);
+                out.write_str("?;\n");
             }
             // Operator::CallIndirect { type_index, table_index, table_byte } => { todo!() }
             Operator::Drop => {
@@ -871,11 +1539,22 @@ pub(in crate::translation) fn write_definition(
             Operator::LocalGet { local_index } => {
                 let _ = write!(out, "let {}", StackValue(validator.operand_stack_height()));
 
-                if let Some(ty) = validator.get_local_type(local_index) {
+                let ty = validator.get_local_type(local_index);
+                if let Some(ty) = ty {
                     let _ = write!(out, ": {}", ValType(ty));
                 }
 
-                let _ = writeln!(out, " = {};", LocalId(local_index));
+                // References are not `Copy`
+                let _ = writeln!(
+                    out,
+                    " = {}{};",
+                    LocalId(local_index),
+                    if ty.is_some_and(|ty| ty.is_reference_type()) {
+                        ".clone()"
+                    } else {
+                        ""
+                    }
+                );
             }
             Operator::LocalSet { local_index } => {
                 let _ = writeln!(
@@ -888,10 +1567,19 @@ pub(in crate::translation) fn write_definition(
             Operator::LocalTee { local_index } => {
                 let arg = PoppedValue::pop(validator, 0);
 
-                // TODO: For `local.set` and `local.tee`, how will `funcref`/`externref` be copied?
-                let _ = write!(out, "{} = {arg};\nlet {arg:#}", LocalId(local_index));
+                let ty = validator.get_local_type(local_index);
+                let _ = write!(
+                    out,
+                    "{} = {arg}{};\nlet {arg:#}",
+                    LocalId(local_index),
+                    if ty.is_some_and(|ty| ty.is_reference_type()) {
+                        ".clone()"
+                    } else {
+                        ""
+                    }
+                );
 
-                if let Some(ty) = validator.get_local_type(local_index) {
+                if let Some(ty) = ty {
                     let _ = write!(out, ": {}", ValType(ty));
                 }
 
@@ -911,19 +1599,35 @@ pub(in crate::translation) fn write_definition(
                 let id = crate::translation::display::GlobalId(global_index);
                 let global_type = types.global_at(global_index);
                 let is_imported = import_counts.is_global_import(global_index);
-                if !global_type.mutable {
-                    if is_imported {
+                let is_field = !is_imported && options.exclusive_access;
+                // References are not `Copy`
+                let is_reference = global_type.content_type.is_reference_type();
+                if !global_type.mutable || is_field {
+                    if is_reference {
+                        out.write_str("::core::clone::Clone::clone(");
+                        if !is_imported {
+                            out.write_str("&");
+                        }
+                    } else if is_imported {
                         out.write_str("*");
                     }
 
-                    // TODO: How to clone global value for non-Copy types?
                     let _ = write!(out, "self.{id}");
 
                     if is_imported {
                         out.write_str("()");
                     }
+
+                    if is_reference {
+                        out.write_str(")");
+                    }
                 } else {
-                    out.write_str("embedder::rt::global::Global::get(");
+                    let _ = write!(
+                        out,
+                        "{}::{}(",
+                        crate::translation::global::cell_type(options.thread_safe),
+                        if is_reference { "get_with" } else { "get" },
+                    );
 
                     if !is_imported {
                         out.write_str("&");
@@ -935,6 +1639,10 @@ pub(in crate::translation) fn write_definition(
                         out.write_str("()");
                     }
 
+                    if is_reference {
+                        out.write_str(", ::core::clone::Clone::clone");
+                    }
+
                     out.write_str(")");
                 }
 
@@ -942,77 +1650,79 @@ pub(in crate::translation) fn write_definition(
             }
             Operator::GlobalSet { global_index } => {
                 let new_value = PoppedValue::pop(validator, 0);
-
-                out.write_str("embedder::rt::global::Global::set(");
-
-                if !import_counts.is_global_import(global_index) {
-                    out.write_str("&");
-                }
-
-                let _ = write!(
-                    out,
-                    "self.{}",
-                    crate::translation::display::GlobalId(global_index)
-                );
+                let id = crate::translation::display::GlobalId(global_index);
+                let cell_type = crate::translation::global::cell_type(options.thread_safe);
 
                 if import_counts.is_global_import(global_index) {
-                    out.write_str("()");
+                    let _ = writeln!(out, "{cell_type}::set(self.{id}(), {new_value});");
+                } else if options.exclusive_access {
+                    let _ = writeln!(out, "self.{id} = {new_value};");
+                } else {
+                    let _ = writeln!(out, "{cell_type}::set(&self.{id}, {new_value});");
                 }
-
-                let _ = write!(out, ", {new_value})");
-
-                out.write_str(";\n");
             }
             Operator::I32Load { memarg } => {
                 let address = PoppedValue::pop(validator, 0);
+                let mem =
+                    LoadStoreAccess::new(memarg.memory, import_counts, options.exclusive_access);
                 let _ = writeln!(
                     out,
-                    "let {}: i32 = {MEMORY}::i32_load::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, &self.embedder)?;",
+                    "let {}: i32 = {helpers}::i32_load::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, &self.embedder)?;",
                     StackValue(validator.operand_stack_height() - 1),
                     memarg.offset,
                     memarg.align,
                     memarg.memory,
                     memarg.memory,
-                    MemAccess::new(memarg.memory, import_counts),
+                    mem,
+                    helpers = mem.helpers(),
                 );
             }
             Operator::I64Load { memarg } => {
                 let address = PoppedValue::pop(validator, 0);
+                let mem =
+                    LoadStoreAccess::new(memarg.memory, import_counts, options.exclusive_access);
                 let _ = writeln!(
                     out,
-                    "let {}: i64 = {MEMORY}::i64_load::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, &self.embedder)?;",
+                    "let {}: i64 = {helpers}::i64_load::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, &self.embedder)?;",
                     StackValue(validator.operand_stack_height() - 1),
                     memarg.offset,
                     memarg.align,
                     memarg.memory,
                     memarg.memory,
-                    MemAccess::new(memarg.memory, import_counts),
+                    mem,
+                    helpers = mem.helpers(),
                 );
             }
             Operator::F32Load { memarg } => {
                 let address = PoppedValue::pop(validator, 0);
+                let mem =
+                    LoadStoreAccess::new(memarg.memory, import_counts, options.exclusive_access);
                 let _ = writeln!(
                     out,
-                    "let {}: f32 = f32::from_bits({MEMORY}::i32_load::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, &self.embedder)? as u32);",
+                    "let {}: f32 = f32::from_bits({helpers}::i32_load::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, &self.embedder)? as u32);",
                     StackValue(validator.operand_stack_height() - 1),
                     memarg.offset,
                     memarg.align,
                     memarg.memory,
                     memarg.memory,
-                    MemAccess::new(memarg.memory, import_counts),
+                    mem,
+                    helpers = mem.helpers(),
                 );
             }
             Operator::F64Load { memarg } => {
                 let address = PoppedValue::pop(validator, 0);
+                let mem =
+                    LoadStoreAccess::new(memarg.memory, import_counts, options.exclusive_access);
                 let _ = writeln!(
                     out,
-                    "let {}: f64 = f64::from_bits({MEMORY}::i64_load::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, &self.embedder)? as u64);",
+                    "let {}: f64 = f64::from_bits({helpers}::i64_load::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, &self.embedder)? as u64);",
                     StackValue(validator.operand_stack_height() - 1),
                     memarg.offset,
                     memarg.align,
                     memarg.memory,
                     memarg.memory,
-                    MemAccess::new(memarg.memory, import_counts),
+                    mem,
+                    helpers = mem.helpers(),
                 );
             }
             Operator::I32Load8S { memarg } => {
@@ -1023,6 +1733,7 @@ pub(in crate::translation) fn write_definition(
                     Signedness::Signed,
                     ValType::I32,
                     import_counts,
+                    options.exclusive_access,
                 );
             }
             Operator::I32Load8U { memarg } => {
@@ -1033,6 +1744,7 @@ pub(in crate::translation) fn write_definition(
                     Signedness::Unsigned,
                     ValType::I32,
                     import_counts,
+                    options.exclusive_access,
                 );
             }
             Operator::I32Load16S { memarg } => {
@@ -1043,6 +1755,7 @@ pub(in crate::translation) fn write_definition(
                     Signedness::Signed,
                     ValType::I32,
                     import_counts,
+                    options.exclusive_access,
                 );
             }
             Operator::I32Load16U { memarg } => {
@@ -1053,6 +1766,7 @@ pub(in crate::translation) fn write_definition(
                     Signedness::Unsigned,
                     ValType::I32,
                     import_counts,
+                    options.exclusive_access,
                 );
             }
             Operator::I64Load8S { memarg } => {
@@ -1063,6 +1777,7 @@ pub(in crate::translation) fn write_definition(
                     Signedness::Signed,
                     ValType::I64,
                     import_counts,
+                    options.exclusive_access,
                 );
             }
             Operator::I64Load8U { memarg } => {
@@ -1073,6 +1788,7 @@ pub(in crate::translation) fn write_definition(
                     Signedness::Unsigned,
                     ValType::I64,
                     import_counts,
+                    options.exclusive_access,
                 );
             }
             Operator::I64Load16S { memarg } => {
@@ -1083,6 +1799,7 @@ pub(in crate::translation) fn write_definition(
                     Signedness::Signed,
                     ValType::I64,
                     import_counts,
+                    options.exclusive_access,
                 );
             }
             Operator::I64Load16U { memarg } => {
@@ -1093,122 +1810,150 @@ pub(in crate::translation) fn write_definition(
                     Signedness::Unsigned,
                     ValType::I64,
                     import_counts,
+                    options.exclusive_access,
                 );
             }
             Operator::I64Load32S { memarg } => {
                 let address = PoppedValue::pop(validator, 0);
+                let mem =
+                    LoadStoreAccess::new(memarg.memory, import_counts, options.exclusive_access);
                 let _ = writeln!(
                     out,
-                    "let {}: i64 = {MEMORY}::i32_load::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, &self.embedder)? as i64;",
+                    "let {}: i64 = {helpers}::i32_load::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, &self.embedder)? as i64;",
                     StackValue(validator.operand_stack_height() - 1),
                     memarg.offset,
                     memarg.align,
                     memarg.memory,
                     memarg.memory,
-                    MemAccess::new(memarg.memory, import_counts),
+                    mem,
+                    helpers = mem.helpers(),
                 );
             }
             Operator::I64Load32U { memarg } => {
                 let address = PoppedValue::pop(validator, 0);
+                let mem =
+                    LoadStoreAccess::new(memarg.memory, import_counts, options.exclusive_access);
                 let _ = writeln!(
                     out,
-                    "let {}: i64 = {MEMORY}::i32_load::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, &self.embedder)? as u32 as i64;",
+                    "let {}: i64 = {helpers}::i32_load::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, &self.embedder)? as u32 as i64;",
                     StackValue(validator.operand_stack_height() - 1),
                     memarg.offset,
                     memarg.align,
                     memarg.memory,
                     memarg.memory,
-                    MemAccess::new(memarg.memory, import_counts),
+                    mem,
+                    helpers = mem.helpers(),
                 );
             }
             Operator::I32Store { memarg } => {
                 let to_store = PoppedValue::pop(validator, 0);
                 let address = PoppedValue::pop(validator, 1);
+                let mem =
+                    LoadStoreAccess::new(memarg.memory, import_counts, options.exclusive_access);
                 let _ = writeln!(
                     out,
-                    "{MEMORY}::i32_store::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, {to_store}, &self.embedder)?;",
+                    "{helpers}::i32_store::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, {to_store}, &self.embedder)?;",
                     memarg.offset,
                     memarg.align,
                     memarg.memory,
                     memarg.memory,
-                    MemAccess::new(memarg.memory, import_counts),
+                    mem,
+                    helpers = mem.helpers(),
                 );
             }
             Operator::I64Store { memarg } => {
                 let to_store = PoppedValue::pop(validator, 0);
                 let address = PoppedValue::pop(validator, 1);
+                let mem =
+                    LoadStoreAccess::new(memarg.memory, import_counts, options.exclusive_access);
                 let _ = writeln!(
                     out,
-                    "{MEMORY}::i64_store::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, {to_store}, &self.embedder)?;",
+                    "{helpers}::i64_store::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, {to_store}, &self.embedder)?;",
                     memarg.offset,
                     memarg.align,
                     memarg.memory,
                     memarg.memory,
-                    MemAccess::new(memarg.memory, import_counts),
+                    mem,
+                    helpers = mem.helpers(),
                 );
             }
             Operator::F32Store { memarg } => {
                 let to_store = PoppedValue::pop(validator, 0);
                 let address = PoppedValue::pop(validator, 1);
+                let mem =
+                    LoadStoreAccess::new(memarg.memory, import_counts, options.exclusive_access);
                 let _ = writeln!(
                     out,
-                    "{MEMORY}::i32_store::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, {to_store}.to_bits() as i32, &self.embedder)?;",
+                    "{helpers}::i32_store::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, {to_store}.to_bits() as i32, &self.embedder)?;",
                     memarg.offset,
                     memarg.align,
                     memarg.memory,
                     memarg.memory,
-                    MemAccess::new(memarg.memory, import_counts),
+                    mem,
+                    helpers = mem.helpers(),
                 );
             }
             Operator::F64Store { memarg } => {
                 let to_store = PoppedValue::pop(validator, 0);
                 let address = PoppedValue::pop(validator, 1);
+                let mem =
+                    LoadStoreAccess::new(memarg.memory, import_counts, options.exclusive_access);
                 let _ = writeln!(
                     out,
-                    "{MEMORY}::i64_store::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, {to_store}.to_bits() as i64, &self.embedder)?;",
+                    "{helpers}::i64_store::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, {to_store}.to_bits() as i64, &self.embedder)?;",
                     memarg.offset,
                     memarg.align,
                     memarg.memory,
                     memarg.memory,
-                    MemAccess::new(memarg.memory, import_counts),
+                    mem,
+                    helpers = mem.helpers(),
                 );
             }
             Operator::I32Store8 { memarg } | Operator::I64Store8 { memarg } => {
                 let to_store = PoppedValue::pop(validator, 0);
                 let address = PoppedValue::pop(validator, 1);
+                let mem =
+                    LoadStoreAccess::new(memarg.memory, import_counts, options.exclusive_access);
                 let _ = writeln!(
                     out,
-                    "{MEMORY}::i8_store::<{}, {}, embedder::Memory{}, embedder::State>({}, {address}, {to_store} as i8, &self.embedder)?;",
+                    "{helpers}::i8_store::<{}, {}, embedder::Memory{}, embedder::State>({}, {address}, {to_store} as i8, &self.embedder)?;",
                     memarg.offset,
                     memarg.memory,
                     memarg.memory,
-                    MemAccess::new(memarg.memory, import_counts),
+                    mem,
+                    helpers = mem.helpers(),
                 );
             }
             Operator::I32Store16 { memarg } | Operator::I64Store16 { memarg } => {
                 let to_store = PoppedValue::pop(validator, 0);
                 let address = PoppedValue::pop(validator, 1);
+                let mem =
+                    LoadStoreAccess::new(memarg.memory, import_counts, options.exclusive_access);
                 let _ = writeln!(
                     out,
-                    "{MEMORY}::i16_store::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, {to_store} as i16, &self.embedder)?;",
+                    "{helpers}::i16_store::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, {to_store} as i16, &self.embedder)?;",
                     memarg.offset,
                     memarg.align,
                     memarg.memory,
                     memarg.memory,
-                    MemAccess::new(memarg.memory, import_counts),
+                    mem,
+                    helpers = mem.helpers(),
                 );
             }
             Operator::I64Store32 { memarg } => {
                 let to_store = PoppedValue::pop(validator, 0);
                 let address = PoppedValue::pop(validator, 1);
+                let mem =
+                    LoadStoreAccess::new(memarg.memory, import_counts, options.exclusive_access);
                 let _ = writeln!(
                     out,
-                    "{MEMORY}::i32_store::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, {to_store} as i32, &self.embedder)?;",
+                    "{helpers}::i32_store::<{}, {}, {}, embedder::Memory{}, embedder::State>({}, {address}, {to_store} as i32, &self.embedder)?;",
                     memarg.offset,
                     memarg.align,
                     memarg.memory,
                     memarg.memory,
-                    MemAccess::new(memarg.memory, import_counts),
+                    mem,
+                    helpers = mem.helpers(),
                 );
             }
             Operator::MemorySize { mem, mem_byte: _ } => {
@@ -1807,26 +2552,84 @@ pub(in crate::translation) fn write_definition(
                 let popped = PoppedValue::pop(validator, 0);
                 let _ = writeln!(out, "let {popped:#}: i64 = ({popped} as u64) as i64;");
             }
+            Operator::RefNull {
+                hty: wasmparser::HeapType::Extern,
+            } => {
+                let _ = writeln!(
+                    out,
+                    "let {}: embedder::rt::extern_ref::ExternRef = embedder::rt::extern_ref::ExternRef::NULL;",
+                    StackValue(validator.operand_stack_height())
+                );
+            }
+            Operator::RefNull {
+                hty: wasmparser::HeapType::Func,
+            } => {
+                let _ = writeln!(
+                    out,
+                    "let {}: embedder::rt::func_ref::FuncRef<'_, embedder::rt::trap::TrapValue> = embedder::rt::func_ref::FuncRef::NULL;",
+                    StackValue(validator.operand_stack_height())
+                );
+            }
+            Operator::RefIsNull => {
+                let popped = PoppedValue::pop(validator, 0);
+                let _ = writeln!(out, "let {popped:#} = {popped}.is_null() as i32;");
+            }
             _ => anyhow::bail!("translation of operation is not yet supported: {op:?}"),
         }
 
+        let control_height_before = validator.control_stack_height();
+
         validator.op(op_offset, &op)?;
+        bounds.update(&op, height_before, validator.operand_stack_height());
         max_operand_stack_size = validator.operand_stack_height().max(max_operand_stack_size);
+
+        // A new basic block starts after entering a block or loop, or when execution continues
+        // after a branch or the end of a block. Loop headers are charged every iteration.
+        if options.fuel_metering
+            && is_basic_block_end(&op)
+            && !matches!(
+                op,
+                Operator::Br { .. }
+                    | Operator::BrTable { .. }
+                    | Operator::Return
+                    | Operator::Unreachable
+            )
+            && !(matches!(op, Operator::End) && control_height_before == 1)
+        {
+            write_fuel_check(out, &operators)?;
+        }
     }
 
     // Implicit return generated when last `end` is handled.
     validator.finish(operators.original_position())?;
 
+    if wrap_body {
+        out.write_str(if is_async {
+            "      }.await;\n"
+        } else {
+            "      };\n      _body()\n      };\n"
+        });
+        out.write_str("      embedder::rt::stack::exit_call(&self.embedder);\n      _result\n");
+    }
+
     out.write_str("    }\n");
 
+    let frame_size = local_stack_space.finish(max_operand_stack_size);
+
     if options.emit_stack_overflow_checks {
         let _ = writeln!(
             out,
-            "\n    const STACK_FRAME_SIZE_{}: usize = {};\n",
+            "\n    const STACK_FRAME_SIZE_{}: usize = {frame_size};\n",
             validator.index(),
-            local_stack_space.finish(max_operand_stack_size)
         );
     }
 
-    Ok(())
+    callees.sort_unstable();
+    callees.dedup();
+
+    Ok(StackUsage {
+        frame_size,
+        callees,
+        calls_indirect,
+    })
 }
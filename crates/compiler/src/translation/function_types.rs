@@ -1,33 +1,53 @@
 use std::fmt::Write;
 
+/// Writes the `WasmValType` corresponding to the given value type.
+pub(in crate::translation) fn write_val_type(
+    out: &mut crate::buffer::Writer,
+    ty: wasmparser::ValType,
+) {
+    use wasmparser::ValType;
+
+    out.write_str("embedder::rt::stack::trace::WasmValType::");
+    out.write_str(match ty {
+        ValType::I32 => "I32",
+        ValType::I64 => "I64",
+        ValType::F32 => "F32",
+        ValType::F64 => "F64",
+        ValType::V128 => "V128",
+        ValType::FUNCREF => "FuncRef",
+        ValType::EXTERNREF => "ExternRef",
+        ValType::Ref(unsupported) => {
+            unimplemented!("unknown value type in signature {unsupported:?}")
+        }
+    });
+}
+
 fn write_result_types(out: &mut crate::buffer::Writer, types: &[wasmparser::ValType]) {
     out.write_str("&[");
 
     for (i, ty) in types.iter().enumerate() {
-        use wasmparser::ValType;
-
         if i > 0 {
             out.write_str(", ");
         }
 
-        out.write_str("embedder::rt::stack::trace::WasmValType::");
-        out.write_str(match ty {
-            ValType::I32 => "I32",
-            ValType::I64 => "I64",
-            ValType::F32 => "F32",
-            ValType::F64 => "F64",
-            ValType::V128 => "V128",
-            &ValType::FUNCREF => "FuncRef",
-            &ValType::EXTERNREF => "ExternRef",
-            ValType::Ref(unsupported) => {
-                unimplemented!("unknown value type in signature {unsupported:?}")
-            }
-        });
+        write_val_type(out, *ty);
     }
 
     out.write_str("]");
 }
 
+/// Writes a `WasmSymbolSignature` struct expression describing the given function signature.
+pub(in crate::translation) fn write_signature(
+    out: &mut crate::buffer::Writer,
+    signature: &wasmparser::FuncType,
+) {
+    out.write_str("embedder::rt::stack::trace::WasmSymbolSignature { parameters: ");
+    write_result_types(out, signature.params());
+    out.write_str(", results: ");
+    write_result_types(out, signature.results());
+    out.write_str(" }");
+}
+
 fn write_function_signature(
     out: &mut crate::buffer::Writer,
     func_idx: u32,
@@ -35,15 +55,12 @@ fn write_function_signature(
 ) {
     let _ = write!(
         out,
-        "    const {}: embedder::rt::stack::trace::WasmSymbolSignature = \
-        embedder::rt::stack::trace::WasmSymbolSignature {{ parameters: ",
+        "    const {}: embedder::rt::stack::trace::WasmSymbolSignature = ",
         crate::translation::display::FuncSignature(func_idx),
     );
 
-    write_result_types(out, signature.params());
-    out.write_str(", results: ");
-    write_result_types(out, signature.results());
-    out.write_str(" };\n");
+    write_signature(out, signature);
+    out.write_str(";\n");
 }
 
 pub fn write(
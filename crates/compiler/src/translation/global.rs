@@ -1,10 +1,36 @@
 use anyhow::Context;
 use std::fmt::Write;
 
+/// Gets the path to the type used to store the value of a mutable global.
+pub(in crate::translation) const fn cell_type(thread_safe: bool) -> &'static str {
+    if thread_safe {
+        "embedder::rt::global::AtomicGlobal"
+    } else {
+        "embedder::rt::global::Global"
+    }
+}
+
+/// Checks that a mutable global with values of the given type can be stored in a
+/// [`cell_type()`].
+pub(in crate::translation) fn check_cell_value_type(
+    ty: wasmparser::ValType,
+    thread_safe: bool,
+) -> crate::Result<()> {
+    if thread_safe && ty.is_reference_type() {
+        anyhow::bail!("mutable {ty} globals are not yet supported in thread-safe instances");
+    }
+
+    Ok(())
+}
+
 pub fn write(
     buffer_pool: &crate::buffer::Pool,
     section: wasmparser::GlobalSectionReader,
     start_index: u32,
+    linked_globals: &[u32],
+    exclusive_access: bool,
+    thread_safe: bool,
+    initialized: Option<&crate::translation::pre_init::Initialized>,
 ) -> crate::Result<crate::translation::GeneratedLines> {
     let mut field_out = crate::buffer::Writer::new(buffer_pool);
     let mut init_out = crate::buffer::Writer::new(buffer_pool);
@@ -14,9 +40,18 @@ pub fn write(
         let id = crate::translation::display::GlobalId(index);
         let val_type = crate::translation::display::ValType(global.ty.content_type);
 
+        // With exclusive access to the instance, mutable globals are stored as plain fields
+        let is_cell = global.ty.mutable && !exclusive_access;
+        let cell_type = cell_type(thread_safe);
+
+        if is_cell {
+            check_cell_value_type(global.ty.content_type, thread_safe)
+                .with_context(|| format!("could not translate global #{index}"))?;
+        }
+
         let _ = write!(field_out, "    {id}: ");
-        if global.ty.mutable {
-            let _ = write!(field_out, "embedder::rt::global::Global<{val_type}>");
+        if is_cell {
+            let _ = write!(field_out, "{cell_type}<{val_type}>");
         } else {
             let _ = write!(field_out, "{val_type}");
         }
@@ -24,14 +59,24 @@ pub fn write(
         field_out.write_str(",\n");
 
         let _ = write!(init_out, "let {id} = ");
-        if global.ty.mutable {
-            init_out.write_str("embedder::rt::global::Global::new(");
+        if is_cell {
+            let _ = write!(init_out, "{cell_type}::new(");
         }
 
-        crate::translation::const_expr::write(&mut init_out, &global.init_expr)
-            .with_context(|| format!("could not translate value for global #{index}"))?;
+        let globals = crate::translation::const_expr::Globals {
+            imported_count: start_index,
+            linked: linked_globals,
+        };
+
+        if let Some(initialized) = initialized {
+            let value = initialized.globals[(index - start_index) as usize];
+            crate::translation::pre_init::write_value(&mut init_out, global.ty.content_type, value);
+        } else {
+            crate::translation::const_expr::write(&mut init_out, &global.init_expr, globals)
+                .with_context(|| format!("could not translate value for global #{index}"))?;
+        }
 
-        if global.ty.mutable {
+        if is_cell {
             init_out.write_str(")");
         }
 
@@ -1,21 +1,140 @@
+use anyhow::Context;
 use std::fmt::Write;
 
 const IMPORTS_OBJECT: &str = "self.embedder.imports()";
 const LIMITS_ENUM: &str = "embedder::rt::trap::LimitsCheck";
+const LINKER: &str = "<embedder::Imports as ::core::borrow::Borrow<embedder::rt::linker::Linker>>::borrow(embedder.imports())";
+
+#[derive(Clone, Copy)]
+pub(in crate::translation) struct Options<'a> {
+    pub debug_info: crate::DebugInfo,
+    pub async_imports: &'a [(&'a str, &'a str)],
+    pub import_caller: bool,
+    pub bound_imports: &'a [(&'a str, &'a str, &'a str)],
+    /// Set if the module has a `dylink.0` custom section.
+    pub dylink: bool,
+    pub runtime_linker: bool,
+    pub thread_safe: bool,
+}
+
+/// Returns `true` if the import is looked up in the `wasm2rs_rt::linker::Linker` when the module
+/// is instantiated.
+fn is_from_linker(import: &wasmparser::Import, options: &Options) -> bool {
+    let is_linked_global = options.dylink
+        && matches!(import.ty, wasmparser::TypeRef::Global(_))
+        && crate::translation::dylink::LinkedGlobal::from_import(import.module, import.name)
+            .is_some();
+
+    options.runtime_linker
+        && !is_linked_global
+        && !options
+            .bound_imports
+            .iter()
+            .any(|(module, name, _)| (*module, *name) == (import.module, import.name))
+}
+
+/// The imports that are looked up in the runtime linker and stored in the `Instance`.
+#[derive(Default)]
+pub(in crate::translation) struct LinkerImports {
+    /// Indices of the imported functions, in ascending order.
+    pub functions: Vec<u32>,
+    /// Indices of the imported globals, in ascending order.
+    pub globals: Vec<u32>,
+}
+
+/// Gets the indices of the imported functions and globals that are looked up in the runtime
+/// linker.
+pub(in crate::translation) fn linker_imports(
+    section: &wasmparser::ImportSectionReader,
+    options: &Options,
+) -> crate::Result<LinkerImports> {
+    let mut imports = LinkerImports::default();
+    let mut function_index = 0u32;
+    let mut global_index = 0u32;
+    for result in section.clone() {
+        let import = result?;
+        let from_linker = is_from_linker(&import, options);
+        match import.ty {
+            wasmparser::TypeRef::Func(_) => {
+                if from_linker {
+                    imports.functions.push(function_index);
+                }
+
+                function_index += 1;
+            }
+            wasmparser::TypeRef::Global(_) => {
+                if from_linker {
+                    imports.globals.push(global_index);
+                }
+
+                global_index += 1;
+            }
+            _ => (),
+        }
+    }
+
+    Ok(imports)
+}
+
+/// Checks that every import [bound to a path] matches an import in the module's import `section`.
+///
+/// [bound to a path]: crate::Translation::bind_import()
+pub(in crate::translation) fn check_bound_imports(
+    section: Option<&wasmparser::ImportSectionReader>,
+    bound_imports: &[(&str, &str, &str)],
+) -> crate::Result<()> {
+    let mut imports = Vec::new();
+    if let Some(section) = section {
+        for result in section.clone() {
+            let import = result?;
+            imports.push((import.module, import.name));
+        }
+    }
+
+    for (module, name, path) in bound_imports {
+        if !imports.contains(&(*module, *name)) {
+            anyhow::bail!(
+                "cannot bind import {name:?} from {module:?} to {path}, the module has no such import"
+            );
+        }
+    }
+
+    Ok(())
+}
 
 pub(in crate::translation) fn write(
     buffer_pool: &crate::buffer::Pool,
     section: wasmparser::ImportSectionReader,
     types: &wasmparser::types::Types,
-    debug_level: crate::DebugInfo,
+    options: Options<'_>,
 ) -> crate::Result<crate::translation::GeneratedLines> {
+    let Options {
+        debug_info: debug_level,
+        async_imports,
+        import_caller,
+        bound_imports,
+        dylink,
+        runtime_linker,
+        thread_safe,
+    } = options;
+
+    if runtime_linker && import_caller {
+        anyhow::bail!(
+            "passing the caller to imported functions is not supported with the runtime linker"
+        );
+    }
+
     let mut impl_out = crate::buffer::Writer::new(buffer_pool);
+    let mut field_out = crate::buffer::Writer::new(buffer_pool);
     let mut init_out = crate::buffer::Writer::new(buffer_pool);
 
     impl_out.write_str("    // Imports\n");
 
     let mut name_map = indexmap::IndexMap::<_, indexmap::IndexSet<_>>::new();
 
+    // Contains the methods of the trait generated for each import module namespace
+    let mut traits = indexmap::IndexMap::<&str, crate::buffer::Writer>::new();
+
     let mut function_index = 0u32;
     let mut memory_index = 0u32;
     let mut global_index = 0u32;
@@ -38,10 +157,74 @@ pub(in crate::translation) fn write(
             }
         }
 
-        impl_out.write_str("    fn ");
+        let is_async = matches!(import.ty, ImportKind::Func(_))
+            && async_imports.contains(&(import.module, import.name));
+
+        impl_out.write_str(if is_async { "    async fn " } else { "    fn " });
 
         let import_module = crate::rust::SafeIdent::from(import.module);
         let import_name = crate::rust::SafeIdent::from(import.name);
+
+        let bound_path = bound_imports
+            .iter()
+            .find(|(module, name, _)| (*module, *name) == (import.module, import.name))
+            .map(|(_, _, path)| *path);
+
+        if bound_path.is_some() && !matches!(import.ty, ImportKind::Func(_)) {
+            anyhow::bail!(
+                "cannot bind import {:?} from {:?}, only imported functions can be bound to a path",
+                import.name,
+                import.module
+            );
+        }
+
+        let linked_global = if dylink && matches!(import.ty, ImportKind::Global(_)) {
+            crate::translation::dylink::LinkedGlobal::from_import(import.module, import.name)
+        } else {
+            None
+        };
+
+        let from_linker = is_from_linker(&import, &options);
+        let is_linkable = match import.ty {
+            ImportKind::Func(_) => true,
+            ImportKind::Global(global_type) => !global_type.mutable,
+            _ => false,
+        };
+
+        if from_linker && !is_linkable {
+            anyhow::bail!(
+                "cannot import {:?} from {:?}, only functions and immutable globals can be \
+                imported with the runtime linker",
+                import.name,
+                import.module
+            );
+        }
+
+        if from_linker && is_async {
+            anyhow::bail!(
+                "cannot import {:?} from {:?}, async functions cannot be imported with the runtime linker",
+                import.name,
+                import.module
+            );
+        }
+
+        // Bound imports, linked globals, and functions from the runtime linker are not provided by
+        // the embedder's imports, so they are excluded from the traits
+        let trait_out = if bound_path.is_none() && linked_global.is_none() && !from_linker {
+            let trait_out = traits
+                .entry(import.module)
+                .or_insert_with(|| crate::buffer::Writer::new(buffer_pool));
+
+            let _ = write!(
+                trait_out,
+                "    {}fn {import_name}",
+                if is_async { "async " } else { "" }
+            );
+
+            Some(trait_out)
+        } else {
+            None
+        };
         match import.ty {
             ImportKind::Func(ty_index) => {
                 let signature = types[types.core_type_at(ty_index).unwrap_sub()].unwrap_func();
@@ -52,82 +235,114 @@ pub(in crate::translation) fn write(
                     crate::translation::display::FuncId(function_index)
                 );
 
-                crate::translation::function::write_definition_signature(&mut impl_out, signature);
-
-                let _ = write!(
-                    impl_out,
-                    " {{ {IMPORTS_OBJECT}.{import_module}().{import_name}("
+                crate::translation::function::write_definition_signature(
+                    &mut impl_out,
+                    signature,
+                    false,
                 );
 
+                if from_linker {
+                    write_linker_function(
+                        import.module,
+                        import.name,
+                        signature,
+                        function_index,
+                        &mut impl_out,
+                        &mut field_out,
+                        &mut init_out,
+                    )?;
+
+                    function_index += 1;
+                    continue;
+                }
+
+                if let Some(trait_out) = trait_out {
+                    trait_out.write_str("(&self");
+                    if import_caller {
+                        trait_out.write_str(", caller: &super::Instance");
+                    }
+
+                    for (i, ty) in (0u32..).zip(signature.params()) {
+                        let _ = write!(
+                            trait_out,
+                            ", {}: {}",
+                            crate::translation::display::LocalId(i),
+                            crate::translation::display::ValType(*ty)
+                        );
+                    }
+
+                    trait_out.write_str(") -> ");
+                    crate::translation::function::write_result_type(trait_out, signature);
+                    trait_out.write_str(";\n");
+                }
+
+                if let Some(path) = bound_path {
+                    let _ = write!(impl_out, " {{ {path}(");
+                } else {
+                    let _ = write!(
+                        impl_out,
+                        " {{ {IMPORTS_OBJECT}.{import_module}().{import_name}("
+                    );
+                }
+
+                if import_caller {
+                    impl_out.write_str("self");
+                }
+
                 let param_count = u32::try_from(signature.params().len()).unwrap();
                 for i in 0..param_count {
-                    if i > 0 {
+                    if i > 0 || import_caller {
                         impl_out.write_str(", ");
                     }
 
                     let _ = write!(impl_out, "{}", crate::translation::display::LocalId(i));
                 }
 
-                impl_out.write_str(") }\n");
+                impl_out.write_str(if is_async { ").await }\n" } else { ") }\n" });
 
                 function_index += 1;
             }
             ImportKind::Memory(mem_type) => {
-                init_out.write_str("      {\n        ");
+                let id = crate::translation::display::MemId(memory_index);
 
-                // Emit code to check imported memory against limits
+                // The imported memory is used to check its limits and to write data segments
                 let _ = writeln!(
                     init_out,
-                    "let import = embedder.imports().{import_module}().{import_name}();"
+                    "      let {id} = embedder.imports().{import_module}().{import_name}();"
                 );
 
-                init_out
-                    .write_str("        let min = embedder::rt::memory::Memory32::size(import);\n");
-
-                let _ = writeln!(init_out, "        if min < {} {{", mem_type.initial,);
-
+                // Emit code to check imported memory against limits
                 let _ = writeln!(
                     init_out,
-                    "          return Err({}::trap(&embedder, {}::MemoryLimitsCheck {{",
-                    crate::translation::function::TRAP_TRAIT,
-                    crate::translation::function::TRAP_CODE,
+                    "      {{\n        let min = embedder::rt::memory::Memory32::size({id});"
                 );
 
-                let _ = writeln!(init_out, "            memory: {memory_index},",);
-
-                let _ = writeln!(
-                    init_out,
-                    "            limits: {LIMITS_ENUM}::Minimum {{ expected: {}, actual: min }},",
-                    mem_type.initial
+                let _ = writeln!(init_out, "        if min < {} {{", mem_type.initial);
+                write_memory_limits_error(
+                    &mut init_out,
+                    &import,
+                    format_args!("Minimum {{ expected: {}, actual: min }}", mem_type.initial),
                 );
 
-                init_out.write_str("          }, None));\n        }\n");
-
                 if let Some(maximum) = mem_type.maximum {
-                    init_out.write_str(
-                        "        let max = embedder::rt::memory::Memory32::limit(import);\n",
-                    );
-                    let _ = writeln!(init_out, "        if max > {maximum} {{",);
-
                     let _ = writeln!(
                         init_out,
-                        "          return Err({}::trap(&embedder, {}::MemoryLimitsCheck {{",
-                        crate::translation::function::TRAP_TRAIT,
-                        crate::translation::function::TRAP_CODE,
+                        "        let max = embedder::rt::memory::Memory32::limit({id});"
                     );
-
-                    let _ = writeln!(init_out, "            memory: {memory_index},",);
-
-                    let _ = writeln!(
-                        init_out,
-                        "            limits: {LIMITS_ENUM}::Maximum {{ expected: {maximum}, actual: max }},",
+                    let _ = writeln!(init_out, "        if max > {maximum} {{");
+                    write_memory_limits_error(
+                        &mut init_out,
+                        &import,
+                        format_args!("Maximum {{ expected: {maximum}, actual: max }}"),
                     );
-
-                    init_out.write_str("          }, None));\n        }\n");
                 }
 
                 init_out.write_str("      }\n");
 
+                if let Some(trait_out) = trait_out {
+                    let _ = writeln!(trait_out, "(&self) -> &embedder::Memory{memory_index};");
+                }
+
                 // Write the method used to access the memory
                 let _ = writeln!(
                     impl_out,
@@ -145,6 +360,55 @@ pub(in crate::translation) fn write(
                 memory_index += 1;
             }
             ImportKind::Global(global_type) => {
+                if from_linker {
+                    write_linker_global(
+                        import.module,
+                        import.name,
+                        global_type,
+                        global_index,
+                        &mut impl_out,
+                        &mut field_out,
+                        &mut init_out,
+                    );
+
+                    global_index += 1;
+                    continue;
+                }
+
+                if let Some(linked) = linked_global {
+                    write_linked_global(
+                        linked,
+                        global_type,
+                        global_index,
+                        &mut impl_out,
+                        &mut field_out,
+                        &mut init_out,
+                        thread_safe,
+                    )
+                    .with_context(|| {
+                        format!(
+                            "could not link global {:?} from {:?}",
+                            import.name, import.module
+                        )
+                    })?;
+
+                    global_index += 1;
+                    continue;
+                }
+
+                if global_type.mutable {
+                    crate::translation::global::check_cell_value_type(
+                        global_type.content_type,
+                        thread_safe,
+                    )
+                    .with_context(|| {
+                        format!(
+                            "could not import global {:?} from {:?}",
+                            import.name, import.module
+                        )
+                    })?;
+                }
+
                 let _ = write!(
                     impl_out,
                     "{}(&self) -> &",
@@ -152,7 +416,11 @@ pub(in crate::translation) fn write(
                 );
 
                 if global_type.mutable {
-                    impl_out.write_str("embedder::rt::global::Global<");
+                    let _ = write!(
+                        impl_out,
+                        "{}<",
+                        crate::translation::global::cell_type(thread_safe)
+                    );
                 }
 
                 let _ = write!(
@@ -170,17 +438,255 @@ pub(in crate::translation) fn write(
                     " {{ {IMPORTS_OBJECT}.{import_module}().{import_name}() }}"
                 );
 
+                if let Some(trait_out) = trait_out {
+                    trait_out.write_str("(&self) -> &");
+                    if global_type.mutable {
+                        let _ = write!(
+                            trait_out,
+                            "{}<",
+                            crate::translation::global::cell_type(thread_safe)
+                        );
+                    }
+
+                    let _ = write!(
+                        trait_out,
+                        "{}",
+                        crate::translation::display::ValType(global_type.content_type)
+                    );
+
+                    if global_type.mutable {
+                        trait_out.write_str(">");
+                    }
+
+                    trait_out.write_str(";\n");
+                }
+
                 global_index += 1;
             }
-            bad => todo!("importing {bad:?} is not yet supported"),
+            ImportKind::Table(_) if dylink => anyhow::bail!(
+                "cannot import the table {:?} from {:?}, tables are not yet supported, so \
+                dynamically linked modules cannot share a table with the main module",
+                import.name,
+                import.module
+            ),
+            ImportKind::Table(_) => anyhow::bail!(
+                "cannot import the table {:?} from {:?}, tables are not yet supported",
+                import.name,
+                import.module
+            ),
+            ImportKind::Tag(_) => anyhow::bail!(
+                "cannot import the tag {:?} from {:?}, exception handling is not yet supported",
+                import.name,
+                import.module
+            ),
         }
     }
 
     impl_out.write_str("\n");
 
     Ok(crate::translation::GeneratedLines {
+        items: write_traits(buffer_pool, traits),
+        fields: field_out.finish(),
         impls: impl_out.finish(),
         inits: init_out.finish(),
-        ..Default::default()
     })
 }
+
+/// Writes a field containing the definition of an imported function, which is looked up in the
+/// `wasm2rs_rt::linker::Linker` when the module is instantiated.
+fn write_linker_function(
+    module: &str,
+    name: &str,
+    signature: &wasmparser::FuncType,
+    function_index: u32,
+    impl_out: &mut crate::buffer::Writer,
+    field_out: &mut crate::buffer::Writer,
+    init_out: &mut crate::buffer::Writer,
+) -> crate::Result<()> {
+    // Limited by the `call_` helpers of `FuncRef`
+    const MAX_PARAMS: usize = 9;
+
+    let param_count = signature.params().len();
+    if param_count > MAX_PARAMS {
+        anyhow::bail!(
+            "cannot import {name:?} from {module:?} with the runtime linker, functions with more \
+            than {MAX_PARAMS} parameters are not supported"
+        );
+    }
+
+    if signature.params().contains(&wasmparser::ValType::FUNCREF) {
+        anyhow::bail!(
+            "cannot import {name:?} from {module:?} with the runtime linker, functions with \
+            funcref parameters are not supported"
+        );
+    }
+
+    let id = crate::translation::display::FuncRefImportId(function_index);
+
+    let _ = write!(impl_out, " {{ self.{id}.call_{param_count}(");
+    for i in 0..u32::try_from(param_count).unwrap() {
+        let _ = write!(impl_out, "{}, ", crate::translation::display::LocalId(i));
+    }
+
+    impl_out.write_str("&self.embedder) }\n");
+
+    let _ = writeln!(field_out, "    {id}: embedder::rt::linker::LinkedFuncRef,");
+
+    // The signature is checked by casting to the function pointer type used by `FuncRef`
+    let _ = write!(
+        init_out,
+        "      let {id} = {LINKER}\n        \
+        .function::<unsafe fn(&embedder::rt::func_ref::RawFuncRefData"
+    );
+
+    for ty in signature.params() {
+        let _ = write!(init_out, ", {}", crate::translation::display::ValType(*ty));
+    }
+
+    init_out.write_str(") -> ");
+    crate::translation::function::write_result_type(init_out, signature);
+    let _ = writeln!(init_out, ">({module:?}, {name:?})?;");
+    Ok(())
+}
+
+/// Writes a field containing the value of an immutable global, which is looked up in the
+/// `wasm2rs_rt::linker::Linker` when the module is instantiated.
+fn write_linker_global(
+    module: &str,
+    name: &str,
+    global_type: wasmparser::GlobalType,
+    global_index: u32,
+    impl_out: &mut crate::buffer::Writer,
+    field_out: &mut crate::buffer::Writer,
+    init_out: &mut crate::buffer::Writer,
+) {
+    let id = crate::translation::display::GlobalId(global_index);
+    let ty = crate::translation::display::ValType(global_type.content_type);
+
+    let _ = writeln!(impl_out, "{id}(&self) -> &{ty} {{ &self.{id} }}");
+    let _ = writeln!(field_out, "    {id}: {ty},");
+    let _ = writeln!(
+        init_out,
+        "      let {id} = {LINKER}.global::<{ty}>({module:?}, {name:?})?;"
+    );
+}
+
+/// Writes a statement returning a link error when an imported memory does not have the expected
+/// limits.
+fn write_memory_limits_error(
+    init_out: &mut crate::buffer::Writer,
+    import: &wasmparser::Import,
+    limits: std::fmt::Arguments,
+) {
+    let _ = writeln!(
+        init_out,
+        "          return Err(embedder::rt::linker::LinkError::new({:?}, {:?}, \
+        embedder::rt::linker::LinkErrorKind::MemoryLimits({LIMITS_ENUM}::{limits})).into());\n        \
+        }}",
+        import.module, import.name,
+    );
+}
+
+/// Writes a field containing the value of a global imported by a dynamically linked module, which
+/// is initialized using the `wasm2rs_rt::dylink::Linking` passed to the `instantiate` function.
+fn write_linked_global(
+    linked: crate::translation::dylink::LinkedGlobal,
+    global_type: wasmparser::GlobalType,
+    global_index: u32,
+    impl_out: &mut crate::buffer::Writer,
+    field_out: &mut crate::buffer::Writer,
+    init_out: &mut crate::buffer::Writer,
+    thread_safe: bool,
+) -> crate::Result<()> {
+    if global_type.content_type != wasmparser::ValType::I32 {
+        anyhow::bail!(
+            "expected global of type i32, but got {:?}",
+            global_type.content_type
+        );
+    }
+
+    let id = crate::translation::display::GlobalId(global_index);
+    let cell_type = crate::translation::global::cell_type(thread_safe);
+    let global_ty = if global_type.mutable {
+        format!("{cell_type}<i32>")
+    } else {
+        String::from("i32")
+    };
+
+    let _ = writeln!(impl_out, "{id}(&self) -> &{global_ty} {{ &self.{id} }}");
+    let _ = writeln!(field_out, "    {id}: {global_ty},");
+
+    let _ = write!(init_out, "      let {id} = ");
+    if global_type.mutable {
+        let _ = write!(init_out, "{cell_type}::new(");
+    }
+
+    linked.write_value(init_out);
+
+    if global_type.mutable {
+        init_out.write_str(")");
+    }
+
+    init_out.write_str(";\n");
+    Ok(())
+}
+
+/// Writes the traits describing the imports that the embedder must provide.
+///
+/// Each import module namespace has a trait in the generated `imports` module containing its
+/// imported functions, memories, and globals, while the `Imports` trait provides access to each
+/// namespace. Embedders can implement these traits to ensure their imports have the exact
+/// signatures that the generated code expects.
+fn write_traits(
+    buffer_pool: &crate::buffer::Pool,
+    traits: indexmap::IndexMap<&str, crate::buffer::Writer>,
+) -> Vec<bytes::BytesMut> {
+    let mut out = crate::buffer::Writer::new(buffer_pool);
+
+    out.write_str(
+        "\n  /// Traits for each module namespace of the WebAssembly module's imports.\n  \
+        $vis mod imports {\n    #[allow(unused_imports)]\n    use super::embedder;\n",
+    );
+
+    let mut accessors = Vec::with_capacity(traits.len());
+    for (module, methods) in traits {
+        let name = crate::rust::SafeIdent::from(module);
+        let _ = writeln!(
+            out,
+            "\n  /// Imports from the {:?} module namespace.\n  pub trait {name} {{",
+            module
+        );
+
+        let methods = methods.finish();
+        for bytes in methods.iter() {
+            out.write(bytes);
+        }
+
+        buffer_pool.return_buffers_many(methods);
+
+        out.write_str("  }\n");
+        accessors.push(name);
+    }
+
+    out.write_str("  }\n\n");
+
+    // Allows calling the trait methods
+    for name in accessors.iter() {
+        let _ = writeln!(
+            out,
+            "  #[allow(unused_imports)]\n  use imports::{name} as _;"
+        );
+    }
+
+    out.write_str("\n  /// Provides access to each module namespace of the WebAssembly module's imports.\n  $vis trait Imports {\n");
+
+    for name in accessors {
+        let _ = writeln!(
+            out,
+            "    #[allow(missing_docs)]\n    type {name}: imports::{name} + ?::core::marker::Sized;\n    fn {name}(&self) -> &Self::{name};",
+        );
+    }
+
+    out.write_str("  }\n");
+    out.finish()
+}
@@ -0,0 +1,515 @@
+//! Links multiple WebAssembly modules together during translation.
+
+use crate::rust::{Ident, SafeIdent};
+use std::fmt::Write as _;
+
+/// The imports and exports of a WebAssembly module.
+struct Interface<'a> {
+    imports: Vec<wasmparser::Import<'a>>,
+    exports: Vec<wasmparser::Export<'a>>,
+    types: wasmparser::types::Types,
+    is_dylink: bool,
+}
+
+impl<'a> Interface<'a> {
+    fn parse(wasm: &'a [u8], features: &wasmparser::WasmFeatures) -> crate::Result<Self> {
+        let mut imports = Vec::new();
+        let mut exports = Vec::new();
+        let mut is_dylink = false;
+        for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+            match payload? {
+                wasmparser::Payload::ImportSection(section) => {
+                    for import in section {
+                        imports.push(import?);
+                    }
+                }
+                wasmparser::Payload::ExportSection(section) => {
+                    for export in section {
+                        exports.push(export?);
+                    }
+                }
+                wasmparser::Payload::CustomSection(section) if section.name() == "dylink.0" => {
+                    is_dylink = true;
+                }
+                _ => (),
+            }
+        }
+
+        Ok(Self {
+            imports,
+            exports,
+            types: wasmparser::Validator::new_with_features(*features).validate_all(wasm)?,
+            is_dylink,
+        })
+    }
+
+    fn function_type(&self, id: wasmparser::types::CoreTypeId) -> &wasmparser::FuncType {
+        crate::translation::function::get_function_type(self.types.get(id).expect("bad type id"))
+    }
+}
+
+/// Formats a function signature in the WebAssembly text format.
+struct DisplayFuncType<'a>(&'a wasmparser::FuncType);
+
+impl std::fmt::Display for DisplayFuncType<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("(func")?;
+
+        if !self.0.params().is_empty() {
+            f.write_str(" (param")?;
+            for param in self.0.params() {
+                write!(f, " {param}")?;
+            }
+            f.write_str(")")?;
+        }
+
+        if !self.0.results().is_empty() {
+            f.write_str(" (result")?;
+            for result in self.0.results() {
+                write!(f, " {result}")?;
+            }
+            f.write_str(")")?;
+        }
+
+        f.write_str(")")
+    }
+}
+
+/// Formats a global type in the WebAssembly text format.
+struct DisplayGlobalType(wasmparser::GlobalType);
+
+impl std::fmt::Display for DisplayGlobalType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.mutable {
+            write!(f, "(global (mut {}))", self.0.content_type)
+        } else {
+            write!(f, "(global {})", self.0.content_type)
+        }
+    }
+}
+
+/// Formats a memory type in the WebAssembly text format.
+struct DisplayMemoryType(wasmparser::MemoryType);
+
+impl std::fmt::Display for DisplayMemoryType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("(memory")?;
+        if self.0.memory64 {
+            f.write_str(" i64")?;
+        }
+
+        write!(f, " {}", self.0.initial)?;
+        if let Some(maximum) = self.0.maximum {
+            write!(f, " {maximum}")?;
+        }
+
+        if self.0.shared {
+            f.write_str(" shared")?;
+        }
+
+        f.write_str(")")
+    }
+}
+
+/// Checks that an exported memory of type `actual` can satisfy an import of type `expected`.
+fn memory_matches(expected: &wasmparser::MemoryType, actual: &wasmparser::MemoryType) -> bool {
+    let maximum_matches = match (expected.maximum, actual.maximum) {
+        (None, _) => true,
+        (Some(expected), Some(actual)) => actual <= expected,
+        (Some(_), None) => false,
+    };
+
+    expected.memory64 == actual.memory64
+        && expected.shared == actual.shared
+        && actual.initial >= expected.initial
+        && maximum_matches
+}
+
+fn import_kind_name(ty: wasmparser::TypeRef) -> &'static str {
+    match ty {
+        wasmparser::TypeRef::Func(_) => "a function",
+        wasmparser::TypeRef::Table(_) => "a table",
+        wasmparser::TypeRef::Memory(_) => "a memory",
+        wasmparser::TypeRef::Global(_) => "a global",
+        wasmparser::TypeRef::Tag(_) => "a tag",
+    }
+}
+
+fn export_kind_name(kind: wasmparser::ExternalKind) -> &'static str {
+    match kind {
+        wasmparser::ExternalKind::Func => "a function",
+        wasmparser::ExternalKind::Table => "a table",
+        wasmparser::ExternalKind::Memory => "a memory",
+        wasmparser::ExternalKind::Global => "a global",
+        wasmparser::ExternalKind::Tag => "a tag",
+    }
+}
+
+struct Module<'a> {
+    name: Ident<'a>,
+    wasm: &'a [u8],
+}
+
+/// Translates several [WebAssembly binary modules] into a single [Rust source file], resolving
+/// the imports of each module that refer to the exports of another.
+///
+/// Each module is given an instance name. Any import whose module namespace is the name of
+/// another instance is satisfied by that instance's export of the same name, resulting in direct
+/// calls between the instances, and allowing memories and globals to be shared. All other imports
+/// are provided by a single host type, which the generated imports dereference to.
+///
+/// The generated macro takes the path to the host type. It defines a Rust module containing a
+/// module for each instance, and an `Instances` struct that instantiates each module in an order
+/// where every instance is instantiated after the instances it imports from:
+///
+/// ```ignore
+/// linked!(pub mod app use super::Host);
+///
+/// let instances = app::Instances::instantiate(Host::default())?;
+/// instances.main.run()?;
+/// ```
+///
+/// Instances are shared with the `wasm2rs_rt::embedder::Shared` type, which requires the `alloc`
+/// feature of `wasm2rs_rt`. Cyclic dependencies between the modules are not supported.
+///
+/// [WebAssembly binary modules]: https://webassembly.github.io/spec/core/binary/index.html
+/// [Rust source file]: https://doc.rust-lang.org/reference/crates-and-source-files.html
+pub struct Linker<'a> {
+    translation: &'a crate::Translation<'a>,
+    modules: Vec<Module<'a>>,
+}
+
+impl<'a> Linker<'a> {
+    /// Creates a new [`Linker`], using the given options to translate each module.
+    ///
+    /// The [`Translation::generated_macro_name()`] is used as the name of the macro containing the
+    /// linked modules.
+    ///
+    /// [`Translation::generated_macro_name()`]: crate::Translation::generated_macro_name()
+    pub fn new(translation: &'a crate::Translation<'a>) -> Self {
+        Self {
+            translation,
+            modules: Vec::new(),
+        }
+    }
+
+    /// Adds an in-memory WebAssembly binary module with the given instance name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `name` is not a valid Rust identifier, or if a module with the same
+    /// name was already added.
+    pub fn module(&mut self, name: &'a str, wasm: &'a [u8]) -> crate::Result<&mut Self> {
+        let Some(name) = Ident::new(name) else {
+            anyhow::bail!("instance name {name:?} is not a valid identifier");
+        };
+
+        if self.modules.iter().any(|module| module.name == name) {
+            anyhow::bail!("an instance named {name} was already added");
+        }
+
+        self.modules.push(Module { name, wasm });
+        Ok(self)
+    }
+
+    /// Determines the order in which the modules are instantiated, and the instances that each
+    /// module imports from.
+    fn resolve(
+        &self,
+        interfaces: &[Interface<'a>],
+    ) -> crate::Result<(Vec<usize>, Vec<Vec<usize>>)> {
+        let mut dependencies = Vec::with_capacity(self.modules.len());
+        for (module, interface) in self.modules.iter().zip(interfaces) {
+            if interface.is_dylink {
+                anyhow::bail!(
+                    "{} is a dynamically linked module, which cannot be linked during translation",
+                    module.name.name()
+                );
+            }
+
+            let mut module_dependencies = Vec::new();
+            for import in interface.imports.iter() {
+                let Some(exporter) = self
+                    .modules
+                    .iter()
+                    .position(|other| other.name.name() == import.module)
+                else {
+                    continue;
+                };
+
+                let export = interfaces[exporter]
+                    .exports
+                    .iter()
+                    .find(|export| export.name == import.name)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "{} imports {:?} from {}, which does not export it",
+                            module.name.name(),
+                            import.name,
+                            import.module
+                        )
+                    })?;
+
+                let exporter_interface = &interfaces[exporter];
+                let mismatch = match (import.ty, export.kind) {
+                    (wasmparser::TypeRef::Func(type_index), wasmparser::ExternalKind::Func) => {
+                        let expected = interface
+                            .function_type(interface.types.core_type_at(type_index).unwrap_sub());
+                        let actual = exporter_interface
+                            .function_type(exporter_interface.types.core_function_at(export.index));
+
+                        (expected != actual).then(|| {
+                            (
+                                DisplayFuncType(expected).to_string(),
+                                DisplayFuncType(actual).to_string(),
+                            )
+                        })
+                    }
+                    (wasmparser::TypeRef::Memory(expected), wasmparser::ExternalKind::Memory) => {
+                        let actual = exporter_interface.types.memory_at(export.index);
+                        (!memory_matches(&expected, &actual)).then(|| {
+                            (
+                                DisplayMemoryType(expected).to_string(),
+                                DisplayMemoryType(actual).to_string(),
+                            )
+                        })
+                    }
+                    (wasmparser::TypeRef::Global(expected), wasmparser::ExternalKind::Global) => {
+                        let actual = exporter_interface.types.global_at(export.index);
+                        (expected != actual).then(|| {
+                            (
+                                DisplayGlobalType(expected).to_string(),
+                                DisplayGlobalType(actual).to_string(),
+                            )
+                        })
+                    }
+                    (wasmparser::TypeRef::Table(_), _) => {
+                        anyhow::bail!(
+                            "{} imports the table {:?} from {}, but tables cannot be shared \
+                            between linked instances",
+                            module.name.name(),
+                            import.name,
+                            import.module
+                        );
+                    }
+                    (wasmparser::TypeRef::Tag(_), _) => {
+                        anyhow::bail!(
+                            "{} imports the tag {:?} from {}, but tags cannot be shared between \
+                            linked instances",
+                            module.name.name(),
+                            import.name,
+                            import.module
+                        );
+                    }
+                    (import_type, export_kind) => Some((
+                        import_kind_name(import_type).to_string(),
+                        export_kind_name(export_kind).to_string(),
+                    )),
+                };
+
+                if let Some((expected, actual)) = mismatch {
+                    anyhow::bail!(
+                        "{} imports {:?} from {} as {expected}, but it is exported as {actual}",
+                        module.name.name(),
+                        import.name,
+                        import.module,
+                    );
+                }
+
+                if !module_dependencies.contains(&exporter) {
+                    module_dependencies.push(exporter);
+                }
+            }
+
+            dependencies.push(module_dependencies);
+        }
+
+        // Sort the modules so that each is instantiated after its dependencies
+        let mut order = Vec::with_capacity(self.modules.len());
+        let mut added = vec![false; self.modules.len()];
+        while order.len() < self.modules.len() {
+            let next = (0..self.modules.len()).find(|&index| {
+                !added[index]
+                    && dependencies[index]
+                        .iter()
+                        .all(|&dependency| added[dependency])
+            });
+
+            let Some(next) = next else {
+                anyhow::bail!("linked modules have a cyclic dependency");
+            };
+
+            added[next] = true;
+            order.push(next);
+        }
+
+        Ok((order, dependencies))
+    }
+
+    /// Translates and links all of the modules, and [`Write`]s the resulting Rust source code to
+    /// the given output.
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if any module could not be translated, if an import refers to an
+    /// export of another instance that does not exist or whose type does not match, if the modules
+    /// have a cyclic dependency, or if an error occured while writing to the `output`.
+    ///
+    /// [`Write`]: std::io::Write
+    pub fn link(&self, output: &mut dyn std::io::Write) -> crate::Result<()> {
+        use anyhow::Context;
+
+        if self.translation.import_caller {
+            anyhow::bail!("passing the caller to imported functions is not supported when linking");
+        }
+
+        if self.translation.runtime_linker {
+            anyhow::bail!("the runtime linker cannot be used when linking during translation");
+        }
+
+        let interfaces = self
+            .modules
+            .iter()
+            .map(|module| Interface::parse(module.wasm, self.translation.wasm_features))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        let (order, dependencies) = self.resolve(&interfaces)?;
+        let macro_name = self.translation.generated_macro_name;
+
+        let instance_macro_name = |module: &Module| {
+            let macro_name = macro_name.to_string();
+            let macro_name = macro_name.strip_prefix("r#").unwrap_or(&macro_name);
+            format!("{macro_name}_{}", module.name.name())
+        };
+
+        for module in self.modules.iter() {
+            let instance_macro_name = instance_macro_name(module);
+            self.translation
+                .translate_with_macro_name(
+                    module.wasm,
+                    SafeIdent::from(instance_macro_name.as_str()),
+                    output,
+                )
+                .with_context(|| format!("failed to translate {}", module.name))?;
+        }
+
+        const SHARED: &str = "::wasm2rs_rt::embedder::Shared";
+
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            "macro_rules! {macro_name} {{\n    \
+                ($vis:vis mod $module:ident use $(:: $host_start:ident ::)? $($host_more:ident)::+) => {{\n\
+                #[allow(non_camel_case_types)]\n\
+                $vis mod $module {{\n  \
+                /// The type that provides the imports that are not satisfied by a linked module.\n  \
+                pub type Host = $(::$host_start::)? $($host_more)::+;\n",
+        );
+
+        for (module, dependencies) in self.modules.iter().zip(dependencies.iter()) {
+            let ident = module.name;
+            let name = ident.name();
+            let _ = write!(
+                out,
+                "\n  /// The imports of the [`{name}`] instance.\n  \
+                #[derive(Debug)]\n  \
+                pub struct {name}_imports {{\n    \
+                host: {SHARED}<Host>,\n",
+            );
+
+            for &dependency in dependencies.iter() {
+                let dependency = self.modules[dependency].name;
+                let _ = writeln!(out, "    {dependency}: {SHARED}<{dependency}::Instance>,");
+            }
+
+            let _ = write!(
+                out,
+                "  }}\n\n  \
+                impl ::core::ops::Deref for {name}_imports {{\n    \
+                type Target = Host;\n\n    \
+                fn deref(&self) -> &Host {{ &self.host }}\n  \
+                }}\n\n  \
+                impl {name}_imports {{\n",
+            );
+
+            for &dependency in dependencies.iter() {
+                let dependency = self.modules[dependency].name;
+                let _ = writeln!(
+                    out,
+                    "    pub fn {}(&self) -> &{dependency}::Instance {{ &self.{dependency} }}",
+                    SafeIdent::from(dependency.name()),
+                );
+            }
+
+            let _ = write!(
+                out,
+                "  }}\n\n  \
+                ::wasm2rs_rt::embedder_with_import! {{ pub mod {name}_embedder({name}_imports) }}\n\n  \
+                {}!{{pub mod {ident} use super::{name}_embedder}}\n",
+                instance_macro_name(module),
+            );
+        }
+
+        out.push_str("\n  /// Contains all of the linked instances.\n  #[derive(Debug)]\n  #[non_exhaustive]\n  pub struct Instances {\n");
+
+        for module in self.modules.iter() {
+            let _ = writeln!(out, "    pub {0}: {SHARED}<{0}::Instance>,", module.name);
+        }
+
+        let _ = writeln!(
+            out,
+            "  }}\n\n  \
+            impl Instances {{\n    \
+            /// Instantiates each linked module, in an order where every instance is instantiated after the instances it imports from.\n    \
+            pub fn instantiate(host: Host) -> ::core::result::Result<Self, ::wasm2rs_rt::linker::InstantiationError> {{\n      \
+            let host = {SHARED}::new(host);",
+        );
+
+        for &index in order.iter() {
+            let name = self.modules[index].name;
+            let _ = write!(
+                out,
+                "      let {name} = {SHARED}::new({name}::Instance::instantiate({0}_embedder::State::new({0}_imports {{ host: {SHARED}::clone(&host)",
+                name.name(),
+            );
+
+            for &dependency in dependencies[index].iter() {
+                let dependency = self.modules[dependency].name;
+                let _ = write!(out, ", {dependency}: {SHARED}::clone(&{dependency})");
+            }
+
+            out.push_str(" }))?);\n");
+        }
+
+        out.push_str("      Ok(Self {");
+        for (i, module) in self.modules.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+
+            let _ = write!(out, " {}", module.name);
+        }
+
+        out.push_str(" })\n    }\n  }\n}\n    };\n}\n");
+
+        output.write_all(out.as_bytes())?;
+        output.flush()?;
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for Linker<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Linker")
+            .field("translation", self.translation)
+            .field(
+                "modules",
+                &self
+                    .modules
+                    .iter()
+                    .map(|module| module.name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
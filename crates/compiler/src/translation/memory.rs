@@ -4,6 +4,7 @@ pub fn write(
     buffer_pool: &crate::buffer::Pool,
     section: wasmparser::MemorySectionReader,
     start_index: u32,
+    initialized: Option<&crate::translation::pre_init::Initialized>,
 ) -> crate::Result<crate::translation::GeneratedLines> {
     let mut field_out = crate::buffer::Writer::new(buffer_pool);
     let mut init_out = crate::buffer::Writer::new(buffer_pool);
@@ -11,13 +12,20 @@ pub fn write(
     for (result, index) in section.into_iter().zip(start_index..) {
         let memory = result?;
         let id = crate::translation::display::MemId(index);
+
+        // Pre-initialized memories may have grown
+        let minimum = match initialized {
+            Some(initialized) => u64::from(initialized.memory_size((index - start_index) as usize)),
+            None => memory.initial,
+        };
+
         let _ = writeln!(field_out, "    {id}: embedder::Memory{index},",);
 
         let _ = writeln!(
             init_out,
             "      let {id} = embedder.initialize{id}::<{}, {}u32, {}u32>()?;",
             id.0,
-            memory.initial,
+            minimum,
             memory.maximum.unwrap_or(u32::MAX.into())
         );
 
@@ -0,0 +1,1021 @@
+//! A reference interpreter used to pre-initialize WebAssembly modules at translation time.
+//!
+//! Pre-initialization instantiates the module, runs its start function followed by an
+//! initialization export, and records the resulting contents of the memories and the values of
+//! the globals defined by the module. These are then emitted in place of the module's data
+//! segments and global initializers, so that the generated `instantiate` function produces an
+//! instance that is already initialized.
+//!
+//! The interpreter supports the same instructions that can be translated. Since the embedder is
+//! not available at translation time, calling imported functions, reading imported globals, and
+//! accessing imported memories are not supported.
+
+use anyhow::Context;
+use std::fmt::Write;
+
+/// Maximum number of instructions that can be executed during pre-initialization, which ensures
+/// that translation of a module with an infinite loop eventually fails.
+const MAX_STEPS: u64 = 1 << 30;
+
+/// Maximum number of nested function calls during pre-initialization.
+const MAX_CALL_DEPTH: u32 = 512;
+
+const PAGE_SIZE: usize = 65536;
+
+/// Data segments emitted for the contents of memories are split at runs of zero bytes that are
+/// at least this long.
+const MIN_ZERO_GAP: usize = 32;
+
+/// The state of an instance of a module after pre-initialization.
+pub(in crate::translation) struct Initialized {
+    /// The contents of each memory defined by the module.
+    pub memories: Vec<Vec<u8>>,
+    /// The values of each global defined by the module.
+    pub globals: Vec<u64>,
+    /// The number of data segments defined by the module.
+    pub data_count: u32,
+}
+
+impl Initialized {
+    /// Gets the number of pages in a memory defined by the module, given its index in the memory
+    /// section.
+    pub fn memory_size(&self, defined_index: usize) -> u32 {
+        u32::try_from(self.memories[defined_index].len() / PAGE_SIZE).unwrap_or(u32::MAX)
+    }
+}
+
+// Values on the operand stack are stored as their bits, since validation ensures that every
+// instruction is given operands of the correct type.
+
+#[allow(clippy::cast_possible_truncation)]
+fn to_i32(value: u64) -> i32 {
+    value as u32 as i32
+}
+
+fn to_u32(value: u64) -> u32 {
+    to_i32(value) as u32
+}
+
+fn from_i32(value: i32) -> u64 {
+    u64::from(value as u32)
+}
+
+fn from_bool(value: bool) -> u64 {
+    u64::from(value)
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn to_f32(value: u64) -> f32 {
+    f32::from_bits(value as u32)
+}
+
+fn from_f32(value: f32) -> u64 {
+    u64::from(value.to_bits())
+}
+
+/// Truncates a float to an integer, checking that the result is within `lower..upper`
+/// (exclusive).
+fn checked_trunc(value: f64, lower: f64, upper: f64) -> crate::Result<f64> {
+    if value.is_nan() {
+        anyhow::bail!("invalid conversion to integer");
+    } else if value > lower && value < upper {
+        Ok(value.trunc())
+    } else {
+        anyhow::bail!("integer overflow");
+    }
+}
+
+/// Writes a Rust expression for a value of the given type.
+pub(in crate::translation) fn write_value(
+    out: &mut crate::buffer::Writer,
+    ty: wasmparser::ValType,
+    bits: u64,
+) {
+    let _ = match ty {
+        wasmparser::ValType::I32 => write!(out, "{}i32", to_i32(bits)),
+        wasmparser::ValType::I64 => write!(out, "{}i64", bits as i64),
+        wasmparser::ValType::F32 => write!(out, "f32::from_bits({:#010X}u32)", to_u32(bits)),
+        wasmparser::ValType::F64 => write!(out, "f64::from_bits({bits:#018X}u64)"),
+        other => unreachable!("pre-initialized globals of type {other} are not supported"),
+    };
+}
+
+struct Function<'a> {
+    params: usize,
+    results: usize,
+    /// The number of local variables, excluding parameters.
+    locals: usize,
+    operators: Vec<wasmparser::Operator<'a>>,
+    /// Maps the index of each `block`, `loop`, `if`, and `else` instruction to the index of the
+    /// matching `end` instruction.
+    ends: std::collections::HashMap<usize, usize>,
+    /// Maps the index of each `if` instruction to the index of the matching `else` instruction.
+    elses: std::collections::HashMap<usize, usize>,
+}
+
+impl<'a> Function<'a> {
+    fn decode(
+        body: &wasmparser::FunctionBody<'a>,
+        signature: &wasmparser::FuncType,
+    ) -> crate::Result<Self> {
+        use wasmparser::Operator;
+
+        let mut locals = 0usize;
+        for result in body.get_locals_reader()? {
+            let (count, _) = result?;
+            locals += count as usize;
+        }
+
+        let mut operators = Vec::new();
+        let mut ends = std::collections::HashMap::new();
+        let mut elses = std::collections::HashMap::new();
+        let mut blocks = Vec::new();
+        let mut reader = body.get_operators_reader()?;
+        while !reader.eof() {
+            let index = operators.len();
+            let op = reader.read()?;
+            match op {
+                Operator::Block { .. } | Operator::Loop { .. } | Operator::If { .. } => {
+                    blocks.push(index)
+                }
+                Operator::Else => {
+                    if let Some(start) = blocks.last() {
+                        elses.insert(*start, index);
+                        ends.insert(index, 0);
+                    }
+                }
+                Operator::End => {
+                    if let Some(start) = blocks.pop() {
+                        ends.insert(start, index);
+                        if let Some(else_index) = elses.get(&start) {
+                            ends.insert(*else_index, index);
+                        }
+                    }
+                }
+                _ => (),
+            }
+
+            operators.push(op);
+        }
+
+        Ok(Self {
+            params: signature.params().len(),
+            results: signature.results().len(),
+            locals,
+            operators,
+            ends,
+            elses,
+        })
+    }
+}
+
+struct Label {
+    is_loop: bool,
+    /// Index of the `block`, `loop`, or `if` instruction.
+    start: usize,
+    /// Index of the `end` instruction.
+    end: usize,
+    /// Height of the operand stack when the block was entered, excluding its parameters.
+    height: usize,
+    /// Number of values passed when branching to the label.
+    arity: usize,
+}
+
+struct Memory {
+    contents: Vec<u8>,
+    /// Maximum number of pages.
+    maximum: u64,
+}
+
+struct Interpreter<'a, 'b> {
+    types: &'b wasmparser::types::Types,
+    imported_functions: Vec<(&'a str, &'a str)>,
+    bodies: &'b [wasmparser::FunctionBody<'a>],
+    functions: Vec<Option<std::rc::Rc<Function<'a>>>>,
+    imported_globals: u32,
+    globals: Vec<u64>,
+    memories: Vec<Memory>,
+    /// The contents of the data segments.
+    data: Vec<&'a [u8]>,
+    steps: u64,
+    depth: u32,
+}
+
+impl<'a> Interpreter<'a, '_> {
+    fn signature(&self, function_index: u32) -> &wasmparser::FuncType {
+        crate::translation::function::get_function_type(
+            self.types
+                .get(self.types.core_function_at(function_index))
+                .expect("bad function type id"),
+        )
+    }
+
+    fn function(&mut self, function_index: u32) -> crate::Result<std::rc::Rc<Function<'a>>> {
+        let defined_index = (function_index as usize) - self.imported_functions.len();
+        if let Some(function) = &self.functions[defined_index] {
+            return Ok(function.clone());
+        }
+
+        let function = std::rc::Rc::new(Function::decode(
+            &self.bodies[defined_index],
+            self.signature(function_index),
+        )?);
+
+        self.functions[defined_index] = Some(function.clone());
+        Ok(function)
+    }
+
+    fn global(&self, global_index: u32) -> crate::Result<&u64> {
+        if global_index < self.imported_globals {
+            anyhow::bail!("cannot read imported global #{global_index} during pre-initialization");
+        }
+
+        Ok(&self.globals[global_index as usize])
+    }
+
+    fn evaluate(&self, expr: &wasmparser::ConstExpr) -> crate::Result<u64> {
+        use wasmparser::Operator;
+
+        let mut stack = Vec::new();
+        let mut ops = expr.get_operators_reader();
+        loop {
+            let op = ops.read()?;
+            let value = match op {
+                Operator::End => break,
+                Operator::I32Const { value } => from_i32(value),
+                Operator::I64Const { value } => value as u64,
+                Operator::F32Const { value } => u64::from(value.bits()),
+                Operator::F64Const { value } => value.bits(),
+                Operator::GlobalGet { global_index } => *self.global(global_index)?,
+                Operator::I32Add | Operator::I32Sub | Operator::I32Mul => {
+                    let b = to_i32(stack.pop().context("missing operand")?);
+                    let a = to_i32(stack.pop().context("missing operand")?);
+                    from_i32(match op {
+                        Operator::I32Add => a.wrapping_add(b),
+                        Operator::I32Sub => a.wrapping_sub(b),
+                        _ => a.wrapping_mul(b),
+                    })
+                }
+                Operator::I64Add | Operator::I64Sub | Operator::I64Mul => {
+                    let b = stack.pop().context("missing operand")?;
+                    let a = stack.pop().context("missing operand")?;
+                    match op {
+                        Operator::I64Add => a.wrapping_add(b),
+                        Operator::I64Sub => a.wrapping_sub(b),
+                        _ => a.wrapping_mul(b),
+                    }
+                }
+                bad => anyhow::bail!("{bad:?} is not supported during pre-initialization"),
+            };
+
+            stack.push(value);
+        }
+
+        stack
+            .pop()
+            .context("constant expression did not produce a value")
+    }
+
+    /// Gets the range of bytes accessed in a memory, or `None` if it is out of bounds.
+    fn range(&self, memory: u32, address: u64, len: u64) -> Option<std::ops::Range<usize>> {
+        let end = address.checked_add(len)?;
+        if end > self.memories[memory as usize].contents.len() as u64 {
+            return None;
+        }
+
+        Some(usize::try_from(address).ok()?..usize::try_from(end).ok()?)
+    }
+
+    fn load<const N: usize>(
+        &self,
+        memarg: &wasmparser::MemArg,
+        address: u64,
+    ) -> crate::Result<[u8; N]> {
+        let address = (address & 0xFFFF_FFFF) + memarg.offset;
+        match self.range(memarg.memory, address, N as u64) {
+            Some(range) => {
+                let mut bytes = [0u8; N];
+                bytes.copy_from_slice(&self.memories[memarg.memory as usize].contents[range]);
+                Ok(bytes)
+            }
+            None => anyhow::bail!("out-of-bounds load of {N} bytes at address {address:#X}"),
+        }
+    }
+
+    fn store<const N: usize>(
+        &mut self,
+        memarg: &wasmparser::MemArg,
+        address: u64,
+        bytes: [u8; N],
+    ) -> crate::Result<()> {
+        let address = (address & 0xFFFF_FFFF) + memarg.offset;
+        match self.range(memarg.memory, address, N as u64) {
+            Some(range) => {
+                self.memories[memarg.memory as usize].contents[range].copy_from_slice(&bytes);
+                Ok(())
+            }
+            None => anyhow::bail!("out-of-bounds store of {N} bytes at address {address:#X}"),
+        }
+    }
+
+    fn call(&mut self, function_index: u32, arguments: Vec<u64>) -> crate::Result<Vec<u64>> {
+        if let Some((module, name)) = self.imported_functions.get(function_index as usize) {
+            anyhow::bail!(
+                "cannot call imported function {module:?} {name:?} during pre-initialization"
+            );
+        }
+
+        if self.depth >= MAX_CALL_DEPTH {
+            anyhow::bail!("call stack exhausted during pre-initialization");
+        }
+
+        let function = self.function(function_index)?;
+        self.depth += 1;
+        let result = self
+            .execute(&function, arguments)
+            .with_context(|| format!("trap in function #{function_index}"));
+        self.depth -= 1;
+        result
+    }
+
+    // Truncating casts are used to implement the wrapping and saturating conversions
+    #[allow(clippy::cast_possible_truncation)]
+    fn execute(&mut self, function: &Function<'a>, arguments: Vec<u64>) -> crate::Result<Vec<u64>> {
+        use wasmparser::Operator;
+
+        let mut locals = arguments;
+        locals.resize(function.params + function.locals, 0);
+
+        let mut stack = Vec::<u64>::new();
+        let mut labels = vec![Label {
+            is_loop: false,
+            start: 0,
+            end: function.operators.len() - 1,
+            height: 0,
+            arity: function.results,
+        }];
+
+        macro_rules! pop {
+            () => {
+                stack.pop().expect("operand stack underflow")
+            };
+        }
+
+        macro_rules! unary {
+            ($from:expr, $to:expr, $op:expr) => {{
+                let a = $from(pop!());
+                stack.push($to($op(a)));
+            }};
+        }
+
+        macro_rules! binary {
+            ($from:expr, $to:expr, $op:expr) => {{
+                let b = $from(pop!());
+                let a = $from(pop!());
+                stack.push($to($op(a, b)));
+            }};
+        }
+
+        macro_rules! checked_binary {
+            ($from:expr, $to:expr, $op:expr) => {{
+                let b = $from(pop!());
+                let a = $from(pop!());
+                stack.push($to($op(a, b)?));
+            }};
+        }
+
+        macro_rules! load {
+            ($memarg:expr, $n:literal, $convert:expr) => {{
+                let address = pop!();
+                let bytes = self.load::<$n>($memarg, address)?;
+                stack.push($convert(bytes));
+            }};
+        }
+
+        macro_rules! store {
+            ($memarg:expr, $convert:expr) => {{
+                let value = pop!();
+                let address = pop!();
+                self.store($memarg, address, $convert(value))?;
+            }};
+        }
+
+        let to_i64 = |value: u64| value as i64;
+        let from_i64 = |value: i64| value as u64;
+        let from_u32 = u64::from;
+        let to_f64 = f64::from_bits;
+        let from_f64 = f64::to_bits;
+
+        let divide_by_zero = || anyhow::anyhow!("integer divide by zero");
+        let overflow = || anyhow::anyhow!("integer overflow");
+
+        let mut pc = 0;
+        while pc < function.operators.len() {
+            self.steps += 1;
+            if self.steps > MAX_STEPS {
+                anyhow::bail!("pre-initialization did not finish after {MAX_STEPS} instructions");
+            }
+
+            let mut next = pc + 1;
+            let mut branch_depth = None;
+
+            match &function.operators[pc] {
+                Operator::Unreachable => anyhow::bail!("unreachable instruction executed"),
+                Operator::Nop => (),
+                Operator::Block { blockty }
+                | Operator::Loop { blockty }
+                | Operator::If { blockty } => {
+                    let is_loop = matches!(function.operators[pc], Operator::Loop { .. });
+                    let condition = if let Operator::If { .. } = function.operators[pc] {
+                        to_i32(pop!()) != 0
+                    } else {
+                        true
+                    };
+
+                    let (params, results) =
+                        crate::translation::function::get_block_type(self.types, blockty);
+                    let end = function.ends[&pc];
+                    labels.push(Label {
+                        is_loop,
+                        start: pc,
+                        end,
+                        height: stack.len() - params.len(),
+                        arity: if is_loop { params.len() } else { results.len() },
+                    });
+
+                    if !condition {
+                        next = match function.elses.get(&pc) {
+                            Some(else_index) => else_index + 1,
+                            None => end,
+                        };
+                    }
+                }
+                Operator::Else => next = function.ends[&pc],
+                Operator::End => {
+                    labels.pop();
+                }
+                Operator::Br { relative_depth } => branch_depth = Some(*relative_depth),
+                Operator::BrIf { relative_depth } => {
+                    if to_i32(pop!()) != 0 {
+                        branch_depth = Some(*relative_depth);
+                    }
+                }
+                Operator::BrTable { targets } => {
+                    let index = to_u32(pop!()) as usize;
+                    branch_depth = Some(match targets.targets().nth(index) {
+                        Some(target) => target?,
+                        None => targets.default(),
+                    });
+                }
+                Operator::Return => branch_depth = Some((labels.len() - 1) as u32),
+                Operator::Call { function_index } => {
+                    let params = self.signature(*function_index).params().len();
+                    let arguments = stack.split_off(stack.len() - params);
+                    let results = self.call(*function_index, arguments)?;
+                    stack.extend(results);
+                }
+                Operator::Drop => {
+                    pop!();
+                }
+                Operator::Select | Operator::TypedSelect { .. } => {
+                    let condition = to_i32(pop!());
+                    let b = pop!();
+                    let a = pop!();
+                    stack.push(if condition != 0 { a } else { b });
+                }
+                Operator::LocalGet { local_index } => stack.push(locals[*local_index as usize]),
+                Operator::LocalSet { local_index } => locals[*local_index as usize] = pop!(),
+                Operator::LocalTee { local_index } => {
+                    locals[*local_index as usize] = *stack.last().expect("operand stack underflow")
+                }
+                Operator::GlobalGet { global_index } => stack.push(*self.global(*global_index)?),
+                Operator::GlobalSet { global_index } => {
+                    let value = pop!();
+                    self.global(*global_index)?;
+                    self.globals[*global_index as usize] = value;
+                }
+                Operator::I32Load { memarg } | Operator::F32Load { memarg } => {
+                    load!(memarg, 4, |b| from_u32(u32::from_le_bytes(b)))
+                }
+                Operator::I64Load { memarg } | Operator::F64Load { memarg } => {
+                    load!(memarg, 8, u64::from_le_bytes)
+                }
+                Operator::I32Load8S { memarg } => {
+                    load!(memarg, 1, |b| from_i32(i32::from(i8::from_le_bytes(b))))
+                }
+                Operator::I32Load8U { memarg } => {
+                    load!(memarg, 1, |b| from_u32(u32::from(u8::from_le_bytes(b))))
+                }
+                Operator::I32Load16S { memarg } => {
+                    load!(memarg, 2, |b| from_i32(i32::from(i16::from_le_bytes(b))))
+                }
+                Operator::I32Load16U { memarg } => {
+                    load!(memarg, 2, |b| from_u32(u32::from(u16::from_le_bytes(b))))
+                }
+                Operator::I64Load8S { memarg } => {
+                    load!(memarg, 1, |b| from_i64(i64::from(i8::from_le_bytes(b))))
+                }
+                Operator::I64Load8U { memarg } => {
+                    load!(memarg, 1, |b| u64::from(u8::from_le_bytes(b)))
+                }
+                Operator::I64Load16S { memarg } => {
+                    load!(memarg, 2, |b| from_i64(i64::from(i16::from_le_bytes(b))))
+                }
+                Operator::I64Load16U { memarg } => {
+                    load!(memarg, 2, |b| u64::from(u16::from_le_bytes(b)))
+                }
+                Operator::I64Load32S { memarg } => {
+                    load!(memarg, 4, |b| from_i64(i64::from(i32::from_le_bytes(b))))
+                }
+                Operator::I64Load32U { memarg } => {
+                    load!(memarg, 4, |b| u64::from(u32::from_le_bytes(b)))
+                }
+                Operator::I32Store { memarg } | Operator::F32Store { memarg } => {
+                    store!(memarg, |v| to_u32(v).to_le_bytes())
+                }
+                Operator::I64Store { memarg } | Operator::F64Store { memarg } => {
+                    store!(memarg, u64::to_le_bytes)
+                }
+                Operator::I32Store8 { memarg } | Operator::I64Store8 { memarg } => {
+                    store!(memarg, |v: u64| [v.to_le_bytes()[0]])
+                }
+                Operator::I32Store16 { memarg } | Operator::I64Store16 { memarg } => {
+                    store!(memarg, |v: u64| {
+                        let [b0, b1, ..] = v.to_le_bytes();
+                        [b0, b1]
+                    })
+                }
+                Operator::I64Store32 { memarg } => store!(memarg, |v| to_u32(v).to_le_bytes()),
+                Operator::MemorySize { mem, .. } => {
+                    let pages = self.memories[*mem as usize].contents.len() / PAGE_SIZE;
+                    stack.push(pages as u64);
+                }
+                Operator::MemoryGrow { mem, .. } => {
+                    let delta = u64::from(to_u32(pop!()));
+                    let memory = &mut self.memories[*mem as usize];
+                    let old = (memory.contents.len() / PAGE_SIZE) as u64;
+                    match old.checked_add(delta) {
+                        Some(new) if new <= memory.maximum => {
+                            memory.contents.resize(new as usize * PAGE_SIZE, 0);
+                            stack.push(old);
+                        }
+                        _ => stack.push(from_i32(-1)),
+                    }
+                }
+                Operator::MemoryFill { mem } => {
+                    let len = u64::from(to_u32(pop!()));
+                    let value = to_u32(pop!()).to_le_bytes()[0];
+                    let address = u64::from(to_u32(pop!()));
+                    match self.range(*mem, address, len) {
+                        Some(range) => self.memories[*mem as usize].contents[range].fill(value),
+                        None => anyhow::bail!("out-of-bounds memory.fill at address {address:#X}"),
+                    }
+                }
+                Operator::MemoryCopy { dst_mem, src_mem } => {
+                    let len = u64::from(to_u32(pop!()));
+                    let src_address = u64::from(to_u32(pop!()));
+                    let dst_address = u64::from(to_u32(pop!()));
+                    match (
+                        self.range(*src_mem, src_address, len),
+                        self.range(*dst_mem, dst_address, len),
+                    ) {
+                        (Some(src), Some(dst)) if src_mem == dst_mem => self.memories
+                            [*dst_mem as usize]
+                            .contents
+                            .copy_within(src, dst.start),
+                        (Some(src), Some(dst)) => {
+                            let bytes = self.memories[*src_mem as usize].contents[src].to_vec();
+                            self.memories[*dst_mem as usize].contents[dst].copy_from_slice(&bytes);
+                        }
+                        _ => anyhow::bail!("out-of-bounds memory.copy"),
+                    }
+                }
+                Operator::MemoryInit { data_index, mem } => {
+                    let len = to_u32(pop!()) as usize;
+                    let offset = to_u32(pop!()) as usize;
+                    let address = u64::from(to_u32(pop!()));
+                    let data = self.data[*data_index as usize];
+                    match (
+                        data.get(offset..).and_then(|data| data.get(..len)),
+                        self.range(*mem, address, len as u64),
+                    ) {
+                        (Some(src), Some(dst)) => {
+                            self.memories[*mem as usize].contents[dst].copy_from_slice(src)
+                        }
+                        _ => anyhow::bail!("out-of-bounds memory.init at address {address:#X}"),
+                    }
+                }
+                // Data segments are never dropped by the generated code either
+                Operator::DataDrop { .. } => (),
+                Operator::I32Const { value } => stack.push(from_i32(*value)),
+                Operator::I64Const { value } => stack.push(from_i64(*value)),
+                Operator::F32Const { value } => stack.push(u64::from(value.bits())),
+                Operator::F64Const { value } => stack.push(value.bits()),
+                Operator::I32Eqz => unary!(to_i32, from_bool, |a| a == 0),
+                Operator::I32Eq => binary!(to_i32, from_bool, |a, b| a == b),
+                Operator::I32Ne => binary!(to_i32, from_bool, |a, b| a != b),
+                Operator::I32LtS => binary!(to_i32, from_bool, |a, b| a < b),
+                Operator::I32LtU => binary!(to_u32, from_bool, |a, b| a < b),
+                Operator::I32GtS => binary!(to_i32, from_bool, |a, b| a > b),
+                Operator::I32GtU => binary!(to_u32, from_bool, |a, b| a > b),
+                Operator::I32LeS => binary!(to_i32, from_bool, |a, b| a <= b),
+                Operator::I32LeU => binary!(to_u32, from_bool, |a, b| a <= b),
+                Operator::I32GeS => binary!(to_i32, from_bool, |a, b| a >= b),
+                Operator::I32GeU => binary!(to_u32, from_bool, |a, b| a >= b),
+                Operator::I64Eqz => unary!(to_i64, from_bool, |a| a == 0),
+                Operator::I64Eq => binary!(to_i64, from_bool, |a, b| a == b),
+                Operator::I64Ne => binary!(to_i64, from_bool, |a, b| a != b),
+                Operator::I64LtS => binary!(to_i64, from_bool, |a, b| a < b),
+                Operator::I64LtU => binary!(u64::from, from_bool, |a, b| a < b),
+                Operator::I64GtS => binary!(to_i64, from_bool, |a, b| a > b),
+                Operator::I64GtU => binary!(u64::from, from_bool, |a, b| a > b),
+                Operator::I64LeS => binary!(to_i64, from_bool, |a, b| a <= b),
+                Operator::I64LeU => binary!(u64::from, from_bool, |a, b| a <= b),
+                Operator::I64GeS => binary!(to_i64, from_bool, |a, b| a >= b),
+                Operator::I64GeU => binary!(u64::from, from_bool, |a, b| a >= b),
+                Operator::F32Eq => binary!(to_f32, from_bool, |a, b| a == b),
+                Operator::F32Ne => binary!(to_f32, from_bool, |a, b| a != b),
+                Operator::F32Gt => binary!(to_f32, from_bool, |a, b| a > b),
+                Operator::F64Eq => binary!(to_f64, from_bool, |a, b| a == b),
+                Operator::F64Ne => binary!(to_f64, from_bool, |a, b| a != b),
+                Operator::F64Gt => binary!(to_f64, from_bool, |a, b| a > b),
+                Operator::I32Clz => unary!(to_i32, from_u32, i32::leading_zeros),
+                Operator::I32Ctz => unary!(to_i32, from_u32, i32::trailing_zeros),
+                Operator::I32Popcnt => unary!(to_i32, from_u32, i32::count_ones),
+                Operator::I32Add => binary!(to_i32, from_i32, i32::wrapping_add),
+                Operator::I32Sub => binary!(to_i32, from_i32, i32::wrapping_sub),
+                Operator::I32Mul => binary!(to_i32, from_i32, i32::wrapping_mul),
+                Operator::I32DivS => checked_binary!(to_i32, from_i32, |a: i32, b: i32| {
+                    if b == 0 {
+                        Err(divide_by_zero())
+                    } else {
+                        a.checked_div(b).ok_or_else(overflow)
+                    }
+                }),
+                Operator::I32DivU => checked_binary!(to_u32, from_u32, |a: u32, b| a
+                    .checked_div(b)
+                    .ok_or_else(divide_by_zero)),
+                Operator::I32RemS => checked_binary!(to_i32, from_i32, |a: i32, b: i32| {
+                    if b == 0 {
+                        Err(divide_by_zero())
+                    } else {
+                        Ok(a.wrapping_rem(b))
+                    }
+                }),
+                Operator::I32RemU => checked_binary!(to_u32, from_u32, |a: u32, b| a
+                    .checked_rem(b)
+                    .ok_or_else(divide_by_zero)),
+                Operator::I32And => binary!(to_i32, from_i32, |a, b| a & b),
+                Operator::I32Or => binary!(to_i32, from_i32, |a, b| a | b),
+                Operator::I32Xor => binary!(to_i32, from_i32, |a, b| a ^ b),
+                Operator::I32Shl => binary!(to_i32, from_i32, |a: i32, b| a.wrapping_shl(b as u32)),
+                Operator::I32ShrS => {
+                    binary!(to_i32, from_i32, |a: i32, b| a.wrapping_shr(b as u32))
+                }
+                Operator::I32ShrU => binary!(to_u32, from_u32, u32::wrapping_shr),
+                Operator::I32Rotl => binary!(to_u32, from_u32, u32::rotate_left),
+                Operator::I32Rotr => binary!(to_u32, from_u32, u32::rotate_right),
+                Operator::I64Clz => unary!(to_i64, u64::from, i64::leading_zeros),
+                Operator::I64Ctz => unary!(to_i64, u64::from, i64::trailing_zeros),
+                Operator::I64Popcnt => unary!(to_i64, u64::from, i64::count_ones),
+                Operator::I64Add => binary!(to_i64, from_i64, i64::wrapping_add),
+                Operator::I64Sub => binary!(to_i64, from_i64, i64::wrapping_sub),
+                Operator::I64Mul => binary!(to_i64, from_i64, i64::wrapping_mul),
+                Operator::I64DivS => checked_binary!(to_i64, from_i64, |a: i64, b: i64| {
+                    if b == 0 {
+                        Err(divide_by_zero())
+                    } else {
+                        a.checked_div(b).ok_or_else(overflow)
+                    }
+                }),
+                Operator::I64DivU => checked_binary!(u64::from, u64::from, |a: u64, b| a
+                    .checked_div(b)
+                    .ok_or_else(divide_by_zero)),
+                Operator::I64RemS => checked_binary!(to_i64, from_i64, |a: i64, b: i64| {
+                    if b == 0 {
+                        Err(divide_by_zero())
+                    } else {
+                        Ok(a.wrapping_rem(b))
+                    }
+                }),
+                Operator::I64RemU => checked_binary!(u64::from, u64::from, |a: u64, b| a
+                    .checked_rem(b)
+                    .ok_or_else(divide_by_zero)),
+                Operator::I64And => binary!(to_i64, from_i64, |a, b| a & b),
+                Operator::I64Or => binary!(to_i64, from_i64, |a, b| a | b),
+                Operator::I64Xor => binary!(to_i64, from_i64, |a, b| a ^ b),
+                Operator::I64Shl => binary!(u64::from, u64::from, |a: u64, b: u64| a << (b % 64)),
+                Operator::I64ShrS => {
+                    binary!(to_i64, from_i64, |a: i64, b: i64| a >> (b.rem_euclid(64)))
+                }
+                Operator::I64ShrU => binary!(u64::from, u64::from, |a: u64, b: u64| a >> (b % 64)),
+                Operator::I64Rotl => binary!(u64::from, u64::from, |a: u64, b: u64| a
+                    .rotate_left((b % 64) as u32)),
+                Operator::I64Rotr => binary!(u64::from, u64::from, |a: u64, b: u64| a
+                    .rotate_right((b % 64) as u32)),
+                Operator::F32Neg => unary!(to_f32, from_f32, |a: f32| -a),
+                Operator::F64Neg => unary!(to_f64, from_f64, |a: f64| -a),
+                Operator::I32WrapI64 => unary!(u64::from, from_u32, |a: u64| to_u32(a)),
+                Operator::I32TruncF32S | Operator::I32TruncF64S => {
+                    let a = self.pop_float(&mut stack, &function.operators[pc]);
+                    let value = checked_trunc(a, -2147483649.0, 2147483648.0)?;
+                    stack.push(from_i32(value as i32));
+                }
+                Operator::I32TruncF32U | Operator::I32TruncF64U => {
+                    let a = self.pop_float(&mut stack, &function.operators[pc]);
+                    let value = checked_trunc(a, -1.0, 4294967296.0)?;
+                    stack.push(from_u32(value as u32));
+                }
+                Operator::I64TruncF32S | Operator::I64TruncF64S => {
+                    let a = self.pop_float(&mut stack, &function.operators[pc]);
+                    let value = checked_trunc(a, -9223372036854777856.0, 9223372036854775808.0)?;
+                    stack.push(from_i64(value as i64));
+                }
+                Operator::I64TruncF32U | Operator::I64TruncF64U => {
+                    let a = self.pop_float(&mut stack, &function.operators[pc]);
+                    let value = checked_trunc(a, -1.0, 18446744073709551616.0)?;
+                    stack.push(value as u64);
+                }
+                // Casts from floats to integers in Rust saturate, and convert NaN to zero.
+                Operator::I32TruncSatF32S | Operator::I32TruncSatF64S => {
+                    let a = self.pop_float(&mut stack, &function.operators[pc]);
+                    stack.push(from_i32(a as i32));
+                }
+                Operator::I32TruncSatF32U | Operator::I32TruncSatF64U => {
+                    let a = self.pop_float(&mut stack, &function.operators[pc]);
+                    stack.push(from_u32(a as u32));
+                }
+                Operator::I64TruncSatF32S | Operator::I64TruncSatF64S => {
+                    let a = self.pop_float(&mut stack, &function.operators[pc]);
+                    stack.push(from_i64(a as i64));
+                }
+                Operator::I64TruncSatF32U | Operator::I64TruncSatF64U => {
+                    let a = self.pop_float(&mut stack, &function.operators[pc]);
+                    stack.push(a as u64);
+                }
+                Operator::I64ExtendI32S => unary!(to_i32, from_i64, i64::from),
+                Operator::I64ExtendI32U => unary!(to_u32, u64::from, u64::from),
+                Operator::F32ConvertI32S => unary!(to_i32, from_f32, |a| a as f32),
+                Operator::F32ConvertI32U => unary!(to_u32, from_f32, |a| a as f32),
+                Operator::F32ConvertI64S => unary!(to_i64, from_f32, |a| a as f32),
+                Operator::F32ConvertI64U => unary!(u64::from, from_f32, |a: u64| a as f32),
+                Operator::F32DemoteF64 => unary!(to_f64, from_f32, |a| a as f32),
+                Operator::F64ConvertI32S => unary!(to_i32, from_f64, f64::from),
+                Operator::F64ConvertI32U => unary!(to_u32, from_f64, f64::from),
+                Operator::F64ConvertI64S => unary!(to_i64, from_f64, |a| a as f64),
+                Operator::F64ConvertI64U => unary!(u64::from, from_f64, |a: u64| a as f64),
+                Operator::F64PromoteF32 => unary!(to_f32, from_f64, f64::from),
+                // Values are already stored as their bits.
+                Operator::I32ReinterpretF32
+                | Operator::I64ReinterpretF64
+                | Operator::F32ReinterpretI32
+                | Operator::F64ReinterpretI64 => (),
+                Operator::I32Extend8S => unary!(to_i32, from_i32, |a| i32::from(a as i8)),
+                Operator::I32Extend16S => unary!(to_i32, from_i32, |a| i32::from(a as i16)),
+                Operator::I64Extend8S => unary!(to_i64, from_i64, |a| i64::from(a as i8)),
+                Operator::I64Extend16S => unary!(to_i64, from_i64, |a| i64::from(a as i16)),
+                Operator::I64Extend32S => unary!(to_i64, from_i64, |a| i64::from(a as i32)),
+                bad => anyhow::bail!("{bad:?} is not supported during pre-initialization"),
+            }
+
+            if let Some(depth) = branch_depth {
+                let label = &labels[labels.len() - 1 - depth as usize];
+                let values = stack.len() - label.arity;
+                stack.drain(label.height..values);
+
+                if label.is_loop {
+                    next = label.start + 1;
+                    labels.truncate(labels.len() - depth as usize);
+                } else {
+                    next = label.end + 1;
+                    labels.truncate(labels.len() - depth as usize - 1);
+                }
+            }
+
+            pc = next;
+        }
+
+        Ok(stack.split_off(stack.len() - function.results))
+    }
+
+    /// Pops the operand of a float to integer conversion instruction.
+    fn pop_float(&self, stack: &mut Vec<u64>, op: &wasmparser::Operator) -> f64 {
+        use wasmparser::Operator;
+
+        let value = stack.pop().expect("operand stack underflow");
+        match op {
+            Operator::I32TruncF32S
+            | Operator::I32TruncF32U
+            | Operator::I64TruncF32S
+            | Operator::I64TruncF32U
+            | Operator::I32TruncSatF32S
+            | Operator::I32TruncSatF32U
+            | Operator::I64TruncSatF32S
+            | Operator::I64TruncSatF32U => f64::from(to_f32(value)),
+            _ => f64::from_bits(value),
+        }
+    }
+}
+
+/// Instantiates the module, then runs its start function and the exported function with the
+/// given `name`.
+///
+/// The `bodies` of the functions defined by the module must have already been validated.
+pub(in crate::translation) fn run(
+    sections: &[crate::translation::KnownSection<'_>],
+    bodies: &[wasmparser::FunctionBody<'_>],
+    types: &wasmparser::types::Types,
+    import_counts: &crate::translation::ImportCounts,
+    start_function: Option<u32>,
+    name: &str,
+) -> crate::Result<Initialized> {
+    use crate::translation::KnownSection;
+
+    if import_counts.memories > 0 {
+        anyhow::bail!("pre-initialization of modules that import memories is not supported");
+    }
+
+    let mut imported_functions = Vec::new();
+    let mut init_function = None;
+    for section in sections.iter() {
+        match section {
+            KnownSection::Dylink(_) => {
+                anyhow::bail!("pre-initialization of dynamically linked modules is not supported")
+            }
+            KnownSection::Import(imports) => {
+                for result in imports.clone() {
+                    let import = result?;
+                    if let wasmparser::TypeRef::Func(_) = import.ty {
+                        imported_functions.push((import.module, import.name));
+                    }
+                }
+            }
+            KnownSection::Export(Some(exports)) => {
+                for result in exports.clone() {
+                    let export = result?;
+                    if export.name == name && export.kind == wasmparser::ExternalKind::Func {
+                        init_function = Some(export.index);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let Some(init_function) = init_function else {
+        anyhow::bail!("module has no exported function named {name:?} to pre-initialize with");
+    };
+
+    let mut interpreter = Interpreter {
+        types,
+        imported_functions,
+        bodies,
+        functions: std::iter::repeat_with(|| None).take(bodies.len()).collect(),
+        imported_globals: import_counts.globals,
+        globals: vec![0; types.global_count() as usize],
+        memories: Vec::with_capacity(types.memory_count() as usize),
+        data: Vec::new(),
+        steps: 0,
+        depth: 0,
+    };
+
+    if !interpreter.signature(init_function).params().is_empty() {
+        anyhow::bail!("exported function {name:?} used for pre-initialization has parameters");
+    }
+
+    for index in 0..types.memory_count() {
+        let memory = types.memory_at(index);
+        interpreter.memories.push(Memory {
+            contents: vec![0; usize::try_from(memory.initial)? * PAGE_SIZE],
+            maximum: memory.maximum.unwrap_or(65536),
+        });
+    }
+
+    for section in sections.iter() {
+        match section {
+            KnownSection::Global(globals) => {
+                for (result, index) in globals.clone().into_iter().zip(import_counts.globals..) {
+                    let global = result?;
+                    match global.ty.content_type {
+                        wasmparser::ValType::I32
+                        | wasmparser::ValType::I64
+                        | wasmparser::ValType::F32
+                        | wasmparser::ValType::F64 => (),
+                        other => anyhow::bail!(
+                            "pre-initialization of modules that define {other} globals is not supported"
+                        ),
+                    }
+
+                    interpreter.globals[index as usize] =
+                        interpreter
+                            .evaluate(&global.init_expr)
+                            .with_context(|| format!("could not initialize global #{index}"))?;
+                }
+            }
+            KnownSection::Data(data) => {
+                for (result, index) in data.clone().into_iter().zip(0u32..) {
+                    let data = result?;
+                    if let wasmparser::DataKind::Active {
+                        memory_index,
+                        offset_expr,
+                    } = data.kind
+                    {
+                        let offset = u64::from(to_u32(interpreter.evaluate(&offset_expr)?));
+                        match interpreter.range(memory_index, offset, data.data.len() as u64) {
+                            Some(range) => interpreter.memories[memory_index as usize].contents
+                                [range]
+                                .copy_from_slice(data.data),
+                            None => anyhow::bail!("data segment #{index} is out of bounds"),
+                        }
+                    }
+
+                    interpreter.data.push(data.data);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if let Some(start_function) = start_function {
+        interpreter
+            .call(start_function, Vec::new())
+            .context("could not run start function during pre-initialization")?;
+    }
+
+    interpreter
+        .call(init_function, Vec::new())
+        .with_context(|| format!("could not run {name:?} during pre-initialization"))?;
+
+    let globals = interpreter
+        .globals
+        .split_off(import_counts.globals as usize);
+    Ok(Initialized {
+        data_count: u32::try_from(interpreter.data.len())?,
+        memories: interpreter
+            .memories
+            .into_iter()
+            .map(|memory| memory.contents)
+            .collect(),
+        globals,
+    })
+}
+
+/// Writes data segments containing the contents of the memories after pre-initialization.
+///
+/// The data segments are numbered after the module's own data segments.
+pub(in crate::translation) fn write_data(
+    buffer_pool: &crate::buffer::Pool,
+    initialized: &Initialized,
+    writer: crate::DataSegmentWriter,
+) -> crate::Result<crate::translation::GeneratedLines> {
+    let mut item_out = crate::buffer::Writer::new(buffer_pool);
+    let mut init_out = crate::buffer::Writer::new(buffer_pool);
+    let mut index = initialized.data_count;
+
+    for (memory_index, contents) in initialized.memories.iter().enumerate() {
+        let memory = crate::translation::display::MemId(u32::try_from(memory_index)?);
+        let mut start = 0;
+        while let Some(offset) = contents[start..].iter().position(|b| *b != 0) {
+            start += offset;
+
+            // Find the end of the non-zero bytes, including short runs of zero bytes.
+            let mut end = start;
+            loop {
+                end += contents[end..]
+                    .iter()
+                    .position(|b| *b == 0)
+                    .unwrap_or(contents.len() - end);
+
+                match contents[end..].iter().position(|b| *b != 0) {
+                    Some(gap) if gap < MIN_ZERO_GAP => end += gap,
+                    _ => break,
+                }
+            }
+
+            let id = crate::translation::display::DataId(index);
+            crate::translation::data_segment::write_item(
+                &mut item_out,
+                index,
+                &contents[start..end],
+                writer,
+            )?;
+
+            let _ = writeln!(
+                init_out,
+                "      embedder::rt::memory::init::<{}, _, _>(&{memory}, {id}, {}i32, 0, {}i32, &embedder)?;",
+                memory.0,
+                u32::try_from(start)? as i32,
+                u32::try_from(end - start)? as i32,
+            );
+
+            index += 1;
+            start = end;
+        }
+    }
+
+    Ok(crate::translation::GeneratedLines {
+        items: item_out.finish(),
+        inits: init_out.finish(),
+        ..Default::default()
+    })
+}
@@ -0,0 +1,124 @@
+use std::fmt::Write;
+
+const SNAPSHOT: &str = "embedder::rt::snapshot";
+
+/// Computes the 64-bit FNV-1a hash of the module's binary, which is used to check that snapshots
+/// are only restored into instances of the module they were taken from.
+fn fingerprint(wasm: &[u8]) -> u64 {
+    wasm.iter().fold(0xCBF2_9CE4_8422_2325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x0000_0100_0000_01B3)
+    })
+}
+
+pub(in crate::translation) fn write(
+    buffer_pool: &crate::buffer::Pool,
+    wasm: &[u8],
+    types: &wasmparser::types::Types,
+    import_counts: &crate::translation::ImportCounts,
+    exclusive_access: bool,
+    thread_safe: bool,
+) -> crate::Result<crate::translation::GeneratedLines> {
+    use crate::translation::display::{GlobalId, MemId, ValType};
+
+    if types.table_count() > 0 {
+        anyhow::bail!("snapshots of modules with tables are not supported");
+    }
+
+    let memories = import_counts.memories..types.memory_count();
+    let mut globals = Vec::new();
+    for index in import_counts.globals..types.global_count() {
+        let global_type = types.global_at(index);
+        if !global_type.mutable {
+            continue;
+        }
+
+        match global_type.content_type {
+            wasmparser::ValType::I32
+            | wasmparser::ValType::I64
+            | wasmparser::ValType::F32
+            | wasmparser::ValType::F64 => globals.push((GlobalId(index), global_type.content_type)),
+            other => anyhow::bail!(
+                "snapshots of mutable {other} globals are not supported, but global #{index} is one"
+            ),
+        }
+    }
+
+    let is_empty = memories.is_empty() && globals.is_empty();
+    let cell_type = crate::translation::global::cell_type(thread_safe);
+    let mut impl_out = crate::buffer::Writer::new(buffer_pool);
+
+    let _ = writeln!(
+        impl_out,
+        "    $vis const SNAPSHOT_FINGERPRINT: u64 = {:#018X}u64;\n",
+        fingerprint(wasm)
+    );
+
+    let _ = writeln!(
+        impl_out,
+        "    $vis fn snapshot(&self) -> {SNAPSHOT}::Bytes {{\n      \
+        let {}snapshot = {SNAPSHOT}::Writer::new(Self::SNAPSHOT_FINGERPRINT);",
+        if is_empty { "" } else { "mut " },
+    );
+
+    for index in memories.clone() {
+        let _ = writeln!(
+            impl_out,
+            "      embedder::rt::memory::write_snapshot(&self.{}, &mut snapshot);",
+            MemId(index)
+        );
+    }
+
+    for (id, ty) in globals.iter() {
+        let _ = write!(
+            impl_out,
+            "      embedder::rt::global::write_snapshot::<{}>(",
+            ValType(*ty)
+        );
+
+        if exclusive_access {
+            let _ = write!(impl_out, "self.{id}");
+        } else {
+            let _ = write!(impl_out, "{cell_type}::get(&self.{id})");
+        }
+
+        impl_out.write_str(", &mut snapshot);\n");
+    }
+
+    impl_out.write_str("      snapshot.finish()\n    }\n\n");
+
+    let _ = writeln!(
+        impl_out,
+        "    $vis fn restore(&{}self, snapshot: &[u8]) -> ::core::result::Result<(), {SNAPSHOT}::SnapshotError> {{\n      \
+        let {}snapshot = {SNAPSHOT}::Reader::new(snapshot, Self::SNAPSHOT_FINGERPRINT)?;",
+        if exclusive_access { "mut " } else { "" },
+        if is_empty { "" } else { "mut " },
+    );
+
+    for index in memories {
+        let _ = writeln!(
+            impl_out,
+            "      embedder::rt::memory::restore_snapshot(&self.{}, &mut snapshot)?;",
+            MemId(index)
+        );
+    }
+
+    for (id, ty) in globals.iter() {
+        let value = format!(
+            "embedder::rt::global::read_snapshot::<{}>(&mut snapshot)?",
+            ValType(*ty)
+        );
+
+        if exclusive_access {
+            let _ = writeln!(impl_out, "      self.{id} = {value};");
+        } else {
+            let _ = writeln!(impl_out, "      {cell_type}::set(&self.{id}, {value});");
+        }
+    }
+
+    impl_out.write_str("      snapshot.finish()\n    }\n\n");
+
+    Ok(crate::translation::GeneratedLines {
+        impls: impl_out.finish(),
+        ..Default::default()
+    })
+}
@@ -5,5 +5,8 @@ fn main() {
         simd_intrinsics: { feature = "simd-intrinsics" }, // not(miri)
         simd_sse2_intrinsics: { all(simd_intrinsics, target_feature = "sse2") },
         simd_no_intrinsics: { not(any(simd_sse2_intrinsics)) },
+        mmap_memory: { all(feature = "mmap", unix, target_pointer_width = "64") },
     }
+
+    println!("cargo::rustc-check-cfg=cfg(mmap_memory)");
 }
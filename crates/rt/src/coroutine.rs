@@ -0,0 +1,173 @@
+//! Support for suspending a WebAssembly function at an import call, and resuming it later.
+//!
+//! When imported functions are marked as async during translation, every function that can reach
+//! them is translated as an `async fn`. The Rust compiler turns each of these into a state machine
+//! that saves the function's locals and operand stack whenever it is suspended. Rather than
+//! running these on an executor, an embedder can step through a call into the WebAssembly module
+//! with [`resume()`], using a [`Suspend`] to exchange values with the suspended import.
+//!
+//! ```
+//! use core::task::Poll;
+//! use wasm2rs_rt::coroutine::{resume, Suspend};
+//!
+//! # type Result<T> = core::result::Result<T, wasm2rs_rt::trap::TrapValue>;
+//! struct Env {
+//!     suspend: Suspend<i32, i32>,
+//! }
+//!
+//! impl Env {
+//!     // An imported function marked as async
+//!     async fn ask(&self, question: i32) -> Result<i32> {
+//!         Ok(self.suspend.suspend(question).await)
+//!     }
+//! }
+//!
+//! let env = Env { suspend: Suspend::new() };
+//!
+//! // Typically a call to an `async` exported function
+//! let mut call = core::pin::pin!(async { Ok::<_, wasm2rs_rt::trap::TrapValue>(env.ask(20).await? + 1) });
+//!
+//! assert!(resume(call.as_mut()).is_pending());
+//! let question = env.suspend.take_yielded().unwrap();
+//! env.suspend.resume_with(question * 2);
+//! assert!(matches!(resume(call.as_mut()), Poll::Ready(Ok(41))));
+//! ```
+
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+enum Slot<Y, R> {
+    Empty,
+    Yielded(Y),
+    Resumed(R),
+}
+
+/// Used to exchange values between an import that suspends a WebAssembly function, and the
+/// embedder that [`resume()`]s it.
+///
+/// The type parameter `Y` is the type of the values passed to the embedder when the function is
+/// suspended, while `R` is the type of the values passed back to the function when it resumes.
+pub struct Suspend<Y, R> {
+    slot: core::cell::Cell<Slot<Y, R>>,
+}
+
+impl<Y, R> Default for Suspend<Y, R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Y, R> Suspend<Y, R> {
+    /// Creates a new [`Suspend`] with no yielded or resumed values.
+    pub const fn new() -> Self {
+        Self {
+            slot: core::cell::Cell::new(Slot::Empty),
+        }
+    }
+
+    /// Returns a [`Future`] that suspends the WebAssembly function, yielding a `value` to the
+    /// embedder, and completes with the value given to [`Suspend::resume_with()`].
+    pub fn suspend(&self, value: Y) -> Suspending<'_, Y, R> {
+        Suspending {
+            suspend: self,
+            value: Some(value),
+        }
+    }
+
+    /// Takes the value yielded by the most recently suspended WebAssembly function, if there is
+    /// one.
+    pub fn take_yielded(&self) -> Option<Y> {
+        match self.slot.replace(Slot::Empty) {
+            Slot::Yielded(value) => Some(value),
+            other => {
+                self.slot.set(other);
+                None
+            }
+        }
+    }
+
+    /// Sets the value returned to the suspended WebAssembly function when it is next
+    /// [`resume()`]d.
+    ///
+    /// Any previously yielded value that was not taken is discarded.
+    pub fn resume_with(&self, value: R) {
+        self.slot.set(Slot::Resumed(value))
+    }
+
+    /// Returns `true` if a value was yielded that has not yet been taken.
+    pub fn is_suspended(&self) -> bool {
+        let slot = self.slot.replace(Slot::Empty);
+        let suspended = matches!(slot, Slot::Yielded(_));
+        self.slot.set(slot);
+        suspended
+    }
+}
+
+impl<Y, R> core::fmt::Debug for Suspend<Y, R> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Suspend")
+            .field("suspended", &self.is_suspended())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A [`Future`] that suspends a WebAssembly function until the embedder provides a value.
+///
+/// This is returned by [`Suspend::suspend()`].
+#[must_use = "futures do nothing unless polled"]
+pub struct Suspending<'a, Y, R> {
+    suspend: &'a Suspend<Y, R>,
+    value: Option<Y>,
+}
+
+// The yielded value is never pinned.
+impl<Y, R> Unpin for Suspending<'_, Y, R> {}
+
+impl<Y, R> Future for Suspending<'_, Y, R> {
+    type Output = R;
+
+    fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<R> {
+        if let Some(value) = self.value.take() {
+            self.suspend.slot.set(Slot::Yielded(value));
+            return Poll::Pending;
+        }
+
+        match self.suspend.slot.replace(Slot::Empty) {
+            Slot::Resumed(value) => Poll::Ready(value),
+            other => {
+                self.suspend.slot.set(other);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<Y, R> core::fmt::Debug for Suspending<'_, Y, R> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Suspending")
+            .field("suspend", self.suspend)
+            .finish_non_exhaustive()
+    }
+}
+
+fn noop_raw_waker() -> core::task::RawWaker {
+    const VTABLE: core::task::RawWakerVTable =
+        core::task::RawWakerVTable::new(|_| noop_raw_waker(), |_| (), |_| (), |_| ());
+
+    core::task::RawWaker::new(core::ptr::null(), &VTABLE)
+}
+
+/// Runs a suspended call into a WebAssembly module until it is suspended again, or until it
+/// completes.
+///
+/// Returns [`Poll::Pending`] if the call was suspended. Since no [`Waker`] is provided, the only
+/// imports that may suspend the call are those that use a [`Suspend`], or that otherwise expect
+/// to be polled again without being woken.
+///
+/// [`Waker`]: core::task::Waker
+pub fn resume<F: Future + ?Sized>(call: Pin<&mut F>) -> Poll<F::Output> {
+    // Safety: all functions in the vtable do nothing, and the data pointer is never used.
+    let waker = unsafe { core::task::Waker::from_raw(noop_raw_waker()) };
+    call.poll(&mut Context::from_waker(&waker))
+}
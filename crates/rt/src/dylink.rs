@@ -0,0 +1,165 @@
+//! Support for [dynamically linked] WebAssembly modules, such as Emscripten side modules or the
+//! output of `wasm-ld -shared`.
+//!
+//! These modules contain a `dylink.0` custom section describing how much memory and table space
+//! they require, and import the following globals to find the space reserved for them by a loader:
+//!
+//! - `env.__memory_base`, the address at which the module's data is placed in the shared memory.
+//! - `env.__table_base`, the index at which the module's functions are placed in the shared table.
+//! - Globals in the `GOT.mem` module namespace, containing the addresses of data symbols.
+//! - Globals in the `GOT.func` module namespace, containing the table indices of functions.
+//!
+//! Rather than being provided by the embedder's imports, `wasm2rs` takes the values of these
+//! globals from a [`Linking`] passed to the generated `instantiate` function. This allows a main
+//! module and its side modules to share one memory:
+//!
+//! ```
+//! use wasm2rs_rt::dylink::{Linking, MemInfo, Symbols};
+//!
+//! struct Loaded;
+//!
+//! impl Symbols for Loaded {
+//!     fn memory_address(&self, name: &str) -> Option<u32> {
+//!         (name == "errno").then_some(0x400)
+//!     }
+//!
+//!     fn table_index(&self, _: &str) -> Option<u32> {
+//!         None
+//!     }
+//! }
+//!
+//! // Typically a `DYLINK_MEM_INFO` constant in the generated code
+//! const SIDE_MODULE: MemInfo = MemInfo {
+//!     memory_size: 100,
+//!     memory_alignment: 4,
+//!     table_size: 0,
+//!     table_alignment: 0,
+//! };
+//!
+//! let next_free_address = 0x1234;
+//! let linking = Linking {
+//!     memory_base: SIDE_MODULE.align_memory_base(next_free_address),
+//!     table_base: SIDE_MODULE.align_table_base(1),
+//!     symbols: &Loaded,
+//! };
+//!
+//! assert_eq!(linking.memory_base, 0x1240);
+//! ```
+//!
+//! Tables are not yet supported, so the shared table imported as `env.__indirect_function_table`
+//! is not provided to the module, and translation fails if a module imports it. The values of
+//! `env.__table_base` and the `GOT.func` entries are still passed to the module, but they can't
+//! be used for indirect calls.
+//!
+//! [dynamically linked]: https://github.com/WebAssembly/tool-conventions/blob/main/DynamicLinking.md
+
+use crate::linker::{LinkError, LinkErrorKind};
+
+/// Describes the memory and table space required by a dynamically linked module, taken from the
+/// `WASM_DYLINK_MEM_INFO` subsection of its `dylink.0` custom section.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct MemInfo {
+    /// The number of bytes that the loader should reserve for the module, starting at
+    /// `env.__memory_base`.
+    pub memory_size: u32,
+    /// The required alignment of the memory space, in bytes, encoded as a power of 2.
+    pub memory_alignment: u32,
+    /// The number of elements that the loader should reserve for the module, starting at
+    /// `env.__table_base`.
+    pub table_size: u32,
+    /// The required alignment of the table space, in elements, encoded as a power of 2.
+    pub table_alignment: u32,
+}
+
+const fn align_up(value: u32, alignment: u32) -> u32 {
+    let mask = match 1u32.checked_shl(alignment) {
+        Some(align) => align - 1,
+        None => u32::MAX,
+    };
+
+    (value.wrapping_add(mask)) & !mask
+}
+
+impl MemInfo {
+    /// Rounds the given address up to the [`memory_alignment`].
+    ///
+    /// [`memory_alignment`]: MemInfo::memory_alignment
+    pub const fn align_memory_base(&self, address: u32) -> u32 {
+        align_up(address, self.memory_alignment)
+    }
+
+    /// Rounds the given table index up to the [`table_alignment`].
+    ///
+    /// [`table_alignment`]: MemInfo::table_alignment
+    pub const fn align_table_base(&self, index: u32) -> u32 {
+        align_up(index, self.table_alignment)
+    }
+}
+
+/// Resolves the entries of the global offset table (GOT) imported by a dynamically linked module.
+pub trait Symbols {
+    /// Gets the address of the data symbol with the given `name`, imported from `GOT.mem`.
+    fn memory_address(&self, name: &str) -> Option<u32>;
+
+    /// Gets the table index of the function with the given `name`, imported from `GOT.func`.
+    fn table_index(&self, name: &str) -> Option<u32>;
+}
+
+/// Used when a dynamically linked module does not import any symbols.
+impl Symbols for () {
+    fn memory_address(&self, _: &str) -> Option<u32> {
+        None
+    }
+
+    fn table_index(&self, _: &str) -> Option<u32> {
+        None
+    }
+}
+
+/// Provides the locations chosen by a loader for a dynamically linked module's memory and table
+/// space, and the entries of its global offset table.
+///
+/// This is passed to the `instantiate` function generated for modules with a `dylink.0` custom
+/// section.
+#[derive(Clone, Copy)]
+pub struct Linking<'a> {
+    /// The value of the `env.__memory_base` global.
+    pub memory_base: u32,
+    /// The value of the `env.__table_base` global.
+    pub table_base: u32,
+    /// Resolves the `GOT.mem` and `GOT.func` globals.
+    pub symbols: &'a dyn Symbols,
+}
+
+impl core::fmt::Debug for Linking<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Linking")
+            .field("memory_base", &self.memory_base)
+            .field("table_base", &self.table_base)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Gets the value of a global imported from `GOT.mem`.
+///
+/// # Errors
+///
+/// Returns a [`LinkError`] if the symbol could not be resolved.
+pub fn resolve_memory_address(linking: &Linking<'_>, name: &'static str) -> Result<i32, LinkError> {
+    match linking.symbols.memory_address(name) {
+        Some(address) => Ok(address as i32),
+        None => Err(LinkError::new("GOT.mem", name, LinkErrorKind::Undefined)),
+    }
+}
+
+/// Gets the value of a global imported from `GOT.func`.
+///
+/// # Errors
+///
+/// Returns a [`LinkError`] if the symbol could not be resolved.
+pub fn resolve_table_index(linking: &Linking<'_>, name: &'static str) -> Result<i32, LinkError> {
+    match linking.symbols.table_index(name) {
+        Some(index) => Ok(index as i32),
+        None => Err(LinkError::new("GOT.func", name, LinkErrorKind::Undefined)),
+    }
+}
@@ -0,0 +1,217 @@
+//! Types used to access the exports of a WebAssembly module instance by name.
+//!
+//! When a WebAssembly module is translated with `Translation::dynamic_api()` enabled, the
+//! generated `Instance` provides a reflective interface alongside the methods named after each
+//! export, which is useful for plugin hosts and other tools that only know which exports to use
+//! at run time:
+//!
+//! - `Instance::exports()` lists the name and [`ExportKind`] of each export.
+//! - `Instance::call_dynamic(name, arguments)` calls an exported function with a slice of
+//!   [`Val`]ues, returning its results.
+//! - `Instance::global_dynamic(name)` reads the current [`Val`]ue of an exported global.
+//!
+//! To get a [`FuncRef`] to an exported function, enable `Translation::func_refs()` instead.
+//!
+//! [`FuncRef`]: crate::func_ref::FuncRef
+
+use crate::stack::trace::{WasmSymbolSignature, WasmValType};
+
+/// A WebAssembly [value].
+///
+/// [value]: https://webassembly.github.io/spec/core/exec/runtime.html#values
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum Val<'a> {
+    /// A 32-bit integer.
+    I32(i32),
+    /// A 64-bit integer.
+    I64(i64),
+    /// A 32-bit IEEE-754 float.
+    F32(f32),
+    /// A 64-bit IEEE-754 float.
+    F64(f64),
+    /// A 128-bit vector.
+    #[cfg(feature = "simd-128")]
+    V128(crate::simd::v128::V128),
+    /// A reference to a function.
+    FuncRef(crate::func_ref::FuncRef<'a, crate::trap::TrapValue>),
+    /// A reference to a host object.
+    ExternRef(crate::extern_ref::ExternRef),
+}
+
+impl Val<'_> {
+    /// Gets the type of the value.
+    pub fn ty(&self) -> WasmValType {
+        match self {
+            Self::I32(_) => WasmValType::I32,
+            Self::I64(_) => WasmValType::I64,
+            Self::F32(_) => WasmValType::F32,
+            Self::F64(_) => WasmValType::F64,
+            #[cfg(feature = "simd-128")]
+            Self::V128(_) => WasmValType::V128,
+            Self::FuncRef(_) => WasmValType::FuncRef,
+            Self::ExternRef(_) => WasmValType::ExternRef,
+        }
+    }
+
+    /// Gets the value if it is an [`i32`].
+    pub fn i32(&self) -> Option<i32> {
+        match self {
+            Self::I32(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Gets the value if it is an [`i64`].
+    pub fn i64(&self) -> Option<i64> {
+        match self {
+            Self::I64(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Gets the value if it is an [`f32`].
+    pub fn f32(&self) -> Option<f32> {
+        match self {
+            Self::F32(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Gets the value if it is an [`f64`].
+    pub fn f64(&self) -> Option<f64> {
+        match self {
+            Self::F64(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Gets the value if it is an [`ExternRef`].
+    ///
+    /// [`ExternRef`]: crate::extern_ref::ExternRef
+    pub fn extern_ref(&self) -> Option<&crate::extern_ref::ExternRef> {
+        match self {
+            Self::ExternRef(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! val_from {
+    ($($ty:ty => $case:ident,)*) => {$(
+        impl From<$ty> for Val<'_> {
+            fn from(value: $ty) -> Self {
+                Self::$case(value)
+            }
+        }
+    )*};
+}
+
+val_from! {
+    i32 => I32,
+    i64 => I64,
+    f32 => F32,
+    f64 => F64,
+    crate::extern_ref::ExternRef => ExternRef,
+}
+
+#[cfg(feature = "simd-128")]
+impl From<crate::simd::v128::V128> for Val<'_> {
+    fn from(value: crate::simd::v128::V128) -> Self {
+        Self::V128(value)
+    }
+}
+
+impl<'a> From<crate::func_ref::FuncRef<'a, crate::trap::TrapValue>> for Val<'a> {
+    fn from(value: crate::func_ref::FuncRef<'a, crate::trap::TrapValue>) -> Self {
+        Self::FuncRef(value)
+    }
+}
+
+/// The results of calling an exported function with `Instance::call_dynamic()`.
+#[cfg(feature = "alloc")]
+pub type Vals<'a> = alloc::vec::Vec<Val<'a>>;
+
+/// Describes what kind of value was exported.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ExportKind {
+    /// An exported function with the given signature.
+    Function(&'static WasmSymbolSignature),
+    /// An exported linear memory.
+    Memory,
+    /// An exported global variable.
+    Global {
+        /// The type of the global's value.
+        ty: WasmValType,
+        /// Whether the global can be modified.
+        mutable: bool,
+    },
+}
+
+/// Describes an export of a WebAssembly module.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Export {
+    /// The name of the export.
+    pub name: &'static str,
+    /// The kind of value that was exported.
+    pub kind: ExportKind,
+}
+
+impl Export {
+    /// Describes an export with the given `name`.
+    pub const fn new(name: &'static str, kind: ExportKind) -> Self {
+        Self { name, kind }
+    }
+}
+
+/// Error type used when calling an exported function with `Instance::call_dynamic()`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CallError {
+    /// The module has no export with the given name.
+    NotFound,
+    /// The export with the given name is not a function.
+    NotAFunction,
+    /// The exported function is `async`, since it can reach an async import, so it can only be
+    /// called through its generated method.
+    Async,
+    /// The number or types of the arguments did not match the function's signature.
+    ArgumentMismatch {
+        /// The signature of the exported function.
+        expected: &'static WasmSymbolSignature,
+    },
+    /// A trap occured while calling the exported function.
+    Trap(crate::trap::TrapValue),
+}
+
+impl core::fmt::Display for CallError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::NotFound => f.write_str("export not found"),
+            Self::NotAFunction => f.write_str("export is not a function"),
+            Self::Async => f.write_str("exported function is async"),
+            Self::ArgumentMismatch { expected } => {
+                write!(f, "arguments do not match the signature {expected}")
+            }
+            Self::Trap(trap) => core::fmt::Display::fmt(trap, f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CallError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Trap(trap) => Some(trap),
+            _ => None,
+        }
+    }
+}
+
+impl From<crate::trap::TrapValue> for CallError {
+    fn from(trap: crate::trap::TrapValue) -> Self {
+        Self::Trap(trap)
+    }
+}
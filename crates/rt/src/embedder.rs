@@ -27,34 +27,138 @@ pub type Result<T> = ::core::result::Result<T, crate::trap::TrapValue>;
 /// [`embedder_with_import!`]: crate::embedder_with_import!
 pub type Imports = ();
 
+/// Counters used to limit the execution of WebAssembly modules translated with fuel metering,
+/// call depth checks, or epoch interruption enabled.
+///
+/// These are not stored in the [`State`] by default. An embedder opts in by using the
+/// `limits(...)` clause of the [`embedder_with_import!`] macro:
+///
+/// ```
+/// mod host {
+///     #[derive(Debug, Default)]
+///     pub struct MyImports;
+///
+///     wasm2rs_rt::embedder_with_import! {
+///         pub mod my_embedder(MyImports) limits(wasm2rs_rt::embedder::ExecutionLimits)
+///     }
+/// }
+///
+/// let state = host::my_embedder::State::default();
+/// state.set_fuel(1000);
+/// assert_eq!(state.remaining_fuel(), 1000);
+/// ```
+///
+/// The [`Fuel`] counter starts out empty. See the documentation for the [`fuel`] module for more
+/// information. Similarly, see the documentation for the [`epoch`] module for more information
+/// about the [`EpochDeadline`].
+///
+/// [`embedder_with_import!`]: crate::embedder_with_import!
+/// [`Fuel`]: crate::fuel::Fuel
+/// [`fuel`]: crate::fuel
+/// [`EpochDeadline`]: crate::epoch::EpochDeadline
+/// [`epoch`]: crate::epoch
+#[derive(Debug, Default)]
+pub struct ExecutionLimits {
+    fuel: crate::fuel::Fuel,
+    call_depth: crate::stack::CallDepth,
+    #[cfg(target_has_atomic = "64")]
+    epoch_deadline: crate::epoch::EpochDeadline,
+}
+
+impl Clone for ExecutionLimits {
+    fn clone(&self) -> Self {
+        Self {
+            fuel: self.fuel.clone(),
+            call_depth: crate::stack::CallDepth::with_limit(self.call_depth.limit()),
+            #[cfg(target_has_atomic = "64")]
+            epoch_deadline: self.epoch_deadline.clone(),
+        }
+    }
+}
+
+/// Reference-counted pointer used to share instances between WebAssembly modules that are
+/// linked together during translation.
+#[cfg(feature = "alloc")]
+pub type Shared<T> = alloc::rc::Rc<T>;
+
+/// Provides an imported function with access to the WebAssembly module instance that called it.
+///
+/// When a WebAssembly module is translated with `Translation::import_caller()` enabled, the
+/// calling `Instance` is passed to every imported function as its first argument. Since the
+/// generated `Instance` implements this trait, imported functions can be written to work with any
+/// module:
+///
+/// ```
+/// use wasm2rs_rt::embedder::Caller;
+/// use wasm2rs_rt::memory::Memory32;
+///
+/// # type Result<T> = core::result::Result<T, wasm2rs_rt::trap::TrapValue>;
+/// struct Env;
+///
+/// impl Env {
+///     fn byte_at(&self, caller: &impl Caller, address: i32) -> Result<i32> {
+///         let memory = caller.memory(0).expect("module has no memory");
+///         let mut byte = [0u8];
+///         memory.copy_to_slice(address as u32, &mut byte).expect("address out of bounds");
+///         Ok(byte[0].into())
+///     }
+/// }
+/// ```
+///
+/// The exported functions, globals, and memories of the calling instance can also be accessed by
+/// taking the generated `Instance` type directly, and calling back into it is allowed.
+pub trait Caller {
+    /// The type of the embedder state.
+    type State;
+
+    /// Gets the embedder state of the calling instance.
+    fn embedder(&self) -> &Self::State;
+
+    /// Gets the linear memory with the given index, whether it is defined or imported by the
+    /// calling instance.
+    ///
+    /// Returns `None` if the module does not have a memory with the given index.
+    fn memory(&self, index: u32) -> Option<&dyn crate::memory::Memory32>;
+}
+
 /// The default embedder state.
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
-pub struct State<I = ()> {
+///
+/// The type parameter `M` is the type of the WebAssembly module's main memory, which is allocated
+/// with the [`AllocateMemory32`] trait when the module is instantiated.
+///
+/// The type parameter `L` is used to store the [`ExecutionLimits`] required by WebAssembly
+/// modules translated with fuel metering, call depth checks, or epoch interruption enabled. By
+/// default, no limits are stored, and the state is [`Copy`] whenever the imports are.
+///
+/// Comparing or hashing a [`State`] only considers its imports, since the limits are counters that
+/// change as WebAssembly code runs.
+///
+/// [`AllocateMemory32`]: crate::memory::AllocateMemory32
+pub struct State<I = (), M = Memory0, L = ()> {
     imports: I,
+    limits: L,
+    _memory: core::marker::PhantomData<fn() -> M>,
 }
 
-impl<I> State<I> {
+impl<I, M, L: Default> State<I, M, L> {
     /// Intiializes the embedder state with the given `imports`.
     pub fn new(imports: I) -> Self {
-        Self { imports }
+        Self {
+            imports,
+            limits: L::default(),
+            _memory: core::marker::PhantomData,
+        }
     }
+}
 
+impl<I, M, L> State<I, M, L> {
     /// Initializes the WebAssembly module's main memory.
-    pub fn initialize_mem_0<const IDX: u32, const MIN: u32, const MAX: u32>(
-        &self,
-    ) -> Result<Memory0> {
-        #[cfg(not(feature = "alloc"))]
-        return Err(self.trap(
-            TrapCode::MemoryAllocation {
-                memory: IDX,
-                error: crate::memory::AllocationError::with_size(MIN),
-            },
-            None,
-        ));
-
-        #[cfg(feature = "alloc")]
-        return Memory0::with_limits(MIN, MAX)
-            .map_err(|error| self.trap(TrapCode::MemoryAllocation { memory: IDX, error }, None));
+    pub fn initialize_mem_0<const IDX: u32, const MIN: u32, const MAX: u32>(&self) -> Result<M>
+    where
+        M: crate::memory::AllocateMemory32,
+    {
+        M::allocate(MIN, MAX)
+            .map_err(|error| self.trap(TrapCode::MemoryAllocation { memory: IDX, error }, None))
     }
 
     /// Gets access to the module's imports.
@@ -63,7 +167,89 @@ impl<I> State<I> {
     }
 }
 
-impl<I> Trap for State<I> {
+impl<I, M> State<I, M, ExecutionLimits> {
+    /// Sets the amount of fuel that the WebAssembly module has remaining.
+    pub fn set_fuel(&self, amount: u64) {
+        self.limits.fuel.set(amount)
+    }
+
+    /// Refills the WebAssembly module with an additional `amount` of fuel.
+    pub fn add_fuel(&self, amount: u64) {
+        self.limits.fuel.add(amount)
+    }
+
+    /// Gets the amount of fuel that the WebAssembly module has remaining.
+    pub fn remaining_fuel(&self) -> u64 {
+        self.limits.fuel.remaining()
+    }
+
+    /// Sets the maximum number of nested WebAssembly function calls.
+    ///
+    /// See the documentation for [`CallDepth`] for more information.
+    ///
+    /// [`CallDepth`]: crate::stack::CallDepth
+    pub fn set_max_call_depth(&self, limit: u32) {
+        self.limits.call_depth.set_limit(limit)
+    }
+
+    /// Sets the [`EpochCounter`] used to determine when the WebAssembly module is interrupted.
+    ///
+    /// [`EpochCounter`]: crate::epoch::EpochCounter
+    #[cfg(target_has_atomic = "64")]
+    pub fn set_epoch_counter(&self, counter: &'static crate::epoch::EpochCounter) {
+        self.limits.epoch_deadline.set_counter(counter)
+    }
+
+    /// Sets the deadline for interrupting the WebAssembly module to the given number of `ticks`
+    /// after the current epoch.
+    #[cfg(target_has_atomic = "64")]
+    pub fn set_epoch_deadline(&self, ticks: u64) {
+        self.limits.epoch_deadline.set_deadline(ticks)
+    }
+}
+
+impl<I: Default, M, L: Default> Default for State<I, M, L> {
+    fn default() -> Self {
+        Self::new(I::default())
+    }
+}
+
+impl<I: Clone, M, L: Clone> Clone for State<I, M, L> {
+    fn clone(&self) -> Self {
+        Self {
+            imports: self.imports.clone(),
+            limits: self.limits.clone(),
+            _memory: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<I: Copy, M, L: Copy> Copy for State<I, M, L> {}
+
+impl<I: PartialEq, M, L> PartialEq for State<I, M, L> {
+    fn eq(&self, other: &Self) -> bool {
+        self.imports == other.imports
+    }
+}
+
+impl<I: Eq, M, L> Eq for State<I, M, L> {}
+
+impl<I: core::hash::Hash, M, L> core::hash::Hash for State<I, M, L> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.imports.hash(state)
+    }
+}
+
+impl<I: core::fmt::Debug, M, L: core::fmt::Debug> core::fmt::Debug for State<I, M, L> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("State")
+            .field("imports", &self.imports)
+            .field("limits", &self.limits)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<I, M, L> Trap for State<I, M, L> {
     type Repr = crate::trap::TrapValue;
 
     #[inline(never)]
@@ -76,23 +262,66 @@ impl<I> Trap for State<I> {
     }
 }
 
+impl<I, M> crate::fuel::FuelMetering for State<I, M, ExecutionLimits> {
+    fn fuel(&self) -> &crate::fuel::Fuel {
+        &self.limits.fuel
+    }
+}
+
+impl<I, M> crate::stack::CallDepthLimit for State<I, M, ExecutionLimits> {
+    fn call_depth(&self) -> &crate::stack::CallDepth {
+        &self.limits.call_depth
+    }
+}
+
+#[cfg(target_has_atomic = "64")]
+impl<I, M> crate::epoch::EpochInterruption for State<I, M, ExecutionLimits> {
+    fn epoch_deadline(&self) -> &crate::epoch::EpochDeadline {
+        &self.limits.epoch_deadline
+    }
+}
+
 /// Defines a new embedder module using the given type as the struct used to contain all of a
 /// WebAssembly module's imports.
+///
+/// The type used for the WebAssembly module's main memory can optionally be specified, which is
+/// useful in `no_std` environments where heap allocation is not available:
+///
+/// ```
+/// mod host {
+///     #[derive(Debug, Default)]
+///     pub struct MyImports;
+///
+///     wasm2rs_rt::embedder_with_import! {
+///         pub mod my_embedder(MyImports) memory(wasm2rs_rt::memory::EmptyMemory)
+///     }
+/// }
+/// ```
+///
+/// See the documentation for [`StaticMemory32`] for an example of a main memory that does not require
+/// heap allocation.
+///
+/// [`StaticMemory32`]: crate::memory::StaticMemory32
 #[macro_export]
 macro_rules! embedder_with_import {
+    (@memory) => { $crate::embedder::Memory0 };
+    (@memory $memory:ty) => { $memory };
     {
-        $vis:vis mod $embedder:ident($imports:tt) $(use {
+        $vis:vis mod $embedder:ident($imports:tt) $(memory($memory:ty))? $(limits($limits:ty))? $(use {
             $($import_namespace:tt as $import_alias:ident),*
         })?
     } => {
         $vis mod $embedder {
-            pub use $crate::embedder::{rt, Memory0, Result};
+            pub use $crate::embedder::{rt, Result};
 
             /// Contains the imports accessed by the WebAssembly module.
             pub type Imports = super::$imports;
 
+            /// The type used for the WebAssembly module's main memory.
+            pub type Memory0 = $crate::embedder_with_import!(@memory $($memory)?);
+
             /// State for the embedder of the WebAssembly module.
-            pub type State = $crate::embedder::State<Imports>;
+            pub type State = $crate::embedder::State<Imports, Memory0 $(, $limits)?>;
 
             $($(
                 #[allow(missing_docs)]
@@ -101,12 +330,12 @@ macro_rules! embedder_with_import {
         }
     };
     {
-        $vis:vis mod ($imports:tt) $(use {
+        $vis:vis mod ($imports:tt) $(memory($memory:ty))? $(limits($limits:ty))? $(use {
             $($import_namespace:tt as $import_alias:ident),*
         })?
     } => {
         $crate::embedder_with_import! {
-            $vis mod embedder($imports) $(use {
+            $vis mod embedder($imports) $(memory($memory))? $(limits($limits))? $(use {
                 $($import_namespace as $import_alias),*
             })?
         }
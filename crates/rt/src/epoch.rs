@@ -0,0 +1,154 @@
+//! Epoch-based interruption, used to stop long-running calls into a WebAssembly module.
+//!
+//! When epoch interruption is enabled during translation, `wasm2rs` generates calls to [`check()`]
+//! at function entries and loop headers. These compare the current value of an [`EpochCounter`],
+//! which can be incremented from another thread, against a deadline provided by the embedder.
+//!
+//! Unlike [fuel metering], checking the epoch only involves a single atomic load, but the point
+//! at which execution is interrupted is not deterministic.
+//!
+//! [fuel metering]: crate::fuel
+
+use crate::trap::{Trap, TrapCode};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// A counter that is periodically incremented, usually by a watchdog thread.
+///
+/// Counters are typically stored in a `static`, allowing them to be shared between all
+/// [`EpochDeadline`]s:
+///
+/// ```
+/// use wasm2rs_rt::epoch::EpochCounter;
+///
+/// static EPOCH: EpochCounter = EpochCounter::new();
+///
+/// EPOCH.increment();
+/// assert_eq!(EPOCH.current(), 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct EpochCounter {
+    value: AtomicU64,
+}
+
+impl EpochCounter {
+    /// Creates a new counter starting at epoch `0`.
+    pub const fn new() -> Self {
+        Self {
+            value: AtomicU64::new(0),
+        }
+    }
+
+    /// Gets the current epoch.
+    #[inline]
+    pub fn current(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+
+    /// Increments the current epoch, returning the previous epoch.
+    pub fn increment(&self) -> u64 {
+        self.value.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+/// Stores the epoch at which a WebAssembly module is interrupted.
+///
+/// By default, no [`EpochCounter`] is set, and the deadline is never reached.
+#[derive(Clone)]
+pub struct EpochDeadline {
+    counter: core::cell::Cell<Option<&'static EpochCounter>>,
+    deadline: core::cell::Cell<u64>,
+}
+
+impl Default for EpochDeadline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EpochDeadline {
+    /// Creates a new deadline with no [`EpochCounter`].
+    pub const fn new() -> Self {
+        Self {
+            counter: core::cell::Cell::new(None),
+            deadline: core::cell::Cell::new(u64::MAX),
+        }
+    }
+
+    /// Sets the counter that is compared against the deadline.
+    pub fn set_counter(&self, counter: &'static EpochCounter) {
+        self.counter.set(Some(counter))
+    }
+
+    /// Gets the counter that is compared against the deadline.
+    pub fn counter(&self) -> Option<&'static EpochCounter> {
+        self.counter.get()
+    }
+
+    /// Sets the deadline to the given number of `ticks` after the current epoch.
+    ///
+    /// If no [`EpochCounter`] was set, the deadline is relative to epoch `0`.
+    pub fn set_deadline(&self, ticks: u64) {
+        let current = self.counter.get().map(EpochCounter::current).unwrap_or(0);
+        self.deadline.set(current.saturating_add(ticks))
+    }
+
+    /// Returns `true` if the current epoch is at or past the deadline.
+    #[inline]
+    pub fn reached(&self) -> bool {
+        match self.counter.get() {
+            Some(counter) => counter.current() >= self.deadline.get(),
+            None => false,
+        }
+    }
+}
+
+impl core::fmt::Debug for EpochDeadline {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("EpochDeadline")
+            .field("counter", &self.counter.get())
+            .field("deadline", &self.deadline.get())
+            .finish()
+    }
+}
+
+/// Trait for embedders that provide an [`EpochDeadline`] to WebAssembly modules translated with
+/// epoch interruption enabled.
+pub trait EpochInterruption: Trap {
+    /// Gets the epoch deadline.
+    fn epoch_deadline(&self) -> &EpochDeadline;
+
+    /// Generates a trap when the epoch deadline is reached.
+    ///
+    /// This function is called by [`check()`].
+    #[inline(always)]
+    fn trap_interrupted(&self) -> Self::Repr {
+        self.trap(TrapCode::Interrupted, None)
+    }
+}
+
+impl<T: EpochInterruption + ?Sized> EpochInterruption for &T {
+    fn epoch_deadline(&self) -> &EpochDeadline {
+        <T>::epoch_deadline(self)
+    }
+
+    fn trap_interrupted(&self) -> Self::Repr {
+        <T>::trap_interrupted(self)
+    }
+}
+
+/// Checks if the epoch deadline has been reached.
+///
+/// # Errors
+///
+/// If the deadline was reached, a [`Trap`] is produced.
+#[inline]
+pub fn check<E>(embedder: &E) -> Result<(), E::Repr>
+where
+    E: EpochInterruption + ?Sized,
+{
+    if embedder.epoch_deadline().reached() {
+        Err(embedder.trap_interrupted())
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+//! Implementation for WebAssembly [**`externref`**] values, which allow host objects to be passed
+//! to and from WebAssembly modules.
+//!
+//! An [`ExternRef`] is either [`NULL`], a reference-counted host object, or a [`handle`] that
+//! refers to an object stored by the embedder. Host objects require the `alloc` feature, while
+//! handles are intended for `no_std` environments, where the embedder can keep its objects in a
+//! fixed-size table and hand out their indices:
+//!
+//! ```
+//! use wasm2rs_rt::extern_ref::ExternRef;
+//!
+//! let file = ExternRef::new(String::from("hello.txt"));
+//! assert_eq!(file.downcast_ref::<String>().map(String::as_str), Some("hello.txt"));
+//! assert!(file.downcast_ref::<u32>().is_none());
+//!
+//! let slot = ExternRef::from_handle(3);
+//! assert_eq!(slot.handle(), Some(3));
+//! assert!(slot.downcast_ref::<String>().is_none());
+//! ```
+//!
+//! WebAssembly code cannot inspect the contents of an [`ExternRef`], so the host functions that
+//! receive one are responsible for checking that it refers to an object of the expected type.
+//!
+//! Host objects are shared with an [`Arc`], and must be [`Send`] and [`Sync`]. This allows an
+//! [`ExternRef`] to be used with thread-safe instances, which may be shared between threads.
+//!
+//! [`Arc`]: alloc::sync::Arc
+//! [**`externref`**]: https://webassembly.github.io/spec/core/syntax/types.html#reference-types
+//! [`NULL`]: ExternRef::NULL
+//! [`handle`]: ExternRef::handle()
+
+#[derive(Clone)]
+enum Repr {
+    Null,
+    Handle(u32),
+    #[cfg(feature = "alloc")]
+    Object(alloc::sync::Arc<dyn core::any::Any + Send + Sync>),
+}
+
+/// Represents a WebAssembly [**`externref`**].
+///
+/// See the [module documentation](crate::extern_ref) for more information.
+///
+/// [**`externref`**]: https://webassembly.github.io/spec/core/syntax/types.html#reference-types
+#[derive(Clone)]
+pub struct ExternRef {
+    repr: Repr,
+}
+
+impl Default for ExternRef {
+    fn default() -> Self {
+        Self::NULL
+    }
+}
+
+impl ExternRef {
+    /// Gets the [`null`] reference.
+    ///
+    /// [`null`]: https://webassembly.github.io/spec/core/exec/runtime.html#values
+    pub const NULL: Self = Self { repr: Repr::Null };
+
+    /// Returns `true` if this [`ExternRef`] is [`NULL`].
+    ///
+    /// [`NULL`]: ExternRef::NULL
+    pub const fn is_null(&self) -> bool {
+        matches!(self.repr, Repr::Null)
+    }
+
+    /// Creates a reference to an object owned by the embedder, identified by the given `handle`.
+    pub const fn from_handle(handle: u32) -> Self {
+        Self {
+            repr: Repr::Handle(handle),
+        }
+    }
+
+    /// Gets the handle that this reference was created with, if it was created with
+    /// [`ExternRef::from_handle()`].
+    pub fn handle(&self) -> Option<u32> {
+        match self.repr {
+            Repr::Handle(handle) => Some(handle),
+            _ => None,
+        }
+    }
+
+    /// Creates a reference to a new host object.
+    #[cfg(feature = "alloc")]
+    pub fn new<T: core::any::Any + Send + Sync>(object: T) -> Self {
+        Self::from_arc(alloc::sync::Arc::new(object))
+    }
+
+    /// Creates a reference to an existing host object.
+    #[cfg(feature = "alloc")]
+    pub fn from_arc(object: alloc::sync::Arc<dyn core::any::Any + Send + Sync>) -> Self {
+        Self {
+            repr: Repr::Object(object),
+        }
+    }
+
+    /// Gets the host object that this reference refers to, if it was created with
+    /// [`ExternRef::new()`] or [`ExternRef::from_arc()`].
+    #[cfg(feature = "alloc")]
+    pub fn object(&self) -> Option<&alloc::sync::Arc<dyn core::any::Any + Send + Sync>> {
+        match &self.repr {
+            Repr::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    /// Gets a reference to the host object if it is of type `T`.
+    ///
+    /// Returns `None` if the reference is [`NULL`], a [`handle`], or refers to an object of a
+    /// different type.
+    ///
+    /// [`NULL`]: ExternRef::NULL
+    /// [`handle`]: ExternRef::handle()
+    #[cfg(feature = "alloc")]
+    pub fn downcast_ref<T: core::any::Any>(&self) -> Option<&T> {
+        let object: &dyn core::any::Any = &**self.object()?;
+        object.downcast_ref()
+    }
+
+    /// Gets a new strong reference to the host object if it is of type `T`.
+    ///
+    /// See the documentation for [`ExternRef::downcast_ref()`] for more information.
+    #[cfg(feature = "alloc")]
+    pub fn downcast<T: core::any::Any + Send + Sync>(&self) -> Option<alloc::sync::Arc<T>> {
+        alloc::sync::Arc::clone(self.object()?).downcast().ok()
+    }
+}
+
+/// Two [`ExternRef`]s are equal if they are both [`NULL`], have the same [`handle`], or refer to
+/// the same host object.
+///
+/// [`NULL`]: ExternRef::NULL
+/// [`handle`]: ExternRef::handle()
+impl PartialEq for ExternRef {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.repr, &other.repr) {
+            (Repr::Null, Repr::Null) => true,
+            (Repr::Handle(a), Repr::Handle(b)) => a == b,
+            #[cfg(feature = "alloc")]
+            (Repr::Object(a), Repr::Object(b)) => alloc::sync::Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for ExternRef {}
+
+impl core::fmt::Debug for ExternRef {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match &self.repr {
+            Repr::Null => f.write_str("ExternRef(null)"),
+            Repr::Handle(handle) => f.debug_tuple("ExternRef").field(handle).finish(),
+            #[cfg(feature = "alloc")]
+            Repr::Object(object) => f
+                .debug_tuple("ExternRef")
+                .field(&alloc::sync::Arc::as_ptr(object))
+                .finish(),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Instruction fuel metering, used to place a deterministic limit on the amount of work that a
+//! WebAssembly module can perform.
+//!
+//! When fuel metering is enabled during translation, `wasm2rs` generates calls to [`consume()`]
+//! at the start of each basic block, charging the number of instructions in the block against a
+//! [`Fuel`] counter provided by the embedder.
+
+use crate::trap::{Trap, TrapCode};
+
+/// A counter for the amount of fuel that a WebAssembly module has remaining.
+#[derive(Clone, Default, Eq, PartialEq)]
+pub struct Fuel {
+    remaining: core::cell::Cell<u64>,
+}
+
+impl Fuel {
+    /// Creates a new fuel counter with the given `amount` of fuel.
+    pub const fn new(amount: u64) -> Self {
+        Self {
+            remaining: core::cell::Cell::new(amount),
+        }
+    }
+
+    /// Gets the amount of fuel that is remaining.
+    pub fn remaining(&self) -> u64 {
+        self.remaining.get()
+    }
+
+    /// Sets the amount of fuel that is remaining.
+    pub fn set(&self, amount: u64) {
+        self.remaining.set(amount)
+    }
+
+    /// Refills the counter with an additional `amount` of fuel, saturating at [`u64::MAX`].
+    pub fn add(&self, amount: u64) {
+        self.remaining
+            .set(self.remaining.get().saturating_add(amount))
+    }
+
+    /// Subtracts the given `amount` of fuel, returning `false` if there was not enough fuel
+    /// remaining.
+    ///
+    /// If there is not enough fuel, then the counter is set to `0`.
+    pub fn consume(&self, amount: u64) -> bool {
+        match self.remaining.get().checked_sub(amount) {
+            Some(remaining) => {
+                self.remaining.set(remaining);
+                true
+            }
+            None => {
+                self.remaining.set(0);
+                false
+            }
+        }
+    }
+}
+
+impl core::fmt::Debug for Fuel {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("Fuel").field(&self.remaining.get()).finish()
+    }
+}
+
+/// Trait for embedders that provide a [`Fuel`] counter to WebAssembly modules translated with
+/// fuel metering enabled.
+pub trait FuelMetering: Trap {
+    /// Gets the fuel counter.
+    fn fuel(&self) -> &Fuel;
+
+    /// Generates a trap when a WebAssembly module runs out of fuel.
+    ///
+    /// This function is called by [`consume()`].
+    #[inline(always)]
+    fn trap_out_of_fuel(&self) -> Self::Repr {
+        self.trap(TrapCode::OutOfFuel, None)
+    }
+}
+
+impl<T: FuelMetering + ?Sized> FuelMetering for &T {
+    fn fuel(&self) -> &Fuel {
+        <T>::fuel(self)
+    }
+
+    fn trap_out_of_fuel(&self) -> Self::Repr {
+        <T>::trap_out_of_fuel(self)
+    }
+}
+
+/// Charges an `amount` of fuel, which is the cost of the basic block that is about to be
+/// executed.
+///
+/// # Errors
+///
+/// If there is not enough fuel remaining, a [`Trap`] is produced.
+#[inline]
+pub fn consume<E>(amount: u64, embedder: &E) -> Result<(), E::Repr>
+where
+    E: FuelMetering + ?Sized,
+{
+    if embedder.fuel().consume(amount) {
+        Ok(())
+    } else {
+        Err(embedder.trap_out_of_fuel())
+    }
+}
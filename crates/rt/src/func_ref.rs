@@ -1,6 +1,12 @@
 //! Runtime support for [references to functions].
 //!
+//! When a WebAssembly module is translated with `Translation::func_refs()` enabled, the generated
+//! `Instance::func_ref(name)` method returns a [`FuncRef`] that calls an exported function,
+//! borrowing the instance. A [`FuncRef`] created by the host, such as with [`from_closure_1()`],
+//! can be passed to a WebAssembly function with a `funcref` parameter.
+//!
 //! [references to functions]: https://webassembly.github.io/spec/core/syntax/types.html#reference-types
+//! [`from_closure_1()`]: FuncRef::from_closure_1()
 
 mod raw;
 mod signature;
@@ -0,0 +1,37 @@
+//! Support for WebAssembly modules translated with async imports.
+//!
+//! When some imported functions are marked as async during translation, `wasm2rs` generates an
+//! `async fn` for every function that can reach one of them, and `.await`s each call to such a
+//! function. Exported functions that can reach an async import are also `async fn`s.
+//!
+//! Async imports are implemented by the embedder as functions returning a [`Future`]:
+//!
+//! ```
+//! # type Result<T> = core::result::Result<T, wasm2rs_rt::trap::TrapValue>;
+//! struct Env;
+//!
+//! impl Env {
+//!     async fn fetch(&self, key: i32) -> Result<i32> {
+//!         // Some asynchronous I/O would be performed here.
+//!         Ok(key * 2)
+//!     }
+//! }
+//! ```
+//!
+//! Instead of running calls on an executor, they can also be suspended and resumed one step at
+//! a time, see the [`coroutine`] module for more information.
+//!
+//! [`Future`]: core::future::Future
+//! [`coroutine`]: crate::coroutine
+
+/// Moves a [`Future`] to the heap.
+///
+/// This is used when calling `async` functions that are recursive, since the resulting future
+/// would otherwise have an infinite size.
+///
+/// [`Future`]: core::future::Future
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn boxed<F: core::future::Future>(future: F) -> core::pin::Pin<alloc::boxed::Box<F>> {
+    alloc::boxed::Box::pin(future)
+}
@@ -24,6 +24,10 @@ impl GlobalValue for f64 {
     const ZERO: f64 = 0.0;
 }
 
+impl GlobalValue for crate::extern_ref::ExternRef {
+    const ZERO: Self = Self::NULL;
+}
+
 /// Represents a [WebAssembly global] variable.
 ///
 /// [WebAssembly global]: https://webassembly.github.io/spec/core/syntax/modules.html#globals
@@ -137,3 +141,154 @@ impl<T: GlobalValue + core::fmt::Debug> core::fmt::Debug for Global<T> {
         self.borrow_with(|contents| core::fmt::Debug::fmt(contents, f))
     }
 }
+
+/// Trait for types of value that can be stored in an [`AtomicGlobal`].
+pub trait AtomicGlobalValue: GlobalValue + Copy {
+    /// The atomic type used to store values of this type.
+    type Atomic: Send + Sync;
+
+    /// Creates an atomic value containing the given `value`.
+    fn new_atomic(value: Self) -> Self::Atomic;
+
+    /// Loads the value contained in the `atomic`.
+    fn load(atomic: &Self::Atomic) -> Self;
+
+    /// Stores the `value` into the `atomic`.
+    fn store(atomic: &Self::Atomic, value: Self);
+}
+
+macro_rules! atomic_global_values {
+    (@to $value:ident) => { $value };
+    (@to $value:ident as $bits:ty) => { $value.to_bits() };
+    (@from $value:ident for $ty:ty) => { $value };
+    (@from $value:ident for $ty:ty as $bits:ty) => { <$ty>::from_bits($value) };
+    ($($ty:ty => $atomic:ident($width:literal) $(as $bits:ty)?;)*) => {$(
+        #[cfg(target_has_atomic = $width)]
+        impl AtomicGlobalValue for $ty {
+            type Atomic = core::sync::atomic::$atomic;
+
+            fn new_atomic(value: Self) -> Self::Atomic {
+                core::sync::atomic::$atomic::new(atomic_global_values!(@to value $(as $bits)?))
+            }
+
+            fn load(atomic: &Self::Atomic) -> Self {
+                let value = atomic.load(core::sync::atomic::Ordering::Relaxed);
+                atomic_global_values!(@from value for $ty $(as $bits)?)
+            }
+
+            fn store(atomic: &Self::Atomic, value: Self) {
+                atomic.store(
+                    atomic_global_values!(@to value $(as $bits)?),
+                    core::sync::atomic::Ordering::Relaxed,
+                )
+            }
+        }
+    )*};
+}
+
+atomic_global_values! {
+    i32 => AtomicI32("32");
+    i64 => AtomicI64("64");
+    f32 => AtomicU32("32") as u32;
+    f64 => AtomicU64("64") as u64;
+}
+
+/// Represents a [WebAssembly global] variable that can be shared between threads.
+///
+/// Unlike a [`Global`], an [`AtomicGlobal`] is [`Sync`], and is used to store mutable globals in
+/// modules translated with thread-safe instances. Since WebAssembly's `global.get` and
+/// `global.set` instructions are not atomic operations, accesses use [`Relaxed`] ordering.
+///
+/// [WebAssembly global]: https://webassembly.github.io/spec/core/syntax/modules.html#globals
+/// [`Relaxed`]: core::sync::atomic::Ordering::Relaxed
+#[repr(transparent)]
+pub struct AtomicGlobal<T: AtomicGlobalValue> {
+    contents: T::Atomic,
+}
+
+impl<T: AtomicGlobalValue> Default for AtomicGlobal<T> {
+    fn default() -> Self {
+        Self::new(T::ZERO)
+    }
+}
+
+impl<T: AtomicGlobalValue> AtomicGlobal<T> {
+    /// Creates a new global variable with the specified initial value.
+    pub fn new(value: T) -> Self {
+        Self {
+            contents: T::new_atomic(value),
+        }
+    }
+
+    /// Sets the value of the global variable.
+    ///
+    /// This implements the [`global.set`] instruction.
+    ///
+    /// [`global.set`]: https://webassembly.github.io/spec/core/syntax/instructions.html#variable-instructions
+    pub fn set(&self, value: T) {
+        T::store(&self.contents, value)
+    }
+
+    /// Gets the value of the global variable.
+    ///
+    /// This implements the [`global.get`] instruction.
+    ///
+    /// [`global.get`]: https://webassembly.github.io/spec/core/syntax/instructions.html#variable-instructions
+    pub fn get(&self) -> T {
+        T::load(&self.contents)
+    }
+}
+
+impl<T: AtomicGlobalValue> Clone for AtomicGlobal<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.get())
+    }
+}
+
+impl<T: AtomicGlobalValue + PartialEq> PartialEq for AtomicGlobal<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<T: AtomicGlobalValue, U> PartialEq<&U> for AtomicGlobal<T>
+where
+    T: PartialEq<U>,
+{
+    fn eq(&self, other: &&U) -> bool {
+        self.get().eq(*other)
+    }
+}
+
+impl<T: AtomicGlobalValue + core::fmt::Debug> core::fmt::Debug for AtomicGlobal<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&self.get(), f)
+    }
+}
+
+/// Appends the value of a mutable global to a [`snapshot`].
+///
+/// [`snapshot`]: crate::snapshot
+#[cfg(feature = "alloc")]
+pub fn write_snapshot<T>(value: T, snapshot: &mut crate::snapshot::Writer)
+where
+    T: GlobalValue + crate::memory::Pod,
+{
+    snapshot.write_value(value)
+}
+
+/// Reads the value of a mutable global from a [`snapshot`].
+///
+/// # Errors
+///
+/// Returns an error if the end of the snapshot was reached.
+///
+/// [`snapshot`]: crate::snapshot
+pub fn read_snapshot<T>(
+    snapshot: &mut crate::snapshot::Reader<'_>,
+) -> Result<T, crate::snapshot::SnapshotError>
+where
+    T: GlobalValue + crate::memory::Pod,
+{
+    snapshot.read_value()
+}
@@ -18,11 +18,21 @@ extern crate std;
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
+pub mod coroutine;
+pub mod dylink;
+pub mod dynamic;
 pub mod embedder;
+#[cfg(target_has_atomic = "64")]
+pub mod epoch;
+pub mod extern_ref;
+pub mod fuel;
 pub mod func_ref;
+pub mod future;
 pub mod global;
+pub mod linker;
 pub mod math;
 pub mod memory;
 pub mod simd;
+pub mod snapshot;
 pub mod stack;
 pub mod trap;
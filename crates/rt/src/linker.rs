@@ -0,0 +1,447 @@
+//! Provides imports to WebAssembly modules by name at run time.
+//!
+//! Usually, the imports of a WebAssembly module are provided by a Rust struct with a method for
+//! each import, which is fixed at compile time. When a WebAssembly module is translated with
+//! `Translation::runtime_linker()` enabled, its imported functions are instead looked up by their
+//! module and field names in a [`Linker`] when the module is instantiated. This allows hosts to
+//! choose which functions to provide at run time, such as when providing test doubles:
+//!
+//! ```
+//! use wasm2rs_rt::func_ref::FuncRef;
+//! use wasm2rs_rt::linker::Linker;
+//!
+//! let mut linker = Linker::new();
+//! linker.define(
+//!     "env",
+//!     "add",
+//!     FuncRef::from_closure_2(|a: i32, b: i32| Ok(a.wrapping_add(b))),
+//! );
+//!
+//! assert!(linker.get("env", "add").is_some());
+//! ```
+//!
+//! The [`Linker`] is used as the imports of the embedder:
+//!
+//! ```
+//! mod host {
+//!     pub use wasm2rs_rt::linker::Linker;
+//!
+//!     wasm2rs_rt::embedder_with_import! {
+//!         pub mod embedder(Linker)
+//!     }
+//! }
+//! ```
+//!
+//! Immutable globals can also be defined with [`Linker::define_global()`].
+//!
+//! When an import is not defined, or its [`FuncRefSignature`] or type does not match the import,
+//! instantiation fails with a [`LinkError`].
+//!
+//! [`FuncRefSignature`]: crate::func_ref::FuncRefSignature
+
+use crate::func_ref::SignatureMismatchError;
+use crate::stack::trace::WasmValType;
+use crate::trap::LimitsCheck;
+
+/// Describes the kinds of values that can be imported by a WebAssembly module.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+#[allow(missing_docs)]
+pub enum ImportKind {
+    Function,
+    Table,
+    Memory,
+    Global,
+}
+
+impl core::fmt::Display for ImportKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Self::Function => "function",
+            Self::Table => "table",
+            Self::Memory => "memory",
+            Self::Global => "global",
+        })
+    }
+}
+
+/// Describes the type of an imported [WebAssembly global].
+///
+/// [WebAssembly global]: crate::global
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct GlobalType {
+    /// The type of the global's value.
+    pub ty: WasmValType,
+    /// Whether the global can be modified.
+    pub mutable: bool,
+}
+
+impl core::fmt::Display for GlobalType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.mutable {
+            write!(f, "(mut {})", self.ty)
+        } else {
+            core::fmt::Display::fmt(&self.ty, f)
+        }
+    }
+}
+
+/// Describes why an import could not be linked.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum LinkErrorKind {
+    /// No value was defined for the import.
+    Undefined,
+    /// A value of a different kind was defined for the import, such as a global where a function
+    /// was expected.
+    KindMismatch {
+        /// The kind of value that the module imports.
+        expected: ImportKind,
+        /// The kind of value that was defined.
+        actual: ImportKind,
+    },
+    /// The imported function was defined with the wrong signature.
+    SignatureMismatch(SignatureMismatchError),
+    /// The imported [linear memory] did not have matching [`limits`].
+    ///
+    /// [linear memory]: crate::memory::Memory32
+    /// [`limits`]: crate::memory::Memory32::limit
+    MemoryLimits(LimitsCheck),
+    /// The imported global did not have the expected type or mutability.
+    GlobalTypeMismatch {
+        /// The type of the global that the module imports.
+        expected: GlobalType,
+        /// The type of the global that was defined.
+        actual: GlobalType,
+    },
+}
+
+impl core::fmt::Display for LinkErrorKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Undefined => f.write_str("import was not defined"),
+            Self::KindMismatch { expected, actual } => {
+                write!(f, "expected {expected}, but got {actual}")
+            }
+            Self::SignatureMismatch(error) => core::fmt::Display::fmt(error, f),
+            Self::MemoryLimits(limits) => write!(f, "{limits} pages in memory"),
+            Self::GlobalTypeMismatch { expected, actual } => {
+                write!(f, "expected global of type {expected}, but got {actual}")
+            }
+        }
+    }
+}
+
+/// Error type used when an import of a WebAssembly module could not be linked.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct LinkError {
+    module: &'static str,
+    name: &'static str,
+    kind: LinkErrorKind,
+}
+
+impl LinkError {
+    /// Creates a new error for the import with the given `module` and `name`.
+    pub const fn new(module: &'static str, name: &'static str, kind: LinkErrorKind) -> Self {
+        Self { module, name, kind }
+    }
+
+    /// Gets the name of the module namespace of the import.
+    pub fn module(&self) -> &'static str {
+        self.module
+    }
+
+    /// Gets the name of the import.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Describes why the import could not be linked.
+    pub fn kind(&self) -> &LinkErrorKind {
+        &self.kind
+    }
+}
+
+impl core::fmt::Display for LinkError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "could not link import {:?} from {:?}: {}",
+            self.name, self.module, self.kind
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LinkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            LinkErrorKind::SignatureMismatch(error) => Some(error),
+            LinkErrorKind::MemoryLimits(limits) => Some(limits),
+            _ => None,
+        }
+    }
+}
+
+/// Error type returned by the generated `instantiate` function.
+///
+/// Imports that could not be linked are reported separately from traps, which only occur when
+/// instantiation runs code, such as the start function or the writing of data segments.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum InstantiationError {
+    /// An import of the module could not be linked.
+    Link(LinkError),
+    /// A trap occured while instantiating the module.
+    Trap(crate::trap::TrapValue),
+}
+
+impl core::fmt::Display for InstantiationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Link(error) => core::fmt::Display::fmt(error, f),
+            Self::Trap(trap) => core::fmt::Display::fmt(trap, f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InstantiationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Link(error) => Some(error),
+            Self::Trap(trap) => Some(trap),
+        }
+    }
+}
+
+impl From<LinkError> for InstantiationError {
+    fn from(error: LinkError) -> Self {
+        Self::Link(error)
+    }
+}
+
+impl From<crate::trap::TrapValue> for InstantiationError {
+    fn from(trap: crate::trap::TrapValue) -> Self {
+        Self::Trap(trap)
+    }
+}
+
+/// Trait for the types of immutable globals that can be defined in a [`Linker`].
+pub trait LinkedGlobalValue: Sized {
+    /// The WebAssembly type of the global.
+    const TYPE: WasmValType;
+
+    /// Gets the value if it has the correct type.
+    fn from_val(value: &crate::dynamic::Val<'_>) -> Option<Self>;
+}
+
+macro_rules! linked_global_values {
+    ($($ty:ty => $case:ident,)*) => {$(
+        impl LinkedGlobalValue for $ty {
+            const TYPE: WasmValType = WasmValType::$case;
+
+            fn from_val(value: &crate::dynamic::Val<'_>) -> Option<Self> {
+                match value {
+                    crate::dynamic::Val::$case(value) => Some(*value),
+                    _ => None,
+                }
+            }
+        }
+    )*};
+}
+
+linked_global_values! {
+    i32 => I32,
+    i64 => I64,
+    f32 => F32,
+    f64 => F64,
+}
+
+/// The type of function references that can be defined in a [`Linker`].
+pub type LinkedFuncRef = crate::func_ref::FuncRef<'static, crate::trap::TrapValue>;
+
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug)]
+enum Definition {
+    Function(LinkedFuncRef),
+    Global(crate::dynamic::Val<'static>),
+}
+
+#[cfg(feature = "alloc")]
+impl Definition {
+    fn kind_mismatch(
+        &self,
+        module: &'static str,
+        name: &'static str,
+        expected: ImportKind,
+    ) -> LinkError {
+        let actual = match self {
+            Self::Function(_) => ImportKind::Function,
+            Self::Global(_) => ImportKind::Global,
+        };
+
+        LinkError::new(
+            module,
+            name,
+            LinkErrorKind::KindMismatch { expected, actual },
+        )
+    }
+}
+
+/// Maps the module and field names of imported functions and globals to their definitions.
+///
+/// See the [module documentation](crate::linker) for more information.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Default)]
+pub struct Linker {
+    modules: alloc::collections::BTreeMap<
+        alloc::boxed::Box<str>,
+        alloc::collections::BTreeMap<alloc::boxed::Box<str>, Definition>,
+    >,
+}
+
+#[cfg(feature = "alloc")]
+impl Linker {
+    /// Creates a new [`Linker`] with no definitions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn insert(&mut self, module: &str, name: &str, definition: Definition) -> &mut Self {
+        self.modules
+            .entry(module.into())
+            .or_default()
+            .insert(name.into(), definition);
+        self
+    }
+
+    fn definition(
+        &self,
+        module: &'static str,
+        name: &'static str,
+    ) -> Result<&Definition, LinkError> {
+        self.modules
+            .get(module)
+            .and_then(|names| names.get(name))
+            .ok_or(LinkError::new(module, name, LinkErrorKind::Undefined))
+    }
+
+    /// Defines the function imported with the given `module` and `name`, replacing any previous
+    /// definition.
+    ///
+    /// Function references are typically created with helpers such as
+    /// [`FuncRef::from_closure_1()`].
+    ///
+    /// [`FuncRef::from_closure_1()`]: crate::func_ref::FuncRef::from_closure_1()
+    pub fn define(&mut self, module: &str, name: &str, func: LinkedFuncRef) -> &mut Self {
+        self.insert(module, name, Definition::Function(func))
+    }
+
+    /// Defines the immutable global imported with the given `module` and `name`, replacing any
+    /// previous definition.
+    pub fn define_global<V>(&mut self, module: &str, name: &str, value: V) -> &mut Self
+    where
+        V: Into<crate::dynamic::Val<'static>>,
+    {
+        self.insert(module, name, Definition::Global(value.into()))
+    }
+
+    /// Gets the definition of the function imported with the given `module` and `name`.
+    pub fn get(&self, module: &str, name: &str) -> Option<&LinkedFuncRef> {
+        match self.modules.get(module)?.get(name)? {
+            Definition::Function(func) => Some(func),
+            Definition::Global(_) => None,
+        }
+    }
+
+    /// Gets the value of the immutable global imported with the given `module` and `name`.
+    pub fn get_global(&self, module: &str, name: &str) -> Option<&crate::dynamic::Val<'static>> {
+        match self.modules.get(module)?.get(name)? {
+            Definition::Global(value) => Some(value),
+            Definition::Function(_) => None,
+        }
+    }
+
+    /// Looks up the definition of an imported function, checking that it has the expected
+    /// signature.
+    ///
+    /// This is an implementation detail used by generated code. Refer to the documentation for
+    /// [`FuncRef::cast()`] for the valid types to use as `C`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`LinkError`] if the function is not defined, or if it does not have the correct
+    /// signature.
+    ///
+    /// [`FuncRef::cast()`]: crate::func_ref::FuncRef::cast()
+    pub fn function<C>(
+        &self,
+        module: &'static str,
+        name: &'static str,
+    ) -> Result<LinkedFuncRef, LinkError>
+    where
+        C: Clone
+            + Copy
+            + Send
+            + Sync
+            + core::marker::Unpin
+            + core::panic::UnwindSafe
+            + core::panic::RefUnwindSafe
+            + 'static,
+    {
+        use crate::func_ref::FuncRefCastError;
+
+        let func = match self.definition(module, name)? {
+            Definition::Function(func) => func,
+            other => return Err(other.kind_mismatch(module, name, ImportKind::Function)),
+        };
+
+        match func.cast::<C>() {
+            Ok(_) => Ok(func.clone()),
+            Err(FuncRefCastError::SignatureMismatch(error)) => Err(LinkError::new(
+                module,
+                name,
+                LinkErrorKind::SignatureMismatch(error),
+            )),
+            Err(FuncRefCastError::Null { .. }) => {
+                Err(LinkError::new(module, name, LinkErrorKind::Undefined))
+            }
+        }
+    }
+
+    /// Looks up the value of an imported immutable global, checking that it has the expected
+    /// type.
+    ///
+    /// This is an implementation detail used by generated code.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`LinkError`] if the global is not defined, or if it does not have the correct
+    /// type.
+    pub fn global<T: LinkedGlobalValue>(
+        &self,
+        module: &'static str,
+        name: &'static str,
+    ) -> Result<T, LinkError> {
+        let value = match self.definition(module, name)? {
+            Definition::Global(value) => value,
+            other => return Err(other.kind_mismatch(module, name, ImportKind::Global)),
+        };
+
+        T::from_val(value).ok_or(LinkError::new(
+            module,
+            name,
+            LinkErrorKind::GlobalTypeMismatch {
+                expected: GlobalType {
+                    ty: T::TYPE,
+                    mutable: false,
+                },
+                actual: GlobalType {
+                    ty: value.ty(),
+                    mutable: false,
+                },
+            },
+        ))
+    }
+}
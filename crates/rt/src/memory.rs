@@ -6,20 +6,44 @@ mod allocation;
 #[cfg(feature = "alloc")]
 mod heap;
 
+#[cfg(mmap_memory)]
+mod mmap;
+
+#[cfg(feature = "std")]
+mod sync_heap;
+
 #[cfg(feature = "alloc")]
 pub use heap::HeapMemory32;
 
+#[cfg(feature = "std")]
+pub use sync_heap::SyncHeapMemory32;
+
+#[cfg(mmap_memory)]
+pub use mmap::MmapMemory32;
+
 mod empty;
+pub mod exclusive;
 mod helpers;
+mod ptr;
+mod static_memory;
 
 pub use empty::EmptyMemory;
 pub use helpers::*;
+pub use ptr::{Pod, WasmPtr, WasmSlice, WasmStr};
+pub use static_memory::{
+    Borrowed, BorrowedPages, Inline, InlinePages, PageStorage, StaticBuffer, StaticMemory32,
+};
+#[cfg(target_has_atomic = "8")]
+pub use static_memory::{StaticPages, TakenPages};
 
 /// The size, in bytes, of a WebAssembly linear memory [page].
 ///
 /// [page]: https://webassembly.github.io/spec/core/exec/runtime.html#page-size
 pub const PAGE_SIZE: u32 = 65536;
 
+/// The maximum number of pages that a 32-bit linear memory can have.
+const MAX_PAGES: u32 = 65536;
+
 /// A constant value used to indicate that a [`memory.grow`] operation failed.
 ///
 /// [`memory.grow`]: Memory32::grow()
@@ -205,6 +229,63 @@ where
     Ok(())
 }
 
+/// Proof that an access of `N` bytes to a [`Memory32`] is within the minimum size of the memory.
+///
+/// Since linear memories can never shrink, an access whose address is known to be within the
+/// minimum number of pages is always in bounds. These proofs are created by the `*_in_bounds`
+/// helper functions, which are generated when `wasm2rs` can determine an upper bound for the
+/// address of an access at compile time. Creating a proof for an upper bound that is not within
+/// the minimum size is a compile-time error.
+///
+/// See the documentation for the [`Memory32::load_in_bounds()`] method for more information.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct InBounds<const N: usize> {
+    address: u32,
+    minimum: u32,
+}
+
+impl<const N: usize> InBounds<N> {
+    /// Gets the address of the first byte that is accessed.
+    pub const fn address(self) -> u32 {
+        self.address
+    }
+
+    /// Gets the minimum number of pages that the access was proven to be within.
+    ///
+    /// A [`Memory32`] that is known to never have fewer than this many pages can perform the access
+    /// without a bounds check.
+    pub const fn minimum(self) -> u32 {
+        self.minimum
+    }
+}
+
+/// Creates [`InBounds`] proofs, checking at compile time that accessing `N` bytes at any address up
+/// to `MAX_ADDRESS` is within the `MINIMUM` number of pages.
+struct Proof<const MAX_ADDRESS: u32, const N: usize, const MINIMUM: u32>;
+
+impl<const MAX_ADDRESS: u32, const N: usize, const MINIMUM: u32> Proof<MAX_ADDRESS, N, MINIMUM> {
+    const VALID: () = assert!(
+        MAX_ADDRESS as u64 + N as u64 <= MINIMUM as u64 * PAGE_SIZE as u64,
+        "address is not within the minimum size of the memory"
+    );
+
+    /// Gets a proof for an access at the given `address`, or `None` if it is greater than the
+    /// `MAX_ADDRESS`.
+    fn check(address: u32) -> Option<InBounds<N>> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID;
+
+        if address <= MAX_ADDRESS {
+            Some(InBounds {
+                address,
+                minimum: MINIMUM,
+            })
+        } else {
+            None
+        }
+    }
+}
+
 mod private {
     /// Implementation detail to allow downcasting an arbitrary `Memory32` implementation.
     #[derive(Debug)]
@@ -324,6 +405,40 @@ pub trait Memory32 {
         }
     }
 
+    /// Reads `N` bytes from linear memory at an address that is known to be in bounds.
+    ///
+    /// The default implementation still checks that the access is in bounds, since [`Memory32`]
+    /// implementations provided by the embedder could be smaller than the minimum size the
+    /// WebAssembly module expects. Implementations that never have fewer than
+    /// [`InBounds::minimum()`] pages skip the check, such as [`HeapMemory32`]s, [`MmapMemory32`]s,
+    /// and [`StaticMemory32`]s that were created with at least that many pages.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the access is not actually in bounds.
+    fn load_in_bounds<const N: usize>(&self, proof: InBounds<N>) -> BoundsCheck<[u8; N]>
+    where
+        Self: Sized,
+    {
+        let mut dst = [0u8; N];
+        self.copy_to_slice(proof.address(), &mut dst)?;
+        Ok(dst)
+    }
+
+    /// Writes `N` bytes into linear memory at an address that is known to be in bounds.
+    ///
+    /// See the documentation for [`Memory32::load_in_bounds()`] for more information.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the access is not actually in bounds.
+    fn store_in_bounds<const N: usize>(&self, proof: InBounds<N>, src: [u8; N]) -> BoundsCheck<()>
+    where
+        Self: Sized,
+    {
+        self.copy_from_slice(proof.address(), &src)
+    }
+
     /// Loads the value of the byte stored at the given address.
     fn i8_load(&self, addr: u32) -> BoundsCheck<i8> {
         let mut dst = 0u8;
@@ -390,7 +505,145 @@ pub trait Memory32 {
     }
 }
 
-//pub trait UnsharedMemory32: Memory32 + core::ops::Deref<Target = [u8]> + core::ops::DerefMut8 where Self: !Sync {}
+/// Trait for [`Memory32`] implementations that can be allocated when a WebAssembly module is
+/// instantiated.
+///
+/// This is used by the [`embedder::State`] to provide a module's main memory.
+///
+/// [`embedder::State`]: crate::embedder::State
+pub trait AllocateMemory32: Memory32 + Sized {
+    /// Allocates a linear memory, with a minimum and maximum number of pages.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `minimum` number of pages could not be allocated.
+    fn allocate(minimum: u32, maximum: u32) -> Result<Self, AllocationError>;
+}
+
+/// Trait for [`Memory32`] implementations whose contents can be accessed directly through a
+/// mutable reference.
+///
+/// This is used by code generated with exclusive access to the module's instance, which calls the
+/// helper functions in the [`exclusive`] module instead of the ones in this module.
+pub trait Memory32Mut: Memory32 {
+    /// Returns a mutable slice to the linear memory contents.
+    ///
+    /// The length of the slice must be the [`size()`] multiplied by the [`PAGE_SIZE`].
+    ///
+    /// [`size()`]: Memory32::size()
+    fn as_mut_slice(&mut self) -> &mut [u8];
+
+    /// Reads `N` bytes from linear memory at an address that is known to be in bounds.
+    ///
+    /// The default implementation calls [`Memory32::load_in_bounds()`]. Implementations that can
+    /// skip the bounds check more cheaply given a mutable reference can override this.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the access is not actually in bounds.
+    fn load_in_bounds_mut<const N: usize>(&mut self, proof: InBounds<N>) -> BoundsCheck<[u8; N]>
+    where
+        Self: Sized,
+    {
+        self.load_in_bounds(proof)
+    }
+
+    /// Writes `N` bytes into linear memory at an address that is known to be in bounds.
+    ///
+    /// See the documentation for [`Memory32Mut::load_in_bounds_mut()`] for more information.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the access is not actually in bounds.
+    fn store_in_bounds_mut<const N: usize>(
+        &mut self,
+        proof: InBounds<N>,
+        src: [u8; N],
+    ) -> BoundsCheck<()>
+    where
+        Self: Sized,
+    {
+        self.store_in_bounds(proof, src)
+    }
+}
+
+/// Appends the size and contents of a linear memory to a [`snapshot`].
+///
+/// Trailing zero bytes are not included in the snapshot.
+///
+/// [`snapshot`]: crate::snapshot
+#[cfg(feature = "alloc")]
+pub fn write_snapshot<M>(mem: &M, snapshot: &mut crate::snapshot::Writer)
+where
+    M: Memory32 + ?Sized,
+{
+    let size = mem.size();
+    let len = usize::try_from(u64::from(size) * u64::from(PAGE_SIZE)).unwrap_or(usize::MAX);
+    let mut contents = alloc::vec![0u8; len];
+
+    // A memory can't be smaller than its own size.
+    let _ = mem.copy_to_slice(0, &mut contents);
+
+    let used = contents
+        .iter()
+        .rposition(|b| *b != 0)
+        .map_or(0, |last| last + 1);
+
+    snapshot.write_value(size);
+    snapshot.write_bytes(&contents[..used]);
+}
+
+/// Restores the size and contents of a linear memory from a [`snapshot`], growing it if
+/// necessary.
+///
+/// # Errors
+///
+/// Returns an error if the snapshot is malformed, or if the memory could not be resized to the
+/// size recorded in the snapshot.
+///
+/// [`snapshot`]: crate::snapshot
+pub fn restore_snapshot<M>(
+    mem: &M,
+    snapshot: &mut crate::snapshot::Reader<'_>,
+) -> Result<(), crate::snapshot::SnapshotError>
+where
+    M: Memory32 + ?Sized,
+{
+    use crate::snapshot::SnapshotError;
+
+    let expected = snapshot.read_value::<u32>()?;
+    let contents = snapshot.read_bytes()?;
+
+    let actual = mem.size();
+    if actual > expected || (actual < expected && mem.grow(expected - actual) == MEMORY_GROW_FAILED)
+    {
+        return Err(SnapshotError::MemorySize { expected, actual });
+    }
+
+    if mem.copy_from_slice(0, contents).is_err() {
+        return Err(SnapshotError::Malformed);
+    }
+
+    // Clear the trailing bytes that were omitted from the snapshot.
+    const ZEROS: [u8; 4096] = [0; 4096];
+
+    let end = usize::try_from(u64::from(expected) * u64::from(PAGE_SIZE)).unwrap_or(usize::MAX);
+    let mut addr = contents.len();
+    while addr < end {
+        let len = ZEROS.len().min(end - addr);
+        let Ok(start) = u32::try_from(addr) else {
+            break;
+        };
+
+        if mem.copy_from_slice(start, &ZEROS[..len]).is_err() {
+            break;
+        }
+
+        addr += len;
+    }
+
+    Ok(())
+}
 
 struct DisplaySize(u32);
 
@@ -53,6 +53,12 @@ impl Memory {
         self.size
     }
 
+    #[cfg(feature = "std")]
+    pub(in crate::memory) fn as_slice(&self) -> &[u8] {
+        // SAFETY: `&self` ensures the underlying allocation is not mutated.
+        unsafe { NonNull::slice_from_raw_parts(self.pointer, self.len()).as_ref() }
+    }
+
     pub(in crate::memory) fn as_mut_slice(&mut self) -> &mut [u8] {
         // SAFETY: `mut` ensures exclusive access of underlying allocation.
         unsafe { NonNull::slice_from_raw_parts(self.pointer, self.len()).as_mut() }
@@ -113,6 +119,9 @@ impl Memory {
 // SAFETY: enforced by bound below.
 unsafe impl Send for Memory where [u8]: Send {}
 
+// SAFETY: the contents of the allocation can only be modified through a mutable reference.
+unsafe impl Sync for Memory where [u8]: Sync {}
+
 impl Drop for Memory {
     fn drop(&mut self) {
         let len = self.len();
@@ -25,3 +25,19 @@ impl crate::memory::Memory32 for EmptyMemory {
         Err(crate::memory::BoundsCheckError)
     }
 }
+
+impl crate::memory::Memory32Mut for EmptyMemory {
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut []
+    }
+}
+
+impl crate::memory::AllocateMemory32 for EmptyMemory {
+    fn allocate(minimum: u32, _: u32) -> Result<Self, crate::memory::AllocationError> {
+        if minimum == 0 {
+            Ok(Self)
+        } else {
+            Err(crate::memory::AllocationError::with_size(minimum))
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! Helper functions for performing memory accesses through a mutable reference to a
+//! [`Memory32Mut`].
+//!
+//! Calls to these functions are generated instead of calls to the helpers in the [`memory`] module
+//! when a module is translated with exclusive access to its instance. Since the caller has
+//! exclusive access to the memory, its contents are accessed directly as a slice.
+//!
+//! [`memory`]: crate::memory
+
+use crate::memory::{AccessError, AccessResult, BoundsCheckError, Memory32Mut, Proof};
+use crate::trap::Trap;
+
+/// Calculates the range of bytes accessed by a memory instruction, adding the static offset to
+/// the dynamic address operand.
+fn range<const OFFSET: u32, const N: usize>(
+    addr: i32,
+    error: AccessError,
+) -> AccessResult<core::ops::Range<usize>> {
+    let Some(effective) = OFFSET.checked_add(addr as u32) else {
+        return Err(AccessError::AddressOverflow { offset: OFFSET });
+    };
+
+    let start = effective as usize;
+    match start.checked_add(N) {
+        Some(end) => Ok(start..end),
+        None => Err(error),
+    }
+}
+
+/// Loads `N` bytes, checking that the access is in bounds.
+fn load<const OFFSET: u32, const N: usize, Mem>(
+    mem: &mut Mem,
+    addr: i32,
+    error: AccessError,
+) -> AccessResult<[u8; N]>
+where
+    Mem: Memory32Mut + ?Sized,
+{
+    let range = range::<OFFSET, N>(addr, error)?;
+    let mut dst = [0u8; N];
+    dst.copy_from_slice(mem.as_mut_slice().get(range).ok_or(error)?);
+    Ok(dst)
+}
+
+/// Stores `N` bytes, checking that the access is in bounds.
+fn store<const OFFSET: u32, const N: usize, Mem>(
+    mem: &mut Mem,
+    addr: i32,
+    src: [u8; N],
+    error: AccessError,
+) -> AccessResult<()>
+where
+    Mem: Memory32Mut + ?Sized,
+{
+    let range = range::<OFFSET, N>(addr, error)?;
+    mem.as_mut_slice()
+        .get_mut(range)
+        .ok_or(error)?
+        .copy_from_slice(&src);
+    Ok(())
+}
+
+/// Calculates the effective address of an access that is known to be in bounds if it is not
+/// greater than the `MAX_ADDRESS`.
+const fn effective_address<const OFFSET: u32>(addr: i32) -> AccessResult<u32> {
+    match OFFSET.checked_add(addr as u32) {
+        Some(effective) => Ok(effective),
+        None => Err(AccessError::AddressOverflow { offset: OFFSET }),
+    }
+}
+
+macro_rules! accesses {
+    {
+        $($int:ty = $error:ident : $load:ident / $store:ident <$(const $param:ident: $param_ty:ty),+>;)*
+    } => {$(
+        #[doc = concat!("Loads an `", stringify!($int), "` from a memory that the caller has exclusive access to.")]
+        ///
+        #[doc = concat!("See the documentation for [`memory::", stringify!($load), "()`] for more information.")]
+        ///
+        #[doc = concat!("[`memory::", stringify!($load), "()`]: crate::memory::", stringify!($load), "()")]
+        pub fn $load<$(const $param: $param_ty,)+ Mem, Tr>(
+            mem: &mut Mem,
+            addr: i32,
+            trap: &Tr,
+        ) -> Result<$int, Tr::Repr>
+        where
+            Mem: Memory32Mut + ?Sized,
+            Tr: Trap + ?Sized,
+        {
+            match load::<OFFSET, { core::mem::size_of::<$int>() }, Mem>(mem, addr, AccessError::$error) {
+                Ok(bytes) => Ok(<$int>::from_le_bytes(bytes)),
+                Err(err) => Err(err.trap(MEMORY, u64::from(addr as u32) + u64::from(OFFSET), trap)),
+            }
+        }
+
+        #[doc = concat!("Stores an `", stringify!($int), "` into a memory that the caller has exclusive access to.")]
+        ///
+        #[doc = concat!("See the documentation for [`memory::", stringify!($store), "()`] for more information.")]
+        ///
+        #[doc = concat!("[`memory::", stringify!($store), "()`]: crate::memory::", stringify!($store), "()")]
+        pub fn $store<$(const $param: $param_ty,)+ Mem, Tr>(
+            mem: &mut Mem,
+            addr: i32,
+            value: $int,
+            trap: &Tr,
+        ) -> Result<(), Tr::Repr>
+        where
+            Mem: Memory32Mut + ?Sized,
+            Tr: Trap + ?Sized,
+        {
+            store::<OFFSET, { core::mem::size_of::<$int>() }, Mem>(mem, addr, value.to_le_bytes(), AccessError::$error)
+                .map_err(|err| err.trap(MEMORY, u64::from(addr as u32) + u64::from(OFFSET), trap))
+        }
+    )*};
+}
+
+accesses! {
+    i8 = I8 : i8_load / i8_store <const OFFSET: u32, const MEMORY: u32>;
+    i16 = I16 : i16_load / i16_store <const OFFSET: u32, const ALIGN: u8, const MEMORY: u32>;
+    i32 = I32 : i32_load / i32_store <const OFFSET: u32, const ALIGN: u8, const MEMORY: u32>;
+    i64 = I64 : i64_load / i64_store <const OFFSET: u32, const ALIGN: u8, const MEMORY: u32>;
+}
+
+macro_rules! in_bounds_accesses {
+    {
+        $($int:ty = $error:ident : $load:ident / $store:ident;)*
+    } => {$(
+        #[doc = concat!("Loads an `", stringify!($int), "` from an address that is known to be in bounds, in a memory that the caller has exclusive access to.")]
+        ///
+        #[doc = concat!("See the documentation for [`memory::", stringify!($load), "()`] for more information.")]
+        ///
+        #[doc = concat!("[`memory::", stringify!($load), "()`]: crate::memory::", stringify!($load), "()")]
+        pub fn $load<const OFFSET: u32, const MAX_ADDRESS: u32, const MINIMUM: u32, const MEMORY: u32, Mem, Tr>(
+            mem: &mut Mem,
+            addr: i32,
+            trap: &Tr,
+        ) -> Result<$int, Tr::Repr>
+        where
+            Mem: Memory32Mut,
+            Tr: Trap + ?Sized,
+        {
+            const N: usize = core::mem::size_of::<$int>();
+
+            let loaded = effective_address::<OFFSET>(addr).and_then(|effective| {
+                match Proof::<MAX_ADDRESS, N, MINIMUM>::check(effective) {
+                    Some(proof) => mem
+                        .load_in_bounds_mut(proof)
+                        .map_err(|BoundsCheckError| AccessError::$error),
+                    None => load::<OFFSET, N, Mem>(mem, addr, AccessError::$error),
+                }
+            });
+
+            match loaded {
+                Ok(bytes) => Ok(<$int>::from_le_bytes(bytes)),
+                Err(err) => Err(err.trap(MEMORY, u64::from(addr as u32) + u64::from(OFFSET), trap)),
+            }
+        }
+
+        #[doc = concat!("Stores an `", stringify!($int), "` into an address that is known to be in bounds, in a memory that the caller has exclusive access to.")]
+        ///
+        #[doc = concat!("See the documentation for [`memory::", stringify!($load), "()`] for more information.")]
+        ///
+        #[doc = concat!("[`memory::", stringify!($load), "()`]: crate::memory::", stringify!($load), "()")]
+        pub fn $store<const OFFSET: u32, const MAX_ADDRESS: u32, const MINIMUM: u32, const MEMORY: u32, Mem, Tr>(
+            mem: &mut Mem,
+            addr: i32,
+            value: $int,
+            trap: &Tr,
+        ) -> Result<(), Tr::Repr>
+        where
+            Mem: Memory32Mut,
+            Tr: Trap + ?Sized,
+        {
+            const N: usize = core::mem::size_of::<$int>();
+
+            let stored = effective_address::<OFFSET>(addr).and_then(|effective| {
+                match Proof::<MAX_ADDRESS, N, MINIMUM>::check(effective) {
+                    Some(proof) => mem
+                        .store_in_bounds_mut(proof, value.to_le_bytes())
+                        .map_err(|BoundsCheckError| AccessError::$error),
+                    None => store::<OFFSET, N, Mem>(mem, addr, value.to_le_bytes(), AccessError::$error),
+                }
+            });
+
+            stored.map_err(|err| err.trap(MEMORY, u64::from(addr as u32) + u64::from(OFFSET), trap))
+        }
+    )*};
+}
+
+in_bounds_accesses! {
+    i8 = I8 : i8_load_in_bounds / i8_store_in_bounds;
+    i16 = I16 : i16_load_in_bounds / i16_store_in_bounds;
+    i32 = I32 : i32_load_in_bounds / i32_store_in_bounds;
+    i64 = I64 : i64_load_in_bounds / i64_store_in_bounds;
+}
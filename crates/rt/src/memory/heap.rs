@@ -1,8 +1,17 @@
 /// A [`Memory32`] implementation backed by a heap allcation.
 ///
+/// The contents of the memory can be borrowed without copying them by using
+/// [`HeapMemory32::with_slice()`] or [`HeapMemory32::with_mut_slice()`].
+///
 /// [`Memory32`]: crate::memory::Memory32
 pub struct HeapMemory32 {
     allocation: core::cell::Cell<crate::memory::allocation::Memory>,
+    /// The number of pages that the memory was created with. Since memories never shrink, this is
+    /// a lower bound for the size of the memory.
+    minimum: u32,
+    /// Set while the contents of the memory are borrowed by [`HeapMemory32::with_slice()`] or
+    /// [`HeapMemory32::with_mut_slice()`].
+    viewed: core::cell::Cell<bool>,
     /// Maximum number of allowed pages.
     limit: u32,
 }
@@ -18,6 +27,8 @@ impl HeapMemory32 {
     pub const fn with_maximum(maximum: u32) -> Self {
         Self {
             allocation: core::cell::Cell::new(crate::memory::allocation::Memory::EMPTY),
+            minimum: 0,
+            viewed: core::cell::Cell::new(false),
             limit: maximum,
         }
     }
@@ -28,6 +39,10 @@ impl HeapMemory32 {
     }
 
     fn modify<R>(&self, f: impl FnOnce(&mut crate::memory::allocation::Memory) -> R) -> R {
+        if self.viewed.get() {
+            already_viewed();
+        }
+
         crate::memory::allocation::Memory::modify(&self.allocation, f)
     }
 
@@ -47,7 +62,10 @@ impl HeapMemory32 {
     pub fn with_limits(minimum: u32, maximum: u32) -> Result<Self, crate::memory::AllocationError> {
         let mut mem = Self::with_maximum(maximum);
         match mem.allocation.get_mut().grow(minimum) {
-            Some(_) => Ok(mem),
+            Some(_) => {
+                mem.minimum = minimum;
+                Ok(mem)
+            }
             None => Err(crate::memory::AllocationError::with_size(minimum)),
         }
     }
@@ -57,6 +75,86 @@ impl HeapMemory32 {
         self.allocation.get_mut().as_mut_slice()
     }
 
+    /// Calls `f` with a shared view of `len` bytes of the linear memory starting at `addr`,
+    /// without copying them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the range of bytes is out of bounds.
+    ///
+    /// # Panics
+    ///
+    /// Like a [`RefCell`], the memory stays borrowed while `f` runs. Any other access to the
+    /// memory made during that time, including attempts to [`grow()`] it or to borrow it again,
+    /// panics.
+    ///
+    /// [`RefCell`]: core::cell::RefCell
+    /// [`grow()`]: crate::memory::Memory32::grow()
+    pub fn with_slice<R>(
+        &self,
+        addr: u32,
+        len: u32,
+        f: impl FnOnce(&[u8]) -> R,
+    ) -> crate::memory::BoundsCheck<R> {
+        self.with_mut_slice(addr, len, |slice| f(slice))
+    }
+
+    /// Calls `f` with an exclusive view of `len` bytes of the linear memory starting at `addr`,
+    /// without copying them.
+    ///
+    /// See the documentation for [`HeapMemory32::with_slice()`] for more information.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the range of bytes is out of bounds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the memory is accessed while `f` runs.
+    pub fn with_mut_slice<R>(
+        &self,
+        addr: u32,
+        len: u32,
+        f: impl FnOnce(&mut [u8]) -> R,
+    ) -> crate::memory::BoundsCheck<R> {
+        /// Returns the allocation to the memory, even if `f` panics.
+        struct View<'a> {
+            memory: &'a HeapMemory32,
+            allocation: crate::memory::allocation::Memory,
+        }
+
+        impl Drop for View<'_> {
+            fn drop(&mut self) {
+                self.memory.allocation.set(core::mem::replace(
+                    &mut self.allocation,
+                    crate::memory::allocation::Memory::EMPTY,
+                ));
+                self.memory.viewed.set(false);
+            }
+        }
+
+        if self.viewed.replace(true) {
+            already_viewed();
+        }
+
+        let mut view = View {
+            memory: self,
+            allocation: self
+                .allocation
+                .replace(crate::memory::allocation::Memory::EMPTY),
+        };
+
+        let start_addr = addr as usize;
+        match view
+            .allocation
+            .as_mut_slice()
+            .get_mut(start_addr..start_addr.wrapping_add(len as usize))
+        {
+            Some(slice) => Ok(f(slice)),
+            None => Err(crate::memory::BoundsCheckError),
+        }
+    }
+
     fn modify_addresses<T, E>(
         &self,
         addr: u32,
@@ -121,6 +219,53 @@ impl crate::memory::Memory32 for HeapMemory32 {
         )
     }
 
+    fn load_in_bounds<const N: usize>(
+        &self,
+        proof: crate::memory::InBounds<N>,
+    ) -> crate::memory::BoundsCheck<[u8; N]> {
+        if proof.minimum() > self.minimum {
+            let mut dst = [0u8; N];
+            self.copy_to_slice(proof.address(), &mut dst)?;
+            return Ok(dst);
+        }
+
+        self.modify(|a| {
+            // SAFETY: the access is within `proof.minimum()` pages, and the memory never has fewer
+            // than `self.minimum` pages.
+            Ok(unsafe {
+                a.as_mut_slice()
+                    .as_mut_ptr()
+                    .add(proof.address() as usize)
+                    .cast::<[u8; N]>()
+                    .read()
+            })
+        })
+    }
+
+    fn store_in_bounds<const N: usize>(
+        &self,
+        proof: crate::memory::InBounds<N>,
+        src: [u8; N],
+    ) -> crate::memory::BoundsCheck<()> {
+        if proof.minimum() > self.minimum {
+            return self.copy_from_slice(proof.address(), &src);
+        }
+
+        self.modify(|a| {
+            // SAFETY: the access is within `proof.minimum()` pages, and the memory never has fewer
+            // than `self.minimum` pages.
+            unsafe {
+                a.as_mut_slice()
+                    .as_mut_ptr()
+                    .add(proof.address() as usize)
+                    .cast::<[u8; N]>()
+                    .write(src)
+            }
+        });
+
+        Ok(())
+    }
+
     fn copy_within(
         &self,
         dst_addr: u32,
@@ -187,8 +332,75 @@ impl crate::memory::Memory32 for HeapMemory32 {
     }
 }
 
+#[cold]
+#[inline(never)]
+fn already_viewed() -> ! {
+    panic!("HeapMemory32 was accessed while its contents were borrowed")
+}
+
+impl crate::memory::Memory32Mut for HeapMemory32 {
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        HeapMemory32::as_mut_slice(self)
+    }
+
+    fn load_in_bounds_mut<const N: usize>(
+        &mut self,
+        proof: crate::memory::InBounds<N>,
+    ) -> crate::memory::BoundsCheck<[u8; N]> {
+        if proof.minimum() > self.minimum {
+            return crate::memory::Memory32::load_in_bounds(self, proof);
+        }
+
+        // SAFETY: the access is within `proof.minimum()` pages, and the memory never has fewer than
+        // `self.minimum` pages.
+        Ok(unsafe {
+            self.as_mut_slice()
+                .as_mut_ptr()
+                .add(proof.address() as usize)
+                .cast::<[u8; N]>()
+                .read()
+        })
+    }
+
+    fn store_in_bounds_mut<const N: usize>(
+        &mut self,
+        proof: crate::memory::InBounds<N>,
+        src: [u8; N],
+    ) -> crate::memory::BoundsCheck<()> {
+        if proof.minimum() > self.minimum {
+            return crate::memory::Memory32::store_in_bounds(self, proof, src);
+        }
+
+        // SAFETY: the access is within `proof.minimum()` pages, and the memory never has fewer than
+        // `self.minimum` pages.
+        unsafe {
+            self.as_mut_slice()
+                .as_mut_ptr()
+                .add(proof.address() as usize)
+                .cast::<[u8; N]>()
+                .write(src)
+        }
+
+        Ok(())
+    }
+}
+
+impl crate::memory::AllocateMemory32 for HeapMemory32 {
+    fn allocate(minimum: u32, maximum: u32) -> Result<Self, crate::memory::AllocationError> {
+        Self::with_limits(minimum, maximum)
+    }
+}
+
 impl core::fmt::Debug for HeapMemory32 {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.viewed.get() {
+            return f
+                .debug_struct("HeapMemory32")
+                .field("allocation", &format_args!("<borrowed>"))
+                .field("limit", &crate::memory::DisplaySize(self.limit))
+                .finish_non_exhaustive();
+        }
+
         self.modify(move |a| {
             f.debug_struct("HeapMemory32")
                 .field("allocation", a)
@@ -2,7 +2,7 @@
 //!
 //! Calls to these functions are generated as part of the `wasm2rs` translation process.
 
-use crate::memory::{AccessError, AccessResult, BoundsCheckError, Memory32};
+use crate::memory::{AccessError, AccessResult, BoundsCheckError, Memory32, Proof};
 use crate::trap::Trap;
 
 /// This implements the [`memory.size`] instruction.
@@ -368,3 +368,79 @@ where
     store::<OFFSET, ALIGN>(mem, addr, value)
         .map_err(|err| err.trap(MEMORY, u64::from(addr as u32) + u64::from(OFFSET), trap))
 }
+
+macro_rules! in_bounds_accesses {
+    {
+        $($int:ty = $error:ident : $load:ident / $store:ident;)*
+    } => {$(
+        #[doc = concat!("Loads an `", stringify!($int), "` from an address that is known to be in bounds.")]
+        ///
+        /// The `MAX_ADDRESS` is an upper bound for the effective address, which includes the
+        /// static `OFFSET`, and the `MINIMUM` is the minimum number of pages of the memory. It is a
+        /// compile-time error to call this function with a `MAX_ADDRESS` that is not within the
+        /// `MINIMUM` size. If the effective address is greater than the `MAX_ADDRESS`, the access is
+        /// bounds checked as usual.
+        ///
+        /// For more information, see the documentation for the [`Memory32::load_in_bounds()`] method.
+        pub fn $load<const OFFSET: u32, const MAX_ADDRESS: u32, const MINIMUM: u32, const MEMORY: u32, Mem, Tr>(
+            mem: &Mem,
+            addr: i32,
+            trap: &Tr,
+        ) -> Result<$int, Tr::Repr>
+        where
+            Mem: Memory32,
+            Tr: Trap + ?Sized,
+        {
+            const N: usize = core::mem::size_of::<$int>();
+
+            let loaded = address::<OFFSET>(addr).and_then(|effective| {
+                match Proof::<MAX_ADDRESS, N, MINIMUM>::check(effective) {
+                    Some(proof) => mem.load_in_bounds(proof),
+                    None => {
+                        let mut dst = [0u8; N];
+                        mem.copy_to_slice(effective, &mut dst).map(|()| dst)
+                    }
+                }
+                .map_err(|BoundsCheckError| AccessError::$error)
+            });
+
+            match loaded {
+                Ok(bytes) => Ok(<$int>::from_le_bytes(bytes)),
+                Err(err) => Err(err.trap(MEMORY, u64::from(addr as u32) + u64::from(OFFSET), trap)),
+            }
+        }
+
+        #[doc = concat!("Stores an `", stringify!($int), "` into an address that is known to be in bounds.")]
+        ///
+        #[doc = concat!("See the documentation for [`", stringify!($load), "()`] for more information.")]
+        pub fn $store<const OFFSET: u32, const MAX_ADDRESS: u32, const MINIMUM: u32, const MEMORY: u32, Mem, Tr>(
+            mem: &Mem,
+            addr: i32,
+            value: $int,
+            trap: &Tr,
+        ) -> Result<(), Tr::Repr>
+        where
+            Mem: Memory32,
+            Tr: Trap + ?Sized,
+        {
+            const N: usize = core::mem::size_of::<$int>();
+
+            let stored = address::<OFFSET>(addr).and_then(|effective| {
+                match Proof::<MAX_ADDRESS, N, MINIMUM>::check(effective) {
+                    Some(proof) => mem.store_in_bounds(proof, value.to_le_bytes()),
+                    None => mem.copy_from_slice(effective, &value.to_le_bytes()),
+                }
+                .map_err(|BoundsCheckError| AccessError::$error)
+            });
+
+            stored.map_err(|err| err.trap(MEMORY, u64::from(addr as u32) + u64::from(OFFSET), trap))
+        }
+    )*};
+}
+
+in_bounds_accesses! {
+    i8 = I8 : i8_load_in_bounds / i8_store_in_bounds;
+    i16 = I16 : i16_load_in_bounds / i16_store_in_bounds;
+    i32 = I32 : i32_load_in_bounds / i32_store_in_bounds;
+    i64 = I64 : i64_load_in_bounds / i64_store_in_bounds;
+}
@@ -0,0 +1,291 @@
+use core::ptr::NonNull;
+
+/// The size, in bytes, of the address space reserved for every [`MmapMemory32`].
+///
+/// This covers the entire 32-bit address space, in addition to the maximum static offset that
+/// can be added to an address by a WebAssembly memory instruction.
+const RESERVATION_SIZE: usize = (1usize << 32) + (u32::MAX as usize);
+
+/// A [`Memory32`] implementation that reserves its entire address space up front.
+///
+/// Creating an [`MmapMemory32`] reserves 8 GiB of virtual address space, which is enough to
+/// contain every 32-bit address plus the largest static offset. Pages of the reservation are only
+/// made accessible as the memory [grows], so accesses past the end of the memory are caught by
+/// the bounds check, and would otherwise fault on the inaccessible guard region.
+///
+/// Unlike the [`HeapMemory32`], the contents of the memory never move, so the pointer returned by
+/// [`MmapMemory32::as_ptr()`] remains valid for as long as the memory exists.
+///
+/// This type is only available with the opt-in `mmap` feature, on 64-bit Unix targets.
+///
+/// [`Memory32`]: crate::memory::Memory32
+/// [grows]: crate::memory::Memory32::grow()
+/// [`HeapMemory32`]: crate::memory::HeapMemory32
+pub struct MmapMemory32 {
+    base: NonNull<u8>,
+    /// The current number of pages that are accessible.
+    size: core::cell::Cell<u32>,
+    /// The number of pages that the memory was created with. Since memories never shrink, this is
+    /// a lower bound for the size of the memory.
+    minimum: u32,
+    /// Maximum number of allowed pages.
+    limit: u32,
+}
+
+impl MmapMemory32 {
+    /// Reserves the address space for an empty linear memory with a maximum number of allowed
+    /// pages.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address space could not be reserved.
+    pub fn with_maximum(maximum: u32) -> Result<Self, crate::memory::AllocationError> {
+        // SAFETY: a new mapping is requested, so no existing memory is affected.
+        let base = unsafe {
+            libc::mmap(
+                core::ptr::null_mut(),
+                RESERVATION_SIZE,
+                libc::PROT_NONE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_NORESERVE,
+                -1,
+                0,
+            )
+        };
+
+        if base == libc::MAP_FAILED {
+            return Err(crate::memory::AllocationError::with_size(0));
+        }
+
+        Ok(Self {
+            base: NonNull::new(base.cast::<u8>())
+                .ok_or(crate::memory::AllocationError::with_size(0))?,
+            size: core::cell::Cell::new(0),
+            minimum: 0,
+            limit: maximum.min(crate::memory::MAX_PAGES),
+        })
+    }
+
+    /// Reserves the address space for a linear memory, with a minimum and maximum number of
+    /// pages.
+    ///
+    /// If the `minimum` is greater than `0`, then the first `minimum` pages are made accessible.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address space could not be reserved, or if the `minimum` number of
+    /// pages could not be made accessible.
+    pub fn with_limits(minimum: u32, maximum: u32) -> Result<Self, crate::memory::AllocationError> {
+        let mut mem = Self::with_maximum(maximum)?;
+        if mem.commit(minimum).is_some() {
+            mem.minimum = minimum;
+            Ok(mem)
+        } else {
+            Err(crate::memory::AllocationError::with_size(minimum))
+        }
+    }
+
+    /// Makes `delta` more pages accessible, returning the old number of pages.
+    fn commit(&self, delta: u32) -> Option<u32> {
+        let old_size = self.size.get();
+        if delta == 0 {
+            return Some(old_size);
+        }
+
+        let new_size = old_size
+            .checked_add(delta)
+            .filter(|size| *size <= self.limit)?;
+        let start = old_size as usize * crate::memory::PAGE_SIZE as usize;
+        let len = delta as usize * crate::memory::PAGE_SIZE as usize;
+
+        // SAFETY: `start + len <= RESERVATION_SIZE`, since `new_size <= MAX_PAGES`.
+        // SAFETY: the pages are part of the reservation, and are not yet accessible.
+        let result = unsafe {
+            libc::mprotect(
+                self.base.as_ptr().add(start).cast::<libc::c_void>(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+            )
+        };
+
+        if result == 0 {
+            self.size.set(new_size);
+            Some(old_size)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the size of the linear memory, in bytes.
+    pub fn len(&self) -> usize {
+        self.size.get() as usize * crate::memory::PAGE_SIZE as usize
+    }
+
+    /// Returns `true` if the memory has a size of `0`.
+    pub fn is_empty(&self) -> bool {
+        self.size.get() == 0
+    }
+
+    /// Returns a pointer to the start of the linear memory.
+    ///
+    /// The pointer is valid for reads and writes of [`MmapMemory32::len()`] bytes, and does not
+    /// change when the memory grows.
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.base.as_ptr()
+    }
+
+    /// Returns a mutable slice to the linear memory contents.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        // SAFETY: the first `len` bytes of the reservation are accessible.
+        // SAFETY: `mut` ensures exclusive access of underlying memory.
+        unsafe { core::slice::from_raw_parts_mut(self.base.as_ptr(), self.len()) }
+    }
+
+    /// Gets a pointer to the bytes in the range of addresses `addr..(addr + len)`, or `None` if
+    /// the range is out of bounds.
+    fn get(&self, addr: u32, len: usize) -> Option<*mut u8> {
+        let start = addr as usize;
+        if start.checked_add(len)? <= self.len() {
+            // SAFETY: check above ensures `start` is within the accessible pages.
+            Some(unsafe { self.base.as_ptr().add(start) })
+        } else {
+            None
+        }
+    }
+}
+
+impl crate::memory::Memory32 for MmapMemory32 {
+    fn try_as_any(&self, _: crate::memory::private::Hidden) -> Option<&dyn core::any::Any> {
+        Some(self)
+    }
+
+    fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    fn size(&self) -> u32 {
+        self.size.get()
+    }
+
+    fn grow(&self, delta: u32) -> u32 {
+        self.commit(delta)
+            .unwrap_or(crate::memory::MEMORY_GROW_FAILED)
+    }
+
+    fn copy_to_slice(&self, addr: u32, dst: &mut [u8]) -> crate::memory::BoundsCheck<()> {
+        let src = self
+            .get(addr, dst.len())
+            .ok_or(crate::memory::BoundsCheckError)?;
+
+        // SAFETY: `get()` ensures the range is in bounds.
+        // SAFETY: `dst` is a unique reference, so it can't overlap with the linear memory.
+        unsafe { core::ptr::copy_nonoverlapping(src, dst.as_mut_ptr(), dst.len()) };
+        Ok(())
+    }
+
+    fn copy_from_slice(&self, addr: u32, src: &[u8]) -> crate::memory::BoundsCheck<()> {
+        let dst = self
+            .get(addr, src.len())
+            .ok_or(crate::memory::BoundsCheckError)?;
+
+        // SAFETY: `get()` ensures the range is in bounds.
+        // SAFETY: `MmapMemory32` is not `Sync`, and never hands out shared references to its
+        // contents, so `src` can't overlap with the linear memory.
+        unsafe { core::ptr::copy_nonoverlapping(src.as_ptr(), dst, src.len()) };
+        Ok(())
+    }
+
+    fn load_in_bounds<const N: usize>(
+        &self,
+        proof: crate::memory::InBounds<N>,
+    ) -> crate::memory::BoundsCheck<[u8; N]> {
+        if proof.minimum() > self.minimum {
+            let mut dst = [0u8; N];
+            self.copy_to_slice(proof.address(), &mut dst)?;
+            return Ok(dst);
+        }
+
+        // SAFETY: the access is within `proof.minimum()` pages, and the memory never has fewer than
+        // `self.minimum` pages.
+        Ok(unsafe {
+            self.base
+                .as_ptr()
+                .add(proof.address() as usize)
+                .cast::<[u8; N]>()
+                .read()
+        })
+    }
+
+    fn store_in_bounds<const N: usize>(
+        &self,
+        proof: crate::memory::InBounds<N>,
+        src: [u8; N],
+    ) -> crate::memory::BoundsCheck<()> {
+        if proof.minimum() > self.minimum {
+            return self.copy_from_slice(proof.address(), &src);
+        }
+
+        // SAFETY: the access is within `proof.minimum()` pages, and the memory never has fewer than
+        // `self.minimum` pages.
+        unsafe {
+            self.base
+                .as_ptr()
+                .add(proof.address() as usize)
+                .cast::<[u8; N]>()
+                .write(src)
+        };
+
+        Ok(())
+    }
+
+    fn copy_within(
+        &self,
+        dst_addr: u32,
+        src_addr: u32,
+        len: u32,
+    ) -> crate::memory::BoundsCheck<()> {
+        let size = len as usize;
+        let src = self
+            .get(src_addr, size)
+            .ok_or(crate::memory::BoundsCheckError)?;
+        let dst = self
+            .get(dst_addr, size)
+            .ok_or(crate::memory::BoundsCheckError)?;
+
+        // SAFETY: `get()` ensures both ranges are in bounds, `copy` allows overlapping ranges.
+        unsafe { core::ptr::copy(src, dst, size) };
+        Ok(())
+    }
+}
+
+impl crate::memory::Memory32Mut for MmapMemory32 {
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        MmapMemory32::as_mut_slice(self)
+    }
+}
+
+impl crate::memory::AllocateMemory32 for MmapMemory32 {
+    fn allocate(minimum: u32, maximum: u32) -> Result<Self, crate::memory::AllocationError> {
+        Self::with_limits(minimum, maximum)
+    }
+}
+
+impl Drop for MmapMemory32 {
+    fn drop(&mut self) {
+        // SAFETY: `base` refers to a mapping of `RESERVATION_SIZE` bytes created in `with_maximum`.
+        let _ =
+            unsafe { libc::munmap(self.base.as_ptr().cast::<libc::c_void>(), RESERVATION_SIZE) };
+    }
+}
+
+// SAFETY: the mapping is exclusively owned by the `MmapMemory32`.
+unsafe impl Send for MmapMemory32 {}
+
+impl core::fmt::Debug for MmapMemory32 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("MmapMemory32")
+            .field("base", &self.base)
+            .field("size", &crate::memory::DisplaySize(self.size.get()))
+            .field("limit", &crate::memory::DisplaySize(self.limit))
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,553 @@
+//! Typed pointers into linear memory, used by host functions to access values passed to them by
+//! WebAssembly code.
+//!
+//! WebAssembly functions usually pass values that do not fit in a single parameter as an address
+//! into linear memory, or as a pair of an address and a length. Rather than decoding these with
+//! [`Memory32::copy_to_slice()`], host functions can wrap them in a [`WasmPtr`], [`WasmSlice`], or
+//! [`WasmStr`]:
+//!
+//! ```
+//! use wasm2rs_rt::embedder::State;
+//! use wasm2rs_rt::memory::{HeapMemory32, Memory32, WasmPtr, WasmStr};
+//!
+//! let memory = HeapMemory32::with_limits(1, 1).unwrap();
+//! let trap = State::<()>::default();
+//! memory.copy_from_slice(16, b"hello").unwrap();
+//!
+//! let greeting: WasmStr = WasmStr::new(16, 5);
+//! assert_eq!(greeting.to_string(&memory, &trap).unwrap().as_deref(), Ok("hello"));
+//!
+//! let counter = WasmPtr::<u32>::new(32);
+//! counter.write(&memory, 41, &trap).unwrap();
+//! assert_eq!(counter.read(&memory, &trap), Ok(41));
+//! ```
+//!
+//! Accesses that are out of bounds produce a [`TrapCode::MemoryBoundsCheck`] containing the address
+//! of the access. The `MEMORY` parameter of each type is the index of the memory reported in the
+//! trap, which defaults to the main memory.
+//!
+//! [`TrapCode::MemoryBoundsCheck`]: crate::trap::TrapCode::MemoryBoundsCheck
+
+use crate::memory::{AccessError, Memory32, PAGE_SIZE};
+use crate::trap::Trap;
+
+/// Trait for plain-old-data types that can be read from and written to linear memory.
+///
+/// Values are stored in little-endian byte order, matching WebAssembly's own loads and stores.
+pub trait Pod: Copy {
+    /// An array of bytes used to store the value, usually `[u8; core::mem::size_of::<Self>()]`.
+    type Bytes: AsRef<[u8]> + AsMut<[u8]> + Default;
+
+    /// Decodes a value from its little-endian representation.
+    fn from_le_bytes(bytes: Self::Bytes) -> Self;
+
+    /// Encodes the value into its little-endian representation.
+    fn to_le_bytes(self) -> Self::Bytes;
+}
+
+macro_rules! pod_numbers {
+    ($($ty:ty,)*) => {$(
+        impl Pod for $ty {
+            type Bytes = [u8; core::mem::size_of::<$ty>()];
+
+            fn from_le_bytes(bytes: Self::Bytes) -> Self {
+                <$ty>::from_le_bytes(bytes)
+            }
+
+            fn to_le_bytes(self) -> Self::Bytes {
+                <$ty>::to_le_bytes(self)
+            }
+        }
+    )*};
+}
+
+pod_numbers! {
+    u8,
+    i8,
+    u16,
+    i16,
+    u32,
+    i32,
+    u64,
+    i64,
+    f32,
+    f64,
+}
+
+/// The size, in bytes, of a [`Pod`] value in linear memory.
+const fn size_of<T: Pod>() -> u32 {
+    // Values are never larger than linear memory.
+    #[allow(clippy::cast_possible_truncation)]
+    {
+        core::mem::size_of::<T::Bytes>() as u32
+    }
+}
+
+/// Checks that `len` bytes starting at `address` are in bounds.
+fn check_bounds<M: Memory32 + ?Sized>(memory: &M, address: u32, len: u64) -> bool {
+    u64::from(address) + len <= u64::from(memory.size()) * u64::from(PAGE_SIZE)
+}
+
+fn bounds_trap<const MEMORY: u32, E: Trap + ?Sized>(
+    error: AccessError,
+    address: u32,
+    trap: &E,
+) -> E::Repr {
+    error.trap(MEMORY, address.into(), trap)
+}
+
+/// A typed address of a `T` in the linear memory with index `MEMORY`.
+#[repr(transparent)]
+pub struct WasmPtr<T, const MEMORY: u32 = 0> {
+    address: u32,
+    _marker: core::marker::PhantomData<fn() -> T>,
+}
+
+impl<T, const MEMORY: u32> WasmPtr<T, MEMORY> {
+    /// Creates a pointer to the given `address`.
+    pub const fn new(address: u32) -> Self {
+        Self {
+            address,
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    /// Gets the address that this pointer points to.
+    pub const fn address(self) -> u32 {
+        self.address
+    }
+
+    /// Casts this pointer to point to a value of another type.
+    pub const fn cast<U>(self) -> WasmPtr<U, MEMORY> {
+        WasmPtr::new(self.address)
+    }
+}
+
+impl<T: Pod, const MEMORY: u32> WasmPtr<T, MEMORY> {
+    /// Gets a pointer to the `count`th `T` after this one, or `None` if the address overflows.
+    pub fn checked_add(self, count: u32) -> Option<Self> {
+        let offset = count.checked_mul(size_of::<T>())?;
+        Some(Self::new(self.address.checked_add(offset)?))
+    }
+
+    /// Reads the value that this pointer points to.
+    ///
+    /// # Errors
+    ///
+    /// A [`Trap`] occurs if the value is not in bounds.
+    pub fn read<M, E>(self, memory: &M, trap: &E) -> Result<T, E::Repr>
+    where
+        M: Memory32 + ?Sized,
+        E: Trap + ?Sized,
+    {
+        let mut bytes = T::Bytes::default();
+        match memory.copy_to_slice(self.address, bytes.as_mut()) {
+            Ok(()) => Ok(T::from_le_bytes(bytes)),
+            Err(_) => Err(bounds_trap::<MEMORY, E>(
+                AccessError::with_size(bytes.as_ref().len()),
+                self.address,
+                trap,
+            )),
+        }
+    }
+
+    /// Writes a value to the location that this pointer points to.
+    ///
+    /// # Errors
+    ///
+    /// A [`Trap`] occurs if the value is not in bounds.
+    pub fn write<M, E>(self, memory: &M, value: T, trap: &E) -> Result<(), E::Repr>
+    where
+        M: Memory32 + ?Sized,
+        E: Trap + ?Sized,
+    {
+        let bytes = value.to_le_bytes();
+        memory
+            .copy_from_slice(self.address, bytes.as_ref())
+            .map_err(|_| {
+                bounds_trap::<MEMORY, E>(
+                    AccessError::with_size(bytes.as_ref().len()),
+                    self.address,
+                    trap,
+                )
+            })
+    }
+}
+
+impl<T, const MEMORY: u32> From<i32> for WasmPtr<T, MEMORY> {
+    fn from(address: i32) -> Self {
+        Self::new(address as u32)
+    }
+}
+
+impl<T, const MEMORY: u32> Clone for WasmPtr<T, MEMORY> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, const MEMORY: u32> Copy for WasmPtr<T, MEMORY> {}
+
+impl<T, const MEMORY: u32> PartialEq for WasmPtr<T, MEMORY> {
+    fn eq(&self, other: &Self) -> bool {
+        self.address == other.address
+    }
+}
+
+impl<T, const MEMORY: u32> Eq for WasmPtr<T, MEMORY> {}
+
+impl<T, const MEMORY: u32> core::hash::Hash for WasmPtr<T, MEMORY> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.address.hash(state)
+    }
+}
+
+impl<T, const MEMORY: u32> core::fmt::Debug for WasmPtr<T, MEMORY> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "WasmPtr({:#X})", self.address)
+    }
+}
+
+/// Pointers are stored as 32-bit addresses, allowing arrays of pointers to be read.
+impl<T, const MEMORY: u32> Pod for WasmPtr<T, MEMORY> {
+    type Bytes = [u8; 4];
+
+    fn from_le_bytes(bytes: Self::Bytes) -> Self {
+        Self::new(u32::from_le_bytes(bytes))
+    }
+
+    fn to_le_bytes(self) -> Self::Bytes {
+        self.address.to_le_bytes()
+    }
+}
+
+/// A typed slice of `len` values of type `T` in the linear memory with index `MEMORY`.
+pub struct WasmSlice<T, const MEMORY: u32 = 0> {
+    ptr: WasmPtr<T, MEMORY>,
+    len: u32,
+}
+
+impl<T, const MEMORY: u32> WasmSlice<T, MEMORY> {
+    /// Creates a slice of `len` elements starting at the given address.
+    pub const fn new(address: u32, len: u32) -> Self {
+        Self {
+            ptr: WasmPtr::new(address),
+            len,
+        }
+    }
+
+    /// Creates a slice from an address and length passed as parameters of a WebAssembly function.
+    pub const fn from_raw_parts(address: i32, len: i32) -> Self {
+        Self::new(address as u32, len as u32)
+    }
+
+    /// Gets a pointer to the first element of the slice.
+    pub const fn as_ptr(&self) -> WasmPtr<T, MEMORY> {
+        self.ptr
+    }
+
+    /// Gets the number of elements in the slice.
+    pub const fn len(&self) -> u32 {
+        self.len
+    }
+
+    /// Returns `true` if the slice has a length of `0`.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<T: Pod, const MEMORY: u32> WasmSlice<T, MEMORY> {
+    /// Gets a pointer to the element at the given `index`, or `None` if it is out of bounds of
+    /// the slice.
+    pub fn get(&self, index: u32) -> Option<WasmPtr<T, MEMORY>> {
+        if index < self.len {
+            self.ptr.checked_add(index)
+        } else {
+            None
+        }
+    }
+
+    fn check_bounds<M, E>(&self, memory: &M, trap: &E) -> Result<(), E::Repr>
+    where
+        M: Memory32 + ?Sized,
+        E: Trap + ?Sized,
+    {
+        let byte_len = u64::from(self.len) * u64::from(size_of::<T>());
+        if check_bounds(memory, self.ptr.address, byte_len) {
+            Ok(())
+        } else {
+            Err(bounds_trap::<MEMORY, E>(
+                AccessError::with_size(usize::try_from(byte_len).unwrap_or(usize::MAX)),
+                self.ptr.address,
+                trap,
+            ))
+        }
+    }
+
+    /// Reads the elements of the slice into the start of `dst`, returning the number of elements
+    /// that were read.
+    ///
+    /// If `dst` is shorter than the slice, only the first `dst.len()` elements are read. If the
+    /// `alloc` feature is enabled, the elements are copied all at once.
+    ///
+    /// # Errors
+    ///
+    /// A [`Trap`] occurs if the slice is not in bounds.
+    pub fn read_into<M, E>(&self, memory: &M, dst: &mut [T], trap: &E) -> Result<usize, E::Repr>
+    where
+        M: Memory32 + ?Sized,
+        E: Trap + ?Sized,
+    {
+        self.check_bounds(memory, trap)?;
+
+        let count = dst
+            .len()
+            .min(usize::try_from(self.len).unwrap_or(usize::MAX));
+        let dst = &mut dst[..count];
+
+        #[cfg(feature = "alloc")]
+        {
+            let bytes = self.read_bytes(memory, count * core::mem::size_of::<T::Bytes>(), trap)?;
+            for (chunk, dst) in bytes
+                .chunks_exact(core::mem::size_of::<T::Bytes>())
+                .zip(dst)
+            {
+                *dst = decode(chunk);
+            }
+        }
+
+        #[cfg(not(feature = "alloc"))]
+        for (ptr, dst) in self.iter().zip(dst) {
+            *dst = ptr.read(memory, trap)?;
+        }
+
+        Ok(count)
+    }
+
+    /// Writes the elements of `src` into the start of the slice, returning the number of elements
+    /// that were written.
+    ///
+    /// If `src` is longer than the slice, only the first [`len()`] elements are written. If the
+    /// `alloc` feature is enabled, the elements are copied all at once.
+    ///
+    /// # Errors
+    ///
+    /// A [`Trap`] occurs if the slice is not in bounds.
+    ///
+    /// [`len()`]: WasmSlice::len()
+    pub fn write_from<M, E>(&self, memory: &M, src: &[T], trap: &E) -> Result<usize, E::Repr>
+    where
+        M: Memory32 + ?Sized,
+        E: Trap + ?Sized,
+    {
+        self.check_bounds(memory, trap)?;
+
+        let count = src
+            .len()
+            .min(usize::try_from(self.len).unwrap_or(usize::MAX));
+        let src = &src[..count];
+
+        #[cfg(feature = "alloc")]
+        {
+            let mut bytes =
+                alloc::vec::Vec::with_capacity(count * core::mem::size_of::<T::Bytes>());
+            for value in src {
+                bytes.extend_from_slice(value.to_le_bytes().as_ref());
+            }
+
+            memory
+                .copy_from_slice(self.ptr.address, &bytes)
+                .map_err(|_| {
+                    bounds_trap::<MEMORY, E>(
+                        AccessError::with_size(bytes.len()),
+                        self.ptr.address,
+                        trap,
+                    )
+                })?;
+        }
+
+        #[cfg(not(feature = "alloc"))]
+        for (ptr, src) in self.iter().zip(src) {
+            ptr.write(memory, *src, trap)?;
+        }
+
+        Ok(count)
+    }
+
+    /// Reads the elements of the slice into a new [`Vec`], copying them all at once.
+    ///
+    /// # Errors
+    ///
+    /// A [`Trap`] occurs if the slice is not in bounds.
+    ///
+    /// [`Vec`]: alloc::vec::Vec
+    #[cfg(feature = "alloc")]
+    pub fn to_vec<M, E>(&self, memory: &M, trap: &E) -> Result<alloc::vec::Vec<T>, E::Repr>
+    where
+        M: Memory32 + ?Sized,
+        E: Trap + ?Sized,
+    {
+        self.check_bounds(memory, trap)?;
+
+        let byte_len = u64::from(self.len) * u64::from(size_of::<T>());
+        let bytes = self.read_bytes(
+            memory,
+            usize::try_from(byte_len).unwrap_or(usize::MAX),
+            trap,
+        )?;
+
+        Ok(bytes
+            .chunks_exact(core::mem::size_of::<T::Bytes>())
+            .map(decode)
+            .collect())
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    fn iter(&self) -> impl Iterator<Item = WasmPtr<T, MEMORY>> + '_ {
+        (0..self.len).map_while(|index| self.ptr.checked_add(index))
+    }
+
+    /// Copies the first `len` bytes of the slice into a new [`Vec`].
+    ///
+    /// [`Vec`]: alloc::vec::Vec
+    #[cfg(feature = "alloc")]
+    fn read_bytes<M, E>(
+        &self,
+        memory: &M,
+        len: usize,
+        trap: &E,
+    ) -> Result<alloc::vec::Vec<u8>, E::Repr>
+    where
+        M: Memory32 + ?Sized,
+        E: Trap + ?Sized,
+    {
+        let mut bytes = alloc::vec![0u8; len];
+        memory
+            .copy_to_slice(self.ptr.address, &mut bytes)
+            .map_err(|_| {
+                bounds_trap::<MEMORY, E>(
+                    AccessError::with_size(bytes.len()),
+                    self.ptr.address,
+                    trap,
+                )
+            })?;
+
+        Ok(bytes)
+    }
+}
+
+/// Decodes a value from a chunk of exactly `size_of::<T::Bytes>()` bytes.
+#[cfg(feature = "alloc")]
+fn decode<T: Pod>(chunk: &[u8]) -> T {
+    let mut bytes = T::Bytes::default();
+    bytes.as_mut().copy_from_slice(chunk);
+    T::from_le_bytes(bytes)
+}
+
+impl<const MEMORY: u32> WasmSlice<u8, MEMORY> {
+    /// Copies the bytes of the slice into a new boxed slice.
+    ///
+    /// This is equivalent to [`WasmSlice::to_vec()`], but avoids decoding each byte.
+    ///
+    /// # Errors
+    ///
+    /// A [`Trap`] occurs if the slice is not in bounds.
+    #[cfg(feature = "alloc")]
+    pub fn to_bytes<M, E>(&self, memory: &M, trap: &E) -> Result<alloc::boxed::Box<[u8]>, E::Repr>
+    where
+        M: Memory32 + ?Sized,
+        E: Trap + ?Sized,
+    {
+        self.check_bounds(memory, trap)?;
+
+        let bytes = self.read_bytes(
+            memory,
+            usize::try_from(self.len).unwrap_or(usize::MAX),
+            trap,
+        )?;
+
+        Ok(bytes.into_boxed_slice())
+    }
+}
+
+impl<T, const MEMORY: u32> Clone for WasmSlice<T, MEMORY> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, const MEMORY: u32> Copy for WasmSlice<T, MEMORY> {}
+
+impl<T, const MEMORY: u32> PartialEq for WasmSlice<T, MEMORY> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr == other.ptr && self.len == other.len
+    }
+}
+
+impl<T, const MEMORY: u32> Eq for WasmSlice<T, MEMORY> {}
+
+impl<T, const MEMORY: u32> core::fmt::Debug for WasmSlice<T, MEMORY> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WasmSlice")
+            .field("ptr", &self.ptr)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+/// A UTF-8 string of `len` bytes in the linear memory with index `MEMORY`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WasmStr<const MEMORY: u32 = 0> {
+    bytes: WasmSlice<u8, MEMORY>,
+}
+
+impl<const MEMORY: u32> WasmStr<MEMORY> {
+    /// Creates a string of `len` bytes starting at the given address.
+    pub const fn new(address: u32, len: u32) -> Self {
+        Self {
+            bytes: WasmSlice::new(address, len),
+        }
+    }
+
+    /// Creates a string from an address and length passed as parameters of a WebAssembly
+    /// function.
+    pub const fn from_raw_parts(address: i32, len: i32) -> Self {
+        Self::new(address as u32, len as u32)
+    }
+
+    /// Gets the bytes of the string, which may not actually be valid UTF-8.
+    pub const fn as_bytes(&self) -> WasmSlice<u8, MEMORY> {
+        self.bytes
+    }
+
+    /// Gets the length of the string, in bytes.
+    pub const fn len(&self) -> u32 {
+        self.bytes.len()
+    }
+
+    /// Returns `true` if the string has a length of `0`.
+    pub const fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Copies the string out of linear memory, checking that it is valid UTF-8.
+    ///
+    /// # Errors
+    ///
+    /// A [`Trap`] occurs if the string is not in bounds. If the string is in bounds but is not
+    /// valid UTF-8, the inner result contains the error.
+    #[cfg(feature = "alloc")]
+    pub fn to_string<M, E>(
+        &self,
+        memory: &M,
+        trap: &E,
+    ) -> Result<Result<alloc::string::String, core::str::Utf8Error>, E::Repr>
+    where
+        M: Memory32 + ?Sized,
+        E: Trap + ?Sized,
+    {
+        let bytes = self.bytes.to_bytes(memory, trap)?.into_vec();
+        Ok(alloc::string::String::from_utf8(bytes).map_err(|error| error.utf8_error()))
+    }
+}
@@ -0,0 +1,527 @@
+use core::cell::{Cell, UnsafeCell};
+use core::ptr::NonNull;
+
+const PAGE_SIZE: usize = crate::memory::PAGE_SIZE as usize;
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Storage for the pages of a [`StaticMemory32`].
+///
+/// This trait is sealed, and is implemented by [`InlinePages`], [`BorrowedPages`], and
+/// [`TakenPages`].
+pub trait PageStorage: private::Sealed {
+    /// Gets a pointer to the first byte of the first page.
+    #[doc(hidden)]
+    fn as_ptr(&self) -> *mut u8;
+
+    /// Gets the number of whole pages in the storage.
+    #[doc(hidden)]
+    fn capacity(&self) -> usize;
+}
+
+/// Pages stored inline in a [`StaticMemory32`], which start out filled with zeroes.
+pub struct InlinePages<const PAGES: usize> {
+    pages: UnsafeCell<[[u8; PAGE_SIZE]; PAGES]>,
+}
+
+impl<const PAGES: usize> InlinePages<PAGES> {
+    const fn new() -> Self {
+        Self {
+            pages: UnsafeCell::new([[0u8; PAGE_SIZE]; PAGES]),
+        }
+    }
+}
+
+impl<const PAGES: usize> private::Sealed for InlinePages<PAGES> {}
+
+impl<const PAGES: usize> PageStorage for InlinePages<PAGES> {
+    fn as_ptr(&self) -> *mut u8 {
+        self.pages.get().cast::<u8>()
+    }
+
+    fn capacity(&self) -> usize {
+        PAGES
+    }
+}
+
+impl<const PAGES: usize> core::fmt::Debug for InlinePages<PAGES> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("InlinePages").finish_non_exhaustive()
+    }
+}
+
+/// Pages stored in a caller-provided `&'static mut [u8]`.
+///
+/// See the documentation for [`StaticMemory32::from_slice()`] for more information.
+pub struct BorrowedPages {
+    pointer: NonNull<u8>,
+    capacity: usize,
+    _buffer: core::marker::PhantomData<&'static mut [u8]>,
+}
+
+impl private::Sealed for BorrowedPages {}
+
+impl PageStorage for BorrowedPages {
+    fn as_ptr(&self) -> *mut u8 {
+        self.pointer.as_ptr()
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+// SAFETY: the buffer is exclusively borrowed.
+unsafe impl Send for BorrowedPages {}
+
+impl core::fmt::Debug for BorrowedPages {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("BorrowedPages")
+            .field("capacity", &self.capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reserves `PAGES` pages of memory in a `static`, which can be used by one [`StaticMemory32`] at a
+/// time.
+///
+/// The pages are [taken] when a memory is allocated, and are returned when the [`TakenPages`] are
+/// dropped along with the memory, allowing a WebAssembly module to be instantiated again after its
+/// previous instance is dropped. Since the pages are stored in the `static` itself, they are never
+/// moved.
+///
+/// [taken]: StaticPages::take()
+#[cfg(target_has_atomic = "8")]
+pub struct StaticPages<const PAGES: usize> {
+    taken: core::sync::atomic::AtomicBool,
+    pages: UnsafeCell<[[u8; PAGE_SIZE]; PAGES]>,
+}
+
+#[cfg(target_has_atomic = "8")]
+impl<const PAGES: usize> StaticPages<PAGES> {
+    // A `Default` implementation would move the pages, which should only be stored in a `static`.
+    #[allow(clippy::new_without_default)]
+    /// Reserves the pages, which start out filled with zeroes.
+    pub const fn new() -> Self {
+        Self {
+            taken: core::sync::atomic::AtomicBool::new(false),
+            pages: UnsafeCell::new([[0u8; PAGE_SIZE]; PAGES]),
+        }
+    }
+
+    /// Takes the pages, or returns `None` if they are currently in use.
+    pub fn take(&'static self) -> Option<TakenPages<PAGES>> {
+        if self.taken.swap(true, core::sync::atomic::Ordering::Acquire) {
+            None
+        } else {
+            Some(TakenPages { source: self })
+        }
+    }
+}
+
+// SAFETY: the pages are only accessed through the single `TakenPages` returned by `take()`.
+#[cfg(target_has_atomic = "8")]
+unsafe impl<const PAGES: usize> Sync for StaticPages<PAGES> {}
+
+#[cfg(target_has_atomic = "8")]
+impl<const PAGES: usize> core::fmt::Debug for StaticPages<PAGES> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("StaticPages")
+            .field("taken", &self.taken)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Exclusive access to the pages of a [`StaticPages`], which are returned when this is dropped.
+#[cfg(target_has_atomic = "8")]
+pub struct TakenPages<const PAGES: usize> {
+    source: &'static StaticPages<PAGES>,
+}
+
+#[cfg(target_has_atomic = "8")]
+impl<const PAGES: usize> private::Sealed for TakenPages<PAGES> {}
+
+#[cfg(target_has_atomic = "8")]
+impl<const PAGES: usize> PageStorage for TakenPages<PAGES> {
+    fn as_ptr(&self) -> *mut u8 {
+        self.source.pages.get().cast::<u8>()
+    }
+
+    fn capacity(&self) -> usize {
+        PAGES
+    }
+}
+
+#[cfg(target_has_atomic = "8")]
+impl<const PAGES: usize> Drop for TakenPages<PAGES> {
+    fn drop(&mut self) {
+        self.source
+            .taken
+            .store(false, core::sync::atomic::Ordering::Release);
+    }
+}
+
+#[cfg(target_has_atomic = "8")]
+impl<const PAGES: usize> core::fmt::Debug for TakenPages<PAGES> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TakenPages").finish_non_exhaustive()
+    }
+}
+
+/// Provides the [`PageStorage`] that a [`StaticMemory32`] stores its contents in when it is
+/// allocated by an embedder.
+///
+/// See the documentation for [`StaticMemory32`] for an example.
+pub trait StaticBuffer<const PAGES: usize> {
+    /// The storage for the pages.
+    type Storage: PageStorage;
+
+    /// Takes the storage, or returns `None` if it is not available, such as when it is in use by
+    /// another memory.
+    fn take() -> Option<Self::Storage>;
+}
+
+/// Stores the pages of a [`StaticMemory32`] inline, in the memory itself.
+#[derive(Clone, Copy, Debug)]
+pub struct Inline;
+
+impl<const PAGES: usize> StaticBuffer<PAGES> for Inline {
+    type Storage = InlinePages<PAGES>;
+
+    fn take() -> Option<InlinePages<PAGES>> {
+        Some(InlinePages::new())
+    }
+}
+
+/// Stores the pages of a [`StaticMemory32`] in a caller-provided buffer.
+///
+/// Memories with borrowed pages are created with [`StaticMemory32::from_slice()`], and can't be
+/// allocated by an embedder.
+#[derive(Clone, Copy, Debug)]
+pub struct Borrowed;
+
+impl<const PAGES: usize> StaticBuffer<PAGES> for Borrowed {
+    type Storage = BorrowedPages;
+
+    fn take() -> Option<BorrowedPages> {
+        None
+    }
+}
+
+/// A [`Memory32`] implementation that does not perform any heap allocations.
+///
+/// The memory can [grow] up to `PAGES` pages. Where its pages are stored is determined by the
+/// [`StaticBuffer`] `B`:
+///
+/// - By default, the pages are stored [`Inline`], in the [`StaticMemory32`] itself.
+/// - A memory created with [`StaticMemory32::from_slice()`] stores its pages in a caller-provided
+///   `&'static mut [u8]`.
+/// - Embedders can provide their own [`StaticBuffer`] implementation, such as one that takes the
+///   pages from a `static` [`StaticPages`].
+///
+/// This is intended for targets that do not have a heap, such as microcontrollers. An embedder can
+/// provide a [`StaticMemory32`] as the WebAssembly module's main memory:
+///
+/// ```
+/// mod host {
+///     use wasm2rs_rt::memory::{StaticBuffer, StaticPages, TakenPages};
+///
+///     static PAGES: StaticPages<1> = StaticPages::new();
+///
+///     pub struct MainMemory;
+///
+///     impl StaticBuffer<1> for MainMemory {
+///         type Storage = TakenPages<1>;
+///
+///         fn take() -> Option<TakenPages<1>> {
+///             PAGES.take()
+///         }
+///     }
+///
+///     #[derive(Debug, Default)]
+///     pub struct MyImports;
+///
+///     wasm2rs_rt::embedder_with_import! {
+///         pub mod my_embedder(MyImports) memory(
+///             wasm2rs_rt::memory::StaticMemory32<1, super::MainMemory>
+///         )
+///     }
+/// }
+///
+/// use wasm2rs_rt::memory::{AllocateMemory32, Memory32};
+///
+/// let mem = host::my_embedder::Memory0::allocate(1, 2).unwrap();
+/// assert_eq!(mem.size(), 1);
+///
+/// // The pages are in use by another memory.
+/// assert!(host::my_embedder::Memory0::allocate(1, 2).is_err());
+///
+/// // Dropping the memory returns the pages.
+/// drop(mem);
+/// assert!(host::my_embedder::Memory0::allocate(1, 2).is_ok());
+/// ```
+///
+/// [`Memory32`]: crate::memory::Memory32
+/// [grow]: crate::memory::Memory32::grow()
+pub struct StaticMemory32<const PAGES: usize, B: StaticBuffer<PAGES> = Inline> {
+    storage: B::Storage,
+    /// The number of whole pages in the storage.
+    capacity: u32,
+    /// The current number of pages.
+    size: Cell<u32>,
+    /// The number of pages that the memory was created with. Since memories never shrink, this is
+    /// a lower bound for the size of the memory.
+    minimum: u32,
+    /// Maximum number of allowed pages.
+    limit: u32,
+}
+
+impl<const PAGES: usize> StaticMemory32<PAGES> {
+    /// Creates a linear memory with its pages stored inline, with a minimum and maximum number of
+    /// pages.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `minimum` number of pages is greater than `PAGES`.
+    pub fn with_limits(minimum: u32, maximum: u32) -> Result<Self, crate::memory::AllocationError> {
+        Self::with_storage(InlinePages::new(), minimum, maximum)
+    }
+}
+
+impl<const PAGES: usize> StaticMemory32<PAGES, Borrowed> {
+    /// Creates a linear memory that stores its contents in the given `buffer`, with a minimum and
+    /// maximum number of pages.
+    ///
+    /// The memory can only grow up to `PAGES` pages, or the number of whole pages that fit in the
+    /// `buffer` if that is smaller. The contents of the `buffer` are overwritten with zeroes as the
+    /// memory grows.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `minimum` number of pages do not fit in the `buffer`.
+    pub fn from_slice(
+        buffer: &'static mut [u8],
+        minimum: u32,
+        maximum: u32,
+    ) -> Result<Self, crate::memory::AllocationError> {
+        let storage = BorrowedPages {
+            capacity: (buffer.len() / PAGE_SIZE).min(PAGES),
+            pointer: NonNull::from(buffer).cast::<u8>(),
+            _buffer: core::marker::PhantomData,
+        };
+
+        Self::with_storage(storage, minimum, maximum)
+    }
+}
+
+impl<const PAGES: usize, B: StaticBuffer<PAGES>> StaticMemory32<PAGES, B> {
+    fn with_storage(
+        storage: B::Storage,
+        minimum: u32,
+        maximum: u32,
+    ) -> Result<Self, crate::memory::AllocationError> {
+        let capacity = u32::try_from(storage.capacity())
+            .unwrap_or(u32::MAX)
+            .min(crate::memory::MAX_PAGES);
+
+        let mut mem = Self {
+            storage,
+            capacity,
+            size: Cell::new(0),
+            minimum: 0,
+            limit: maximum.min(capacity),
+        };
+
+        if mem.grow_pages(minimum) == crate::memory::MEMORY_GROW_FAILED {
+            return Err(crate::memory::AllocationError::with_size(minimum));
+        }
+
+        mem.minimum = minimum;
+        Ok(mem)
+    }
+
+    fn as_ptr(&self) -> *mut u8 {
+        self.storage.as_ptr()
+    }
+
+    /// Returns the size of the linear memory, in bytes.
+    pub fn len(&self) -> usize {
+        self.size.get() as usize * PAGE_SIZE
+    }
+
+    /// Returns `true` if the memory has a size of `0`.
+    pub fn is_empty(&self) -> bool {
+        self.size.get() == 0
+    }
+
+    /// Returns a mutable slice to the linear memory contents.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        // SAFETY: the first `len` bytes of the storage are in bounds.
+        // SAFETY: `mut` ensures exclusive access of underlying storage.
+        unsafe { core::slice::from_raw_parts_mut(self.as_ptr(), self.len()) }
+    }
+
+    /// Gets a pointer to the bytes in the range of addresses `addr..(addr + len)`, or `None` if
+    /// the range is out of bounds.
+    fn get(&self, addr: u32, len: usize) -> Option<*mut u8> {
+        let start = addr as usize;
+        if start.checked_add(len)? <= self.len() {
+            // SAFETY: check above ensures `start` is within the storage.
+            Some(unsafe { self.as_ptr().add(start) })
+        } else {
+            None
+        }
+    }
+
+    fn grow_pages(&self, delta: u32) -> u32 {
+        let old_size = self.size.get();
+        let new_size = match old_size.checked_add(delta) {
+            Some(size) if size <= self.limit => size,
+            _ => return crate::memory::MEMORY_GROW_FAILED,
+        };
+
+        let start = old_size as usize * PAGE_SIZE;
+
+        // SAFETY: the `limit` is never greater than the `capacity`, so the new pages are within
+        // the storage.
+        // SAFETY: `StaticMemory32` is not `Sync`, so no other accesses can occur at the same time.
+        unsafe {
+            core::ptr::write_bytes(self.as_ptr().add(start), 0, delta as usize * PAGE_SIZE);
+        }
+
+        self.size.set(new_size);
+        old_size
+    }
+}
+
+impl<const PAGES: usize, B: StaticBuffer<PAGES> + 'static> crate::memory::Memory32
+    for StaticMemory32<PAGES, B>
+{
+    fn try_as_any(&self, _: crate::memory::private::Hidden) -> Option<&dyn core::any::Any> {
+        Some(self)
+    }
+
+    fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    fn size(&self) -> u32 {
+        self.size.get()
+    }
+
+    fn grow(&self, delta: u32) -> u32 {
+        self.grow_pages(delta)
+    }
+
+    fn copy_to_slice(&self, addr: u32, dst: &mut [u8]) -> crate::memory::BoundsCheck<()> {
+        let src = self
+            .get(addr, dst.len())
+            .ok_or(crate::memory::BoundsCheckError)?;
+
+        // SAFETY: `get()` ensures the range is in bounds.
+        // SAFETY: `dst` is a unique reference, so it can't overlap with the linear memory.
+        unsafe { core::ptr::copy_nonoverlapping(src, dst.as_mut_ptr(), dst.len()) };
+        Ok(())
+    }
+
+    fn copy_from_slice(&self, addr: u32, src: &[u8]) -> crate::memory::BoundsCheck<()> {
+        let dst = self
+            .get(addr, src.len())
+            .ok_or(crate::memory::BoundsCheckError)?;
+
+        // SAFETY: `get()` ensures the range is in bounds.
+        // SAFETY: `StaticMemory32` never hands out shared references to its contents, so `src`
+        // can't overlap with the linear memory.
+        unsafe { core::ptr::copy_nonoverlapping(src.as_ptr(), dst, src.len()) };
+        Ok(())
+    }
+
+    fn load_in_bounds<const N: usize>(
+        &self,
+        proof: crate::memory::InBounds<N>,
+    ) -> crate::memory::BoundsCheck<[u8; N]> {
+        if proof.minimum() > self.minimum {
+            let mut dst = [0u8; N];
+            self.copy_to_slice(proof.address(), &mut dst)?;
+            return Ok(dst);
+        }
+
+        // SAFETY: the access is within `proof.minimum()` pages, and the memory never has fewer than
+        // `self.minimum` pages.
+        Ok(unsafe {
+            self.as_ptr()
+                .add(proof.address() as usize)
+                .cast::<[u8; N]>()
+                .read()
+        })
+    }
+
+    fn store_in_bounds<const N: usize>(
+        &self,
+        proof: crate::memory::InBounds<N>,
+        src: [u8; N],
+    ) -> crate::memory::BoundsCheck<()> {
+        if proof.minimum() > self.minimum {
+            return self.copy_from_slice(proof.address(), &src);
+        }
+
+        // SAFETY: the access is within `proof.minimum()` pages, and the memory never has fewer than
+        // `self.minimum` pages.
+        unsafe {
+            self.as_ptr()
+                .add(proof.address() as usize)
+                .cast::<[u8; N]>()
+                .write(src)
+        };
+
+        Ok(())
+    }
+
+    fn copy_within(
+        &self,
+        dst_addr: u32,
+        src_addr: u32,
+        len: u32,
+    ) -> crate::memory::BoundsCheck<()> {
+        let size = len as usize;
+        let src = self
+            .get(src_addr, size)
+            .ok_or(crate::memory::BoundsCheckError)?;
+        let dst = self
+            .get(dst_addr, size)
+            .ok_or(crate::memory::BoundsCheckError)?;
+
+        // SAFETY: `get()` ensures both ranges are in bounds, `copy` allows overlapping ranges.
+        unsafe { core::ptr::copy(src, dst, size) };
+        Ok(())
+    }
+}
+
+impl<const PAGES: usize, B: StaticBuffer<PAGES> + 'static> crate::memory::Memory32Mut
+    for StaticMemory32<PAGES, B>
+{
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        StaticMemory32::as_mut_slice(self)
+    }
+}
+
+impl<const PAGES: usize, B: StaticBuffer<PAGES> + 'static> crate::memory::AllocateMemory32
+    for StaticMemory32<PAGES, B>
+{
+    fn allocate(minimum: u32, maximum: u32) -> Result<Self, crate::memory::AllocationError> {
+        let storage = B::take().ok_or(crate::memory::AllocationError::with_size(minimum))?;
+        Self::with_storage(storage, minimum, maximum)
+    }
+}
+
+impl<const PAGES: usize, B: StaticBuffer<PAGES>> core::fmt::Debug for StaticMemory32<PAGES, B> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("StaticMemory32")
+            .field("size", &crate::memory::DisplaySize(self.size.get()))
+            .field("limit", &crate::memory::DisplaySize(self.limit))
+            .field("capacity", &crate::memory::DisplaySize(self.capacity))
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,170 @@
+use crate::memory::allocation;
+
+/// A [`Memory32`] implementation backed by a heap allocation that can be shared between threads.
+///
+/// Unlike the [`HeapMemory32`], a [`SyncHeapMemory32`] is [`Sync`], allowing an instance of a
+/// WebAssembly module that uses it to be shared between threads. The allocation is protected by a
+/// [`RwLock`], so loads from different threads can happen concurrently, while stores and attempts
+/// to [grow] the memory have exclusive access to it.
+///
+/// [`Memory32`]: crate::memory::Memory32
+/// [`HeapMemory32`]: crate::memory::HeapMemory32
+/// [`RwLock`]: std::sync::RwLock
+/// [grow]: crate::memory::Memory32::grow()
+pub struct SyncHeapMemory32 {
+    allocation: std::sync::RwLock<allocation::Memory>,
+    /// Maximum number of allowed pages.
+    limit: u32,
+}
+
+impl Default for SyncHeapMemory32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyncHeapMemory32 {
+    /// Allocates an empty linear memory with a maximum number of allowed pages.
+    pub const fn with_maximum(maximum: u32) -> Self {
+        Self {
+            allocation: std::sync::RwLock::new(allocation::Memory::EMPTY),
+            limit: maximum,
+        }
+    }
+
+    /// Allocates an empty linear memory.
+    pub const fn new() -> Self {
+        Self::with_maximum(u32::MAX)
+    }
+
+    /// Allocates a linear memory, with a minimum and maximum number of pages.
+    ///
+    /// If the `minimum` is greater than `0`, then new pages are allocated.
+    pub fn with_limits(minimum: u32, maximum: u32) -> Result<Self, crate::memory::AllocationError> {
+        let mut mem = Self::with_maximum(maximum);
+        match mem.allocation_mut().grow(minimum) {
+            Some(_) => Ok(mem),
+            None => Err(crate::memory::AllocationError::with_size(minimum)),
+        }
+    }
+
+    // The contents of the memory are plain bytes, so a panic while the lock is held can't leave
+    // them in an invalid state.
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, allocation::Memory> {
+        self.allocation
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, allocation::Memory> {
+        self.allocation
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn allocation_mut(&mut self) -> &mut allocation::Memory {
+        self.allocation
+            .get_mut()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Returns the size of the linear memory, in bytes.
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Returns `true` if the memory has a size of `0`.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a mutable slice to the linear memory contents.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.allocation_mut().as_mut_slice()
+    }
+}
+
+/// Gets the range of bytes `addr..(addr + len)`, or `None` if it is out of bounds.
+fn range(memory: &allocation::Memory, addr: u32, len: usize) -> Option<core::ops::Range<usize>> {
+    let start = addr as usize;
+    let end = start.checked_add(len)?;
+    if end <= memory.len() {
+        Some(start..end)
+    } else {
+        None
+    }
+}
+
+impl crate::memory::Memory32 for SyncHeapMemory32 {
+    fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    fn size(&self) -> u32 {
+        self.read().size()
+    }
+
+    fn grow(&self, delta: u32) -> u32 {
+        let mut allocation = self.write();
+        match allocation.size().checked_add(delta) {
+            Some(new_size) if new_size <= self.limit => allocation
+                .grow(delta)
+                .unwrap_or(crate::memory::MEMORY_GROW_FAILED),
+            _ => crate::memory::MEMORY_GROW_FAILED,
+        }
+    }
+
+    fn copy_to_slice(&self, addr: u32, dst: &mut [u8]) -> crate::memory::BoundsCheck<()> {
+        let allocation = self.read();
+        let range = range(&allocation, addr, dst.len()).ok_or(crate::memory::BoundsCheckError)?;
+        dst.copy_from_slice(&allocation.as_slice()[range]);
+        Ok(())
+    }
+
+    fn copy_from_slice(&self, addr: u32, src: &[u8]) -> crate::memory::BoundsCheck<()> {
+        let mut allocation = self.write();
+        let range = range(&allocation, addr, src.len()).ok_or(crate::memory::BoundsCheckError)?;
+        allocation.as_mut_slice()[range].copy_from_slice(src);
+        Ok(())
+    }
+
+    fn copy_within(
+        &self,
+        dst_addr: u32,
+        src_addr: u32,
+        len: u32,
+    ) -> crate::memory::BoundsCheck<()> {
+        let mut allocation = self.write();
+        let src = range(&allocation, src_addr, len as usize);
+        let dst = range(&allocation, dst_addr, len as usize);
+        match (src, dst) {
+            (Some(src), Some(dst)) => {
+                allocation.as_mut_slice().copy_within(src, dst.start);
+                Ok(())
+            }
+            _ => Err(crate::memory::BoundsCheckError),
+        }
+    }
+}
+
+impl crate::memory::Memory32Mut for SyncHeapMemory32 {
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        SyncHeapMemory32::as_mut_slice(self)
+    }
+}
+
+impl crate::memory::AllocateMemory32 for SyncHeapMemory32 {
+    fn allocate(minimum: u32, maximum: u32) -> Result<Self, crate::memory::AllocationError> {
+        Self::with_limits(minimum, maximum)
+    }
+}
+
+impl core::fmt::Debug for SyncHeapMemory32 {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SyncHeapMemory32")
+            .field("allocation", &*self.read())
+            .field("limit", &crate::memory::DisplaySize(self.limit))
+            .finish()
+    }
+}
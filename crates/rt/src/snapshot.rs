@@ -0,0 +1,219 @@
+//! Saving and restoring the mutable state of WebAssembly module instances.
+//!
+//! When a WebAssembly module is translated with `Translation::snapshots()` enabled, the generated
+//! `Instance` can serialize the contents of the memories and mutable globals it defines into a
+//! binary blob, which can later be used to restore a fresh instance of the same module to that
+//! state. This is useful for crash recovery, or to skip expensive initialization on cold starts:
+//!
+//! - `Instance::snapshot()` returns the snapshot [`Bytes`], and requires the `alloc` feature.
+//! - `Instance::restore(snapshot)` overwrites the state of the instance with the snapshot.
+//!
+//! Imported memories and globals are owned by the embedder, and are not included in snapshots.
+//! Snapshots of modules with tables are not yet supported.
+//!
+//! A snapshot begins with a header containing the snapshot format [`VERSION`] and a fingerprint
+//! of the WebAssembly module, which is checked when the snapshot is restored:
+//!
+//! ```
+//! use wasm2rs_rt::snapshot::{Reader, SnapshotError, Writer};
+//!
+//! let mut writer = Writer::new(0xC0FFEE);
+//! writer.write_value(42i32);
+//! let snapshot = writer.finish();
+//!
+//! let mut reader = Reader::new(&snapshot, 0xC0FFEE).unwrap();
+//! assert_eq!(reader.read_value::<i32>(), Ok(42));
+//! assert_eq!(reader.finish(), Ok(()));
+//!
+//! assert_eq!(
+//!     Reader::new(&snapshot, 0xDECAF).err(),
+//!     Some(SnapshotError::FingerprintMismatch { expected: 0xDECAF, actual: 0xC0FFEE }),
+//! );
+//! ```
+
+use crate::memory::Pod;
+
+/// The bytes that every snapshot starts with.
+pub const MAGIC: [u8; 4] = *b"\0w2s";
+
+/// The version of the snapshot format written by this version of `wasm2rs_rt`.
+pub const VERSION: u32 = 1;
+
+/// The contents of a snapshot.
+#[cfg(feature = "alloc")]
+pub type Bytes = alloc::vec::Vec<u8>;
+
+/// Error type used when a snapshot could not be restored.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum SnapshotError {
+    /// The bytes did not start with the snapshot [`MAGIC`].
+    InvalidHeader,
+    /// The snapshot was written in a different format [`VERSION`].
+    UnsupportedVersion(u32),
+    /// The snapshot was taken from an instance of a different WebAssembly module.
+    FingerprintMismatch {
+        /// The fingerprint of the module being restored.
+        expected: u64,
+        /// The fingerprint recorded in the snapshot.
+        actual: u64,
+    },
+    /// The snapshot was truncated, had trailing bytes, or was otherwise malformed.
+    Malformed,
+    /// A [linear memory] could not be resized to the number of pages recorded in the snapshot.
+    ///
+    /// [linear memory]: crate::memory::Memory32
+    MemorySize {
+        /// The number of pages recorded in the snapshot.
+        expected: u32,
+        /// The number of pages in the memory.
+        actual: u32,
+    },
+}
+
+impl core::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidHeader => f.write_str("not a wasm2rs snapshot"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {version}")
+            }
+            Self::FingerprintMismatch { expected, actual } => write!(
+                f,
+                "snapshot was taken from a different module (expected fingerprint {expected:#018X}, but got {actual:#018X})"
+            ),
+            Self::Malformed => f.write_str("snapshot is malformed"),
+            Self::MemorySize { expected, actual } => write!(
+                f,
+                "could not resize memory with {actual} pages to the {expected} pages in the snapshot"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SnapshotError {}
+
+/// Writes the contents of a snapshot.
+///
+/// See the [module documentation](crate::snapshot) for more information.
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+pub struct Writer {
+    bytes: Bytes,
+}
+
+#[cfg(feature = "alloc")]
+impl Writer {
+    /// Creates a new snapshot of an instance of the module with the given `fingerprint`.
+    pub fn new(fingerprint: u64) -> Self {
+        let mut writer = Self {
+            bytes: Bytes::with_capacity(16),
+        };
+
+        writer.bytes.extend_from_slice(&MAGIC);
+        writer.write_value(VERSION);
+        writer.write_value(fingerprint);
+        writer
+    }
+
+    /// Appends a value to the snapshot.
+    pub fn write_value<T: Pod>(&mut self, value: T) {
+        self.bytes.extend_from_slice(value.to_le_bytes().as_ref());
+    }
+
+    /// Appends a slice of bytes to the snapshot, preceded by its length.
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_value(bytes.len() as u64);
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    /// Returns the contents of the snapshot.
+    pub fn finish(self) -> Bytes {
+        self.bytes
+    }
+}
+
+/// Reads the contents of a snapshot.
+///
+/// See the [module documentation](crate::snapshot) for more information.
+#[derive(Debug)]
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    /// Checks the header of the snapshot, which must have been taken from an instance of the
+    /// module with the given `fingerprint`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `bytes` are not a snapshot written in the current [`VERSION`], or
+    /// if the snapshot was taken from an instance of a different module.
+    pub fn new(bytes: &'a [u8], fingerprint: u64) -> Result<Self, SnapshotError> {
+        let mut reader = match bytes.strip_prefix(&MAGIC) {
+            Some(bytes) => Self { bytes },
+            None => return Err(SnapshotError::InvalidHeader),
+        };
+
+        let version = reader.read_value::<u32>()?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let actual = reader.read_value::<u64>()?;
+        if actual != fingerprint {
+            return Err(SnapshotError::FingerprintMismatch {
+                expected: fingerprint,
+                actual,
+            });
+        }
+
+        Ok(reader)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if len > self.bytes.len() {
+            return Err(SnapshotError::Malformed);
+        }
+
+        let (taken, remaining) = self.bytes.split_at(len);
+        self.bytes = remaining;
+        Ok(taken)
+    }
+
+    /// Reads the next value in the snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the end of the snapshot was reached.
+    pub fn read_value<T: Pod>(&mut self) -> Result<T, SnapshotError> {
+        let mut bytes = T::Bytes::default();
+        let dst = bytes.as_mut();
+        dst.copy_from_slice(self.take(dst.len())?);
+        Ok(T::from_le_bytes(bytes))
+    }
+
+    /// Reads the next slice of bytes in the snapshot, written by [`Writer::write_bytes()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the end of the snapshot was reached.
+    pub fn read_bytes(&mut self) -> Result<&'a [u8], SnapshotError> {
+        let len = self.read_value::<u64>()?;
+        self.take(usize::try_from(len).map_err(|_| SnapshotError::Malformed)?)
+    }
+
+    /// Checks that the entire snapshot was read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are any bytes remaining in the snapshot.
+    pub fn finish(self) -> Result<(), SnapshotError> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(SnapshotError::Malformed)
+        }
+    }
+}
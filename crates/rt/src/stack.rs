@@ -4,6 +4,12 @@ use crate::trap::Trap;
 
 pub mod trace;
 
+mod depth;
+
+pub use depth::{
+    enter_call, enter_call_unguarded, exit_call, CallDepth, CallDepthGuard, CallDepthLimit,
+};
+
 /// Checks if there is enough space on the stack for approximately `amount` bytes worth of
 /// variables.
 ///
@@ -12,6 +18,9 @@ pub mod trace;
 /// stack overflow. Typical usage with `wasm2rs` will often overestimate the amount of stack space
 /// used for functions.
 ///
+/// For behavior that is reproducible across machines, and that is also available in `no_std`
+/// environments, see [`enter_call()`] instead.
+///
 /// If the `stack-overflow-detection` feature is enabled, it uses the [`stacker::remaining_stack()`]
 /// function to estimate the remaining stack space. In situations where the remaining stack space
 /// cannot be queried, or if the feature is not enabled, this function does nothing.
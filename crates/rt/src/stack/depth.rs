@@ -0,0 +1,141 @@
+use crate::trap::Trap;
+
+/// Tracks the number of WebAssembly function calls that are currently executing, used to
+/// deterministically detect call stack exhaustion.
+///
+/// Unlike [`check_for_overflow()`], which estimates the remaining space on the native stack, a
+/// call depth limit produces identical results on every host, and works in `no_std` environments.
+/// However, the limit must be low enough that the native stack does not actually overflow before
+/// it is reached.
+///
+/// [`check_for_overflow()`]: crate::stack::check_for_overflow()
+#[derive(Clone)]
+pub struct CallDepth {
+    current: core::cell::Cell<u32>,
+    limit: core::cell::Cell<u32>,
+}
+
+impl Default for CallDepth {
+    fn default() -> Self {
+        Self::with_limit(Self::DEFAULT_LIMIT)
+    }
+}
+
+impl CallDepth {
+    /// The default maximum number of nested calls.
+    pub const DEFAULT_LIMIT: u32 = 10000;
+
+    /// Creates a new call depth counter with the given maximum number of nested calls.
+    pub const fn with_limit(limit: u32) -> Self {
+        Self {
+            current: core::cell::Cell::new(0),
+            limit: core::cell::Cell::new(limit),
+        }
+    }
+
+    /// Gets the number of nested calls that are currently executing.
+    pub fn current(&self) -> u32 {
+        self.current.get()
+    }
+
+    /// Gets the maximum number of nested calls.
+    pub fn limit(&self) -> u32 {
+        self.limit.get()
+    }
+
+    /// Sets the maximum number of nested calls.
+    ///
+    /// This only affects calls that occur after the limit is changed.
+    pub fn set_limit(&self, limit: u32) {
+        self.limit.set(limit)
+    }
+}
+
+impl core::fmt::Debug for CallDepth {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CallDepth")
+            .field("current", &self.current.get())
+            .field("limit", &self.limit.get())
+            .finish()
+    }
+}
+
+/// Decrements the [`CallDepth`] when a WebAssembly function returns.
+///
+/// This is returned by [`enter_call()`].
+#[derive(Debug)]
+#[must_use = "the call depth is decremented when the guard is dropped"]
+pub struct CallDepthGuard<'a> {
+    depth: &'a CallDepth,
+}
+
+impl Drop for CallDepthGuard<'_> {
+    fn drop(&mut self) {
+        self.depth.current.set(self.depth.current.get() - 1);
+    }
+}
+
+/// Trait for embedders that provide a [`CallDepth`] counter to WebAssembly modules translated with
+/// call depth checks enabled.
+pub trait CallDepthLimit: Trap {
+    /// Gets the call depth counter.
+    fn call_depth(&self) -> &CallDepth;
+}
+
+impl<T: CallDepthLimit + ?Sized> CallDepthLimit for &T {
+    fn call_depth(&self) -> &CallDepth {
+        <T>::call_depth(self)
+    }
+}
+
+/// Increments the [`CallDepth`] at the start of a WebAssembly function, returning a guard that
+/// decrements it when the function returns.
+///
+/// # Errors
+///
+/// If the [`CallDepth::limit()`] would be exceeded, a [`Trap`] is produced using
+/// [`Trap::trap_stack_overflow()`].
+#[inline]
+pub fn enter_call<E>(embedder: &E) -> Result<CallDepthGuard<'_>, E::Repr>
+where
+    E: CallDepthLimit + ?Sized,
+{
+    let depth = embedder.call_depth();
+    let current = depth.current.get();
+    if current < depth.limit.get() {
+        depth.current.set(current + 1);
+        Ok(CallDepthGuard { depth })
+    } else {
+        Err(embedder.trap_stack_overflow())
+    }
+}
+
+/// Increments the [`CallDepth`] at the start of a WebAssembly function, without returning a guard.
+///
+/// This is used instead of [`enter_call()`] by code generated with exclusive access to the
+/// instance, since a [`CallDepthGuard`] would keep the embedder borrowed while the function calls
+/// other functions. Each successful call must be paired with a call to [`exit_call()`] when the
+/// function returns.
+///
+/// # Errors
+///
+/// See the documentation for [`enter_call()`] for more information.
+#[inline]
+pub fn enter_call_unguarded<E>(embedder: &E) -> Result<(), E::Repr>
+where
+    E: CallDepthLimit + ?Sized,
+{
+    core::mem::forget(enter_call(embedder)?);
+    Ok(())
+}
+
+/// Decrements the [`CallDepth`] when a WebAssembly function entered with
+/// [`enter_call_unguarded()`] returns.
+#[inline]
+pub fn exit_call<E>(embedder: &E)
+where
+    E: CallDepthLimit + ?Sized,
+{
+    let depth = embedder.call_depth();
+    depth.current.set(depth.current.get() - 1);
+}
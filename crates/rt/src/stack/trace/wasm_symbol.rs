@@ -22,7 +22,7 @@ impl core::fmt::Display for WasmImportSymbol {
 
 /// Describes the type of a function parameter or result in a [`WasmSymbolSignature`].
 #[allow(missing_docs)]
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 #[non_exhaustive]
 pub enum WasmValType {
     I32,
@@ -7,9 +7,13 @@ mod trap_value;
 pub use crate::stack::trace::WasmStackTraceFrame;
 pub use trap_value::TrapValue;
 
-/// Describes which limits a memory or table did not match.
+/// Describes which limits an imported memory or table did not match.
 ///
 /// For memories, the limits are expressed as the number of pages.
+///
+/// See the documentation for [`LinkErrorKind`] for more information.
+///
+/// [`LinkErrorKind`]: crate::linker::LinkErrorKind
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 #[non_exhaustive]
 #[allow(missing_docs)]
@@ -76,19 +80,22 @@ pub enum TrapCode {
         /// The error describing why the memory could not be allocated.
         error: crate::memory::AllocationError,
     },
-    /// Instantiating a module failed because a linear memory did not have matching [`limits`].
-    ///
-    /// [`limits`]: crate::memory::Memory32::limit
-    MemoryLimitsCheck {
-        /// The index of the memory whose limits did not match.
-        memory: u32,
-        /// Describes which limit the memory did not match.
-        limits: LimitsCheck,
-    },
     /// The stack space was exhausted, usually due to an infinitely recursive function.
     ///
     /// See the documentation for [`Trap::trap_stack_overflow()`] for more information.
     CallStackExhausted,
+    /// A WebAssembly module translated with fuel metering enabled ran out of fuel.
+    ///
+    /// See the documentation for the [`fuel`] module for more information.
+    ///
+    /// [`fuel`]: crate::fuel
+    OutOfFuel,
+    /// A WebAssembly module translated with epoch interruption enabled reached its deadline.
+    ///
+    /// See the documentation for the [`epoch`] module for more information.
+    ///
+    /// [`epoch`]: crate::epoch
+    Interrupted,
 }
 
 impl core::cmp::PartialEq<TrapCode> for &TrapCode {
@@ -126,10 +133,9 @@ impl core::fmt::Display for TrapCode {
             Self::MemoryAllocation { memory, error } => {
                 write!(f, "{error} #{memory}")
             }
-            Self::MemoryLimitsCheck { memory, limits } => {
-                write!(f, "{limits} pages in memory #{memory}")
-            }
             Self::CallStackExhausted => f.write_str("call stack exhausted"),
+            Self::OutOfFuel => f.write_str("all fuel consumed"),
+            Self::Interrupted => f.write_str("interrupted"),
         }
     }
 }
@@ -141,7 +147,6 @@ impl std::error::Error for TrapCode {
             Self::MemoryBoundsCheck { source, .. } => Some(source),
             Self::IndirectCallSignatureMismatch(error) => Some(error),
             Self::MemoryAllocation { error, .. } => Some(error),
-            Self::MemoryLimitsCheck { limits, .. } => Some(limits),
             _ => None,
         }
     }
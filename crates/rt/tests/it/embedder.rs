@@ -0,0 +1,21 @@
+use wasm2rs_rt::embedder::{ExecutionLimits, State};
+
+#[test]
+fn state_without_limits_is_copy() {
+    fn assert_copy<T: Copy>(_: &T) {}
+
+    let state = State::<i32>::new(5);
+    assert_copy(&state);
+    assert_eq!(*state.imports(), 5);
+}
+
+#[test]
+fn limits_are_cloned() {
+    let state = State::<(), wasm2rs_rt::embedder::Memory0, ExecutionLimits>::default();
+    state.set_fuel(10);
+    state.add_fuel(5);
+
+    let cloned = state.clone();
+    state.set_fuel(0);
+    assert_eq!(cloned.remaining_fuel(), 15);
+}
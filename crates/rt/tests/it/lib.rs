@@ -1,3 +1,5 @@
 //! Unit tests for [`wasm2rs_rt`].
 
+mod embedder;
 mod func_ref;
+mod memory;
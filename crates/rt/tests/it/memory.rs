@@ -0,0 +1,300 @@
+#[cfg(all(feature = "mmap", unix, target_pointer_width = "64"))]
+mod mmap {
+    use wasm2rs_rt::memory::{Memory32, MmapMemory32, PAGE_SIZE};
+
+    #[test]
+    fn reads_and_writes() {
+        let mem = MmapMemory32::with_limits(1, 4).unwrap();
+        assert_eq!(mem.size(), 1);
+        assert_eq!(mem.i32_load::<0>(PAGE_SIZE - 4), Ok(0));
+
+        mem.i32_store::<0>(1234, 0x0403_0201).unwrap();
+        assert_eq!(mem.i32_load::<0>(1234), Ok(0x0403_0201));
+        assert_eq!(mem.i8_load(1235), Ok(2));
+
+        assert!(mem.i32_load::<0>(PAGE_SIZE - 3).is_err());
+        assert!(mem.i64_store::<0>(u32::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn growing_does_not_move() {
+        let mem = MmapMemory32::with_limits(1, 3).unwrap();
+        let base = mem.as_ptr();
+        mem.i32_store::<0>(16, 42).unwrap();
+
+        assert_eq!(mem.grow(2), 1);
+        assert_eq!(mem.size(), 3);
+        assert_eq!(mem.as_ptr(), base);
+        assert_eq!(mem.i32_load::<0>(16), Ok(42));
+        assert_eq!(mem.i32_load::<0>(PAGE_SIZE * 2 + 8), Ok(0));
+
+        // Exceeds the maximum.
+        assert_eq!(mem.grow(1), u32::MAX);
+        assert_eq!(mem.size(), 3);
+    }
+
+    #[test]
+    fn copy_within_overlapping() {
+        let mem = MmapMemory32::with_limits(1, 1).unwrap();
+        mem.copy_from_slice(0, &[1, 2, 3, 4, 5]).unwrap();
+        mem.copy_within(2, 0, 3).unwrap();
+
+        let mut contents = [0u8; 5];
+        mem.copy_to_slice(0, &mut contents).unwrap();
+        assert_eq!(contents, [1, 2, 1, 2, 3]);
+        assert!(mem.copy_within(PAGE_SIZE - 1, 0, 2).is_err());
+    }
+}
+
+mod static_memory {
+    use wasm2rs_rt::memory::{AllocateMemory32, Memory32, StaticMemory32, PAGE_SIZE};
+
+    #[test]
+    fn inline_pages() {
+        let mem = StaticMemory32::<2>::with_limits(1, u32::MAX).unwrap();
+        assert_eq!(mem.limit(), 2);
+        mem.i32_store::<0>(PAGE_SIZE - 4, 7).unwrap();
+        assert!(mem.i32_load::<0>(PAGE_SIZE).is_err());
+
+        assert_eq!(mem.grow(1), 1);
+        assert_eq!(mem.i32_load::<0>(PAGE_SIZE), Ok(0));
+        assert_eq!(mem.grow(1), u32::MAX);
+
+        assert!(StaticMemory32::<1>::with_limits(2, 2).is_err());
+    }
+
+    #[test]
+    fn borrowed_buffer() {
+        let buffer = Box::leak(vec![0xAAu8; PAGE_SIZE as usize * 2 + 100].into_boxed_slice());
+        let mem = StaticMemory32::<4, _>::from_slice(buffer, 1, u32::MAX).unwrap();
+        assert_eq!(mem.limit(), 2);
+        assert_eq!(mem.i8_load(PAGE_SIZE - 1), Ok(0));
+        assert!(mem.i8_load(PAGE_SIZE).is_err());
+
+        assert_eq!(mem.grow(1), 1);
+        assert_eq!(mem.i64_load::<0>(PAGE_SIZE + 8), Ok(0));
+        assert_eq!(mem.grow(1), u32::MAX);
+
+        let buffer = Box::leak(vec![0u8; PAGE_SIZE as usize].into_boxed_slice());
+        assert!(StaticMemory32::<4, _>::from_slice(buffer, 2, 2).is_err());
+    }
+
+    #[test]
+    fn allocated_from_static_pages() {
+        use wasm2rs_rt::memory::{StaticBuffer, StaticPages, TakenPages};
+
+        static PAGES: StaticPages<2> = StaticPages::new();
+
+        struct Buffer;
+
+        impl StaticBuffer<2> for Buffer {
+            type Storage = TakenPages<2>;
+
+            fn take() -> Option<TakenPages<2>> {
+                PAGES.take()
+            }
+        }
+
+        let mem = StaticMemory32::<2, Buffer>::allocate(1, 8).unwrap();
+        assert_eq!(mem.size(), 1);
+        assert_eq!(mem.limit(), 2);
+
+        mem.i32_store::<0>(PAGE_SIZE - 4, 7).unwrap();
+        assert!(mem.i32_store::<0>(PAGE_SIZE, 7).is_err());
+
+        assert_eq!(mem.grow(1), 1);
+        assert_eq!(mem.i32_load::<0>(PAGE_SIZE), Ok(0));
+        assert_eq!(mem.i32_load::<0>(PAGE_SIZE - 4), Ok(7));
+        assert_eq!(mem.grow(1), u32::MAX);
+
+        // The pages are in use.
+        assert!(StaticMemory32::<2, Buffer>::allocate(0, 1).is_err());
+
+        // Dropping the memory returns the pages, which are cleared when they are reused.
+        drop(mem);
+        let mem = StaticMemory32::<2, Buffer>::allocate(1, 1).unwrap();
+        assert_eq!(mem.i32_load::<0>(PAGE_SIZE - 4), Ok(0));
+    }
+}
+
+mod heap {
+    use wasm2rs_rt::embedder::State;
+    use wasm2rs_rt::memory::{HeapMemory32, PAGE_SIZE};
+
+    #[test]
+    fn in_bounds_accesses() {
+        use wasm2rs_rt::memory::{i32_load_in_bounds, i32_store_in_bounds};
+
+        let trap = State::<()>::default();
+        let mem = HeapMemory32::with_limits(1, 1).unwrap();
+        i32_store_in_bounds::<4, { PAGE_SIZE - 4 }, 1, 0, _, _>(&mem, 8, 42, &trap).unwrap();
+        assert_eq!(
+            i32_load_in_bounds::<0, { PAGE_SIZE - 4 }, 1, 0, _, _>(&mem, 12, &trap),
+            Ok(42)
+        );
+
+        // Addresses above the bound are checked as usual.
+        assert!(i32_load_in_bounds::<0, 0, 1, 0, _, _>(&mem, -1, &trap).is_err());
+    }
+
+    #[test]
+    fn in_bounds_accesses_trap_on_smaller_memory() {
+        use wasm2rs_rt::memory::{i64_load_in_bounds, i64_store_in_bounds};
+
+        let trap = State::<()>::default();
+        let mem = HeapMemory32::with_limits(0, 1).unwrap();
+        assert!(i64_load_in_bounds::<0, 0, 1, 0, _, _>(&mem, 0, &trap).is_err());
+        assert!(i64_store_in_bounds::<0, 0, 1, 0, _, _>(&mem, 0, 1, &trap).is_err());
+    }
+
+    #[test]
+    fn exclusive_in_bounds_accesses() {
+        use wasm2rs_rt::memory::exclusive::{i64_load_in_bounds, i64_store_in_bounds};
+
+        let trap = State::<()>::default();
+        let mut mem = HeapMemory32::with_limits(1, 1).unwrap();
+        i64_store_in_bounds::<8, { PAGE_SIZE - 8 }, 1, 0, _, _>(&mut mem, 0, -3, &trap).unwrap();
+        assert_eq!(
+            i64_load_in_bounds::<0, { PAGE_SIZE - 8 }, 1, 0, _, _>(&mut mem, 8, &trap),
+            Ok(-3)
+        );
+
+        // Addresses above the bound are checked as usual.
+        assert!(i64_load_in_bounds::<0, 0, 1, 0, _, _>(&mut mem, -1, &trap).is_err());
+
+        // Memories smaller than the minimum are still checked.
+        let mut mem = HeapMemory32::with_limits(0, 1).unwrap();
+        assert!(i64_store_in_bounds::<0, 0, 1, 0, _, _>(&mut mem, 0, 1, &trap).is_err());
+    }
+
+    #[test]
+    fn borrowed_views() {
+        use wasm2rs_rt::memory::Memory32;
+
+        let mem = HeapMemory32::with_limits(1, 2).unwrap();
+        mem.with_mut_slice(4, 4, |bytes| bytes.copy_from_slice(&[1, 2, 3, 4]))
+            .unwrap();
+
+        let sum = mem.with_slice(0, PAGE_SIZE, |bytes| {
+            bytes.iter().map(|b| u32::from(*b)).sum::<u32>()
+        });
+
+        assert_eq!(sum, Ok(10));
+        assert_eq!(mem.i32_load::<0>(4), Ok(0x0403_0201));
+        assert!(mem.with_slice(PAGE_SIZE - 1, 2, |_| ()).is_err());
+        assert_eq!(mem.grow(1), 1);
+
+        // The memory is returned even if the view panics.
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            mem.with_slice(0, 4, |_| panic!("oops"))
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(mem.size(), 2);
+        assert_eq!(mem.i32_load::<0>(4), Ok(0x0403_0201));
+    }
+
+    #[test]
+    #[should_panic = "borrowed"]
+    fn access_while_viewed() {
+        use wasm2rs_rt::memory::i32_load_in_bounds;
+
+        let trap = State::<()>::default();
+        let mem = HeapMemory32::with_limits(1, 1).unwrap();
+        let _ = mem.with_slice(0, 4, |_| {
+            i32_load_in_bounds::<0, 0, 1, 0, _, _>(&mem, 0, &trap)
+        });
+    }
+
+    #[test]
+    #[should_panic = "borrowed"]
+    fn grow_while_viewed() {
+        use wasm2rs_rt::memory::Memory32;
+
+        let mem = HeapMemory32::with_limits(1, 2).unwrap();
+        let _ = mem.with_mut_slice(0, 4, |_| mem.grow(1));
+    }
+}
+
+mod ptr {
+    use wasm2rs_rt::embedder::State;
+    use wasm2rs_rt::memory::{HeapMemory32, Memory32, WasmPtr, WasmSlice, WasmStr, PAGE_SIZE};
+    use wasm2rs_rt::trap::TrapCode;
+
+    fn bounds_check_address(code: &TrapCode) -> Option<(u32, u64)> {
+        match code {
+            TrapCode::MemoryBoundsCheck {
+                memory, address, ..
+            } => Some((*memory, *address)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn pointers_read_and_write() {
+        let trap = State::<()>::default();
+        let mem = HeapMemory32::with_limits(1, 1).unwrap();
+        let ptr = WasmPtr::<i64>::from(8);
+
+        ptr.write(&mem, -2, &trap).unwrap();
+        assert_eq!(ptr.read(&mem, &trap), Ok(-2));
+        assert_eq!(mem.i64_load::<0>(8), Ok(-2));
+        assert_eq!(ptr.checked_add(2).map(WasmPtr::address), Some(24));
+        assert_eq!(ptr.cast::<u8>().read(&mem, &trap), Ok(0xFE));
+
+        let error = WasmPtr::<u32, 1>::new(PAGE_SIZE - 2)
+            .read(&mem, &trap)
+            .unwrap_err();
+        assert_eq!(
+            bounds_check_address(error.code()),
+            Some((1, u64::from(PAGE_SIZE - 2)))
+        );
+    }
+
+    #[test]
+    fn slices_read_and_write() {
+        let trap = State::<()>::default();
+        let mem = HeapMemory32::with_limits(1, 1).unwrap();
+        let slice = WasmSlice::<u16>::new(4, 3);
+
+        assert_eq!(slice.write_from(&mem, &[1, 2, 3, 4], &trap), Ok(3));
+        assert_eq!(mem.i32_load::<0>(4), Ok(0x0002_0001));
+        assert_eq!(mem.i32_load::<0>(8), Ok(3));
+        assert_eq!(slice.to_vec(&mem, &trap), Ok(vec![1, 2, 3]));
+        assert_eq!(slice.get(2).unwrap().read(&mem, &trap), Ok(3));
+        assert!(slice.get(3).is_none());
+
+        let mut dst = [0u16; 2];
+        assert_eq!(slice.read_into(&mem, &mut dst, &trap), Ok(2));
+        assert_eq!(dst, [1, 2]);
+
+        // The whole slice is checked before anything is written.
+        let error = WasmSlice::<u16>::new(PAGE_SIZE - 4, 3)
+            .write_from(&mem, &[5, 6, 7], &trap)
+            .unwrap_err();
+        assert_eq!(
+            bounds_check_address(error.code()),
+            Some((0, u64::from(PAGE_SIZE - 4)))
+        );
+        assert_eq!(mem.i32_load::<0>(PAGE_SIZE - 4), Ok(0));
+    }
+
+    #[test]
+    fn strings_are_validated() {
+        let trap = State::<()>::default();
+        let mem = HeapMemory32::with_limits(1, 1).unwrap();
+        mem.copy_from_slice(0, b"hello\xFF").unwrap();
+
+        assert_eq!(
+            WasmStr::<0>::from_raw_parts(0, 5).to_string(&mem, &trap),
+            Ok(Ok(String::from("hello")))
+        );
+        assert!(WasmStr::<0>::new(0, 6)
+            .to_string(&mem, &trap)
+            .unwrap()
+            .is_err());
+        assert!(WasmStr::<0>::new(PAGE_SIZE, 1)
+            .to_string(&mem, &trap)
+            .is_err());
+    }
+}
@@ -3,13 +3,73 @@ fn main() {
 
     let buffer_pool = wasm2rs::buffer::Pool::default();
     let func_validator_allocation_pool = wasm2rs::FuncValidatorAllocationPool::default();
-    let mut translation_options = wasm2rs::Translation::new();
-    translation_options
-        .buffer_pool(&buffer_pool)
-        .func_validator_allocation_pool(&func_validator_allocation_pool)
-        .debug_info(wasm2rs::DebugInfo::Full);
+    let options = |configure: &dyn Fn(&mut wasm2rs::Translation)| {
+        let mut options = wasm2rs::Translation::new();
+        options
+            .buffer_pool(&buffer_pool)
+            .func_validator_allocation_pool(&func_validator_allocation_pool)
+            .debug_info(wasm2rs::DebugInfo::Full);
+        configure(&mut options);
+        options
+    };
+
+    let translation_options = options(&|_| ());
+    let fuel_metering_options = options(&|o| {
+        o.fuel_metering(true);
+    });
+    let epoch_interruption_options = options(&|o| {
+        o.epoch_interruption(true);
+    });
+    let call_depth_options = options(&|o| {
+        o.emit_call_depth_checks(true);
+    });
+    let stack_usage_options = options(&|o| {
+        o.stack_usage_analysis(true);
+    });
+    let async_imports_options = options(&|o| {
+        o.async_imports(&[("env", "fetch")]);
+    });
+    let coroutine_options = options(&|o| {
+        o.async_imports(&[("host", "next")]);
+    });
+    let caller_options = options(&|o| {
+        o.import_caller(true);
+    });
+    let bound_imports_options = options(&|o| {
+        o.bind_import("math", "mul_add", "crate::bound_imports::mul_add");
+    });
+    let dynamic_api_options = options(&|o| {
+        o.dynamic_api(true);
+    });
+    let func_refs_options = options(&|o| {
+        o.func_refs(true);
+    });
+    let runtime_linker_options = options(&|o| {
+        o.runtime_linker(true);
+    });
+    let exclusive_access_options = options(&|o| {
+        o.exclusive_access(true);
+    });
+    let exclusive_call_depth_options = options(&|o| {
+        o.exclusive_access(true).emit_call_depth_checks(true);
+    });
+    let combined_options = options(&|o| {
+        o.exclusive_access(true)
+            .emit_call_depth_checks(true)
+            .fuel_metering(true)
+            .epoch_interruption(true);
+    });
+    let thread_safe_options = options(&|o| {
+        o.thread_safe(true);
+    });
+    let snapshot_options = options(&|o| {
+        o.snapshots(true);
+    });
+    let pre_init_options = options(&|o| {
+        o.pre_initialize(Some("_initialize"));
+    });
 
-    let compile_wasm = |wat: &str, name: &str| {
+    let compile_wasm_with = |options: &wasm2rs::Translation, wat: &str, name: &str| {
         let mut out_path = std::path::Path::join(out_dir.as_ref(), name);
         out_path.set_extension("rs");
 
@@ -23,15 +83,149 @@ fn main() {
             Err(e) => panic!("could not open output file {out_path:?}: {e}"),
         };
 
-        if let Err(e) = translation_options.translate_from_buffer(&wasm, &mut output) {
+        if let Err(e) = options.translate_from_buffer(&wasm, &mut output) {
             panic!("compilation failed for {name:?}: {e}");
         }
     };
 
+    let compile_wasm = |wat: &str, name: &str| compile_wasm_with(&translation_options, wat, name);
+
     println!("cargo:rerun-if-changed=src/simple.wat");
     println!("cargo:rerun-if-changed=src/memory.wat");
     println!("cargo:rerun-if-changed=src/imports.wat");
+    println!("cargo:rerun-if-changed=src/fuel.wat");
+    println!("cargo:rerun-if-changed=src/epoch.wat");
+    println!("cargo:rerun-if-changed=src/call_depth.wat");
+    println!("cargo:rerun-if-changed=src/stack_usage.wat");
+    println!("cargo:rerun-if-changed=src/async_imports.wat");
+    println!("cargo:rerun-if-changed=src/coroutine.wat");
+    println!("cargo:rerun-if-changed=src/caller.wat");
+    println!("cargo:rerun-if-changed=src/bound_imports.wat");
+    println!("cargo:rerun-if-changed=src/linked_lib.wat");
+    println!("cargo:rerun-if-changed=src/linked_main.wat");
+    println!("cargo:rerun-if-changed=src/dylink.wat");
+    println!("cargo:rerun-if-changed=src/dynamic.wat");
+    println!("cargo:rerun-if-changed=src/func_ref.wat");
+    println!("cargo:rerun-if-changed=src/host_func_ref.wat");
+    println!("cargo:rerun-if-changed=src/runtime_linker.wat");
+    println!("cargo:rerun-if-changed=src/extern_ref.wat");
+    println!("cargo:rerun-if-changed=src/exclusive_access.wat");
+    println!("cargo:rerun-if-changed=src/thread_safe.wat");
+    println!("cargo:rerun-if-changed=src/snapshot.wat");
+    println!("cargo:rerun-if-changed=src/pre_init.wat");
+    println!("cargo:rerun-if-changed=src/combined.wat");
     compile_wasm(include_str!("./src/simple.wat"), "simple");
     compile_wasm(include_str!("./src/memory.wat"), "memory");
     compile_wasm(include_str!("./src/imports.wat"), "imports");
+    compile_wasm_with(
+        &fuel_metering_options,
+        include_str!("./src/fuel.wat"),
+        "fuel",
+    );
+    compile_wasm_with(
+        &epoch_interruption_options,
+        include_str!("./src/epoch.wat"),
+        "epoch",
+    );
+    compile_wasm_with(
+        &call_depth_options,
+        include_str!("./src/call_depth.wat"),
+        "call_depth",
+    );
+    compile_wasm_with(
+        &stack_usage_options,
+        include_str!("./src/stack_usage.wat"),
+        "stack_usage",
+    );
+    compile_wasm_with(
+        &async_imports_options,
+        include_str!("./src/async_imports.wat"),
+        "async_imports",
+    );
+    compile_wasm_with(
+        &coroutine_options,
+        include_str!("./src/coroutine.wat"),
+        "coroutine",
+    );
+    compile_wasm_with(&caller_options, include_str!("./src/caller.wat"), "caller");
+    compile_wasm_with(
+        &bound_imports_options,
+        include_str!("./src/bound_imports.wat"),
+        "bound_imports",
+    );
+    compile_wasm(include_str!("./src/dylink.wat"), "dylink");
+    compile_wasm_with(
+        &dynamic_api_options,
+        include_str!("./src/dynamic.wat"),
+        "dynamic",
+    );
+    compile_wasm_with(
+        &func_refs_options,
+        include_str!("./src/func_ref.wat"),
+        "func_ref",
+    );
+    compile_wasm_with(
+        &func_refs_options,
+        include_str!("./src/host_func_ref.wat"),
+        "host_func_ref",
+    );
+    compile_wasm_with(
+        &runtime_linker_options,
+        include_str!("./src/runtime_linker.wat"),
+        "runtime_linker",
+    );
+    compile_wasm_with(
+        &dynamic_api_options,
+        include_str!("./src/extern_ref.wat"),
+        "extern_ref",
+    );
+    compile_wasm_with(
+        &exclusive_access_options,
+        include_str!("./src/exclusive_access.wat"),
+        "exclusive_access",
+    );
+    compile_wasm_with(
+        &exclusive_call_depth_options,
+        include_str!("./src/call_depth.wat"),
+        "exclusive_call_depth",
+    );
+    compile_wasm_with(
+        &combined_options,
+        include_str!("./src/combined.wat"),
+        "combined",
+    );
+    compile_wasm_with(
+        &thread_safe_options,
+        include_str!("./src/thread_safe.wat"),
+        "thread_safe",
+    );
+    compile_wasm_with(
+        &snapshot_options,
+        include_str!("./src/snapshot.wat"),
+        "snapshot",
+    );
+    compile_wasm_with(
+        &pre_init_options,
+        include_str!("./src/pre_init.wat"),
+        "pre_init",
+    );
+
+    {
+        let lib = wat::parse_str(include_str!("./src/linked_lib.wat")).unwrap();
+        let main = wat::parse_str(include_str!("./src/linked_main.wat")).unwrap();
+
+        let linked_options = options(&|o| {
+            o.generated_macro_name(wasm2rs::rust::Ident::new("linked").unwrap());
+        });
+
+        let mut linker = wasm2rs::Linker::new(&linked_options);
+        linker.module("main", &main).unwrap();
+        linker.module("lib", &lib).unwrap();
+
+        let out_path = std::path::Path::join(out_dir.as_ref(), "linked.rs");
+        let mut output = std::fs::File::create(&out_path).unwrap();
+        if let Err(e) = linker.link(&mut output) {
+            panic!("linking failed: {e:#}");
+        }
+    }
 }
@@ -0,0 +1,85 @@
+include!(concat!(env!("OUT_DIR"), "/async_imports.rs"));
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A future that is pending once before completing, to ensure suspension points are handled.
+struct YieldOnce(bool);
+
+impl Future for YieldOnce {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Env {
+    fetches: std::cell::Cell<u32>,
+}
+
+impl Env {
+    async fn fetch(&self, key: i32) -> Result<i32, wasm2rs_rt::trap::TrapValue> {
+        YieldOnce(false).await;
+        self.fetches.set(self.fetches.get() + 1);
+        Ok(key * 10)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Imports {
+    env: Env,
+}
+
+impl Imports {
+    fn env(&self) -> &Env {
+        &self.env
+    }
+}
+
+wasm2rs_rt::embedder_with_import! {
+    pub mod embedder(Imports)
+}
+
+wasm!(pub mod wasm use super::embedder);
+
+#[cfg(test)]
+fn block_on<F: Future>(future: F) -> F::Output {
+    struct NoopWaker;
+
+    impl std::task::Wake for NoopWaker {
+        fn wake(self: std::sync::Arc<Self>) {}
+    }
+
+    let waker = std::task::Waker::from(std::sync::Arc::new(NoopWaker));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        if let Poll::Ready(result) = future.as_mut().poll(&mut cx) {
+            return result;
+        }
+    }
+}
+
+#[test]
+fn async_calls() {
+    block_on(async {
+        let inst = wasm::Instance::instantiate(Default::default())
+            .await
+            .unwrap();
+
+        assert_eq!(inst.started(), Ok(10));
+        assert_eq!(inst.add(1, 2), Ok(3));
+        assert_eq!(inst.fetch_twice(4).await, Ok(80));
+        assert_eq!(inst.sum(3).await, Ok(60));
+        assert_eq!(inst.embedder().imports().env.fetches.get(), 6);
+    })
+}
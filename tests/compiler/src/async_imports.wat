@@ -0,0 +1,29 @@
+(module
+  (import "env" "fetch" (func $fetch (param i32) (result i32)))
+  (global $started (mut i32) (i32.const 0))
+
+  (func $add (export "add") (param i32 i32) (result i32)
+    (i32.add (local.get 0) (local.get 1)))
+
+  (func $fetch_twice (export "fetch_twice") (param i32) (result i32)
+    (call $add
+      (call $fetch (local.get 0))
+      (call $fetch (local.get 0))))
+
+  ;; Sums the fetched values of n, n - 1, ..., 1
+  (func $sum (export "sum") (param $n i32) (result i32)
+    (if (result i32) (i32.eqz (local.get $n))
+      (then (i32.const 0))
+      (else
+        (call $add
+          (call $fetch (local.get $n))
+          (call $sum (i32.sub (local.get $n) (i32.const 1)))))))
+
+  (func $start
+    (global.set $started (call $fetch (i32.const 1))))
+
+  (func (export "started") (result i32)
+    (global.get $started))
+
+  (start $start)
+)
@@ -0,0 +1,41 @@
+include!(concat!(env!("OUT_DIR"), "/bound_imports.rs"));
+
+/// Bound to the `mul_add` import in the build script.
+pub fn mul_add(a: i32, b: i32, c: i32) -> Result<i32, wasm2rs_rt::trap::TrapValue> {
+    Ok(a * b + c)
+}
+
+#[derive(Debug, Default)]
+pub struct Env;
+
+impl wasm::imports::env for Env {
+    fn offset(&self) -> Result<i32, wasm2rs_rt::trap::TrapValue> {
+        Ok(4)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Imports {
+    env: Env,
+}
+
+// The `math` namespace only has bound imports, so it is not required here
+impl wasm::Imports for Imports {
+    type env = Env;
+
+    fn env(&self) -> &Env {
+        &self.env
+    }
+}
+
+wasm2rs_rt::embedder_with_import! {
+    pub mod embedder(Imports)
+}
+
+wasm!(pub mod wasm use super::embedder);
+
+#[test]
+fn bound_and_dynamic_imports() {
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    assert_eq!(inst.compute(5), Ok(19));
+}
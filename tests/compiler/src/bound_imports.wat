@@ -0,0 +1,7 @@
+(module
+  (import "math" "mul_add" (func $mul_add (param i32 i32 i32) (result i32)))
+  (import "env" "offset" (func $offset (result i32)))
+
+  (func (export "compute") (param i32) (result i32)
+    (call $mul_add (local.get 0) (i32.const 3) (call $offset)))
+)
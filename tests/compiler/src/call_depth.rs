@@ -0,0 +1,40 @@
+include!(concat!(env!("OUT_DIR"), "/call_depth.rs"));
+
+#[derive(Debug, Default)]
+pub struct Imports;
+
+wasm2rs_rt::embedder_with_import! {
+    pub mod embedder(Imports) limits(wasm2rs_rt::embedder::ExecutionLimits)
+}
+
+wasm!(pub mod wasm use super::embedder);
+
+#[test]
+fn limit_is_exact() {
+    use wasm2rs_rt::stack::CallDepthLimit as _;
+    use wasm2rs_rt::trap::TrapCode;
+
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    inst.embedder().set_max_call_depth(100);
+    assert_eq!(inst.recurse(99), Ok(99));
+    assert_eq!(
+        inst.recurse(100).unwrap_err().code(),
+        TrapCode::CallStackExhausted
+    );
+    assert_eq!(inst.embedder().call_depth().current(), 0);
+}
+
+#[test]
+fn infinite_recursion() {
+    use wasm2rs_rt::stack::CallDepthLimit as _;
+    use wasm2rs_rt::trap::TrapCode;
+
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    inst.embedder().set_max_call_depth(500);
+    assert_eq!(
+        inst.forever().unwrap_err().code(),
+        TrapCode::CallStackExhausted
+    );
+    assert_eq!(inst.embedder().call_depth().current(), 0);
+    assert_eq!(inst.recurse(10), Ok(10));
+}
@@ -0,0 +1,13 @@
+(module
+  ;; Calls itself n times, then returns n
+  (func $recurse (export "recurse") (param $n i32) (result i32)
+    (if (result i32) (i32.eqz (local.get $n))
+      (then (i32.const 0))
+      (else
+        (i32.add
+          (call $recurse (i32.sub (local.get $n) (i32.const 1)))
+          (i32.const 1)))))
+
+  (func $forever (export "forever")
+    (call $forever))
+)
@@ -0,0 +1,55 @@
+include!(concat!(env!("OUT_DIR"), "/caller.rs"));
+
+use wasm2rs_rt::embedder::Caller;
+use wasm2rs_rt::trap::TrapValue;
+
+#[derive(Debug, Default)]
+pub struct Env {
+    printed: std::cell::RefCell<Vec<u8>>,
+}
+
+impl Env {
+    fn print(&self, caller: &impl Caller, address: i32, length: i32) -> Result<(), TrapValue> {
+        let memory = caller.memory(0).unwrap();
+        let mut bytes = vec![0u8; length as usize];
+        memory.copy_to_slice(address as u32, &mut bytes).unwrap();
+        self.printed.borrow_mut().extend_from_slice(&bytes);
+        Ok(())
+    }
+
+    fn double_via_guest(&self, caller: &wasm::Instance, value: i32) -> Result<i32, TrapValue> {
+        caller.double(value)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Imports {
+    env: Env,
+}
+
+impl Imports {
+    fn env(&self) -> &Env {
+        &self.env
+    }
+}
+
+wasm2rs_rt::embedder_with_import! {
+    pub mod embedder(Imports)
+}
+
+wasm!(pub mod wasm use super::embedder);
+
+#[test]
+fn import_reads_caller_memory() {
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    assert_eq!(inst.greet(), Ok(()));
+    assert_eq!(*inst.embedder().imports().env.printed.borrow(), b"hello");
+    assert!(Caller::memory(&inst, 1).is_none());
+}
+
+#[test]
+fn import_calls_back_into_caller() {
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    assert_eq!(inst.quadruple(3), Ok(12));
+    assert_eq!(inst.counter().get(), 2);
+}
@@ -0,0 +1,19 @@
+(module
+  (import "env" "print" (func $print (param i32 i32)))
+  (import "env" "double_via_guest" (func $double_via_guest (param i32) (result i32)))
+
+  (memory (export "memory") 1)
+  (global $counter (export "counter") (mut i32) (i32.const 0))
+
+  (data (i32.const 16) "hello")
+
+  (func (export "greet")
+    (call $print (i32.const 16) (i32.const 5)))
+
+  (func (export "double") (param i32) (result i32)
+    (global.set $counter (i32.add (global.get $counter) (i32.const 1)))
+    (i32.mul (local.get 0) (i32.const 2)))
+
+  (func (export "quadruple") (param i32) (result i32)
+    (call $double_via_guest (call $double_via_guest (local.get 0))))
+)
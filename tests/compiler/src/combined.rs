@@ -0,0 +1,45 @@
+include!(concat!(env!("OUT_DIR"), "/combined.rs"));
+
+#[derive(Debug, Default)]
+pub struct Imports;
+
+wasm2rs_rt::embedder_with_import! {
+    pub mod embedder(Imports) limits(wasm2rs_rt::embedder::ExecutionLimits)
+}
+
+wasm!(pub mod wasm use super::embedder);
+
+#[test]
+fn all_limits_satisfied() {
+    let mut inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    inst.embedder().set_fuel(10_000);
+    assert_eq!(inst.fill(10), Ok(45));
+    assert_eq!(inst.sum(10), Ok(165));
+    assert_eq!(*inst.calls(), 11);
+    assert!(inst.embedder().remaining_fuel() < 10_000);
+}
+
+#[test]
+fn limits_are_enforced() {
+    use wasm2rs_rt::epoch::EpochCounter;
+    use wasm2rs_rt::stack::CallDepthLimit as _;
+    use wasm2rs_rt::trap::TrapCode;
+
+    static EPOCH: EpochCounter = EpochCounter::new();
+
+    let mut inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    assert_eq!(inst.fill(10).unwrap_err().code(), TrapCode::OutOfFuel);
+
+    inst.embedder().set_fuel(10_000);
+    inst.embedder().set_max_call_depth(5);
+    assert_eq!(
+        inst.sum(10).unwrap_err().code(),
+        TrapCode::CallStackExhausted
+    );
+    assert_eq!(inst.embedder().call_depth().current(), 0);
+
+    inst.embedder().set_epoch_counter(&EPOCH);
+    inst.embedder().set_epoch_deadline(1);
+    EPOCH.increment();
+    assert_eq!(inst.sum(1).unwrap_err().code(), TrapCode::Interrupted);
+}
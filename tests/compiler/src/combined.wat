@@ -0,0 +1,27 @@
+(module
+  (memory (export "mem") 1)
+  (global $calls (export "calls") (mut i32) (i32.const 0))
+
+  ;; Stores the running totals of 0 + 1 + ... + (n - 1), then returns the last one
+  (func $fill (export "fill") (param $n i32) (result i32)
+    (local $i i32)
+    (local $total i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.eq (local.get $i) (local.get $n)))
+        (local.set $total (i32.add (local.get $total) (local.get $i)))
+        (i32.store (i32.shl (local.get $i) (i32.const 2)) (local.get $total))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (local.get $total))
+
+  ;; Recursively adds up the first n values stored by $fill
+  (func $sum (export "sum") (param $n i32) (result i32)
+    (global.set $calls (i32.add (global.get $calls) (i32.const 1)))
+    (if (result i32) (i32.eqz (local.get $n))
+      (then (i32.const 0))
+      (else
+        (i32.add
+          (i32.load (i32.shl (i32.sub (local.get $n) (i32.const 1)) (i32.const 2)))
+          (call $sum (i32.sub (local.get $n) (i32.const 1)))))))
+)
@@ -0,0 +1,75 @@
+include!(concat!(env!("OUT_DIR"), "/coroutine.rs"));
+
+use wasm2rs_rt::coroutine::Suspend;
+
+#[derive(Debug, Default)]
+pub struct Host {
+    suspend: Suspend<i32, i32>,
+}
+
+impl Host {
+    async fn next(&self, total: i32) -> Result<i32, wasm2rs_rt::trap::TrapValue> {
+        Ok(self.suspend.suspend(total).await)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Imports {
+    host: Host,
+}
+
+impl Imports {
+    fn host(&self) -> &Host {
+        &self.host
+    }
+}
+
+wasm2rs_rt::embedder_with_import! {
+    pub mod embedder(Imports)
+}
+
+wasm!(pub mod wasm use super::embedder);
+
+#[test]
+fn suspend_and_resume() {
+    use std::task::Poll;
+    use wasm2rs_rt::coroutine::resume;
+
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    let suspend = &inst.embedder().imports().host.suspend;
+    let mut call = Box::pin(inst.accumulate(1));
+
+    let mut totals = Vec::new();
+    let mut inputs = [5, 10, 0].into_iter();
+
+    let result = loop {
+        match resume(call.as_mut()) {
+            Poll::Ready(result) => break result,
+            Poll::Pending => {
+                totals.push(suspend.take_yielded().unwrap());
+                suspend.resume_with(inputs.next().unwrap());
+            }
+        }
+    };
+
+    assert_eq!(result, Ok(16));
+    assert_eq!(totals, [1, 6, 16]);
+    assert!(!suspend.is_suspended());
+}
+
+#[test]
+fn spurious_resume_stays_suspended() {
+    use std::task::Poll;
+    use wasm2rs_rt::coroutine::resume;
+
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    let suspend = &inst.embedder().imports().host.suspend;
+    let mut call = Box::pin(inst.accumulate(2));
+
+    assert!(resume(call.as_mut()).is_pending());
+    assert!(suspend.is_suspended());
+    assert!(resume(call.as_mut()).is_pending());
+    assert_eq!(suspend.take_yielded(), Some(2));
+    suspend.resume_with(0);
+    assert_eq!(resume(call.as_mut()), Poll::Ready(Ok(2)));
+}
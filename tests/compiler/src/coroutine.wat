@@ -0,0 +1,15 @@
+(module
+  (import "host" "next" (func $next (param i32) (result i32)))
+
+  ;; Repeatedly passes a running total to the host, adding the values it returns until it
+  ;; returns zero.
+  (func (export "accumulate") (param $total i32) (result i32)
+    (local $value i32)
+    (loop $continue
+      (local.set $value (call $next (local.get $total)))
+      (if (local.get $value)
+        (then
+          (local.set $total (i32.add (local.get $total) (local.get $value)))
+          (br $continue))))
+    (local.get $total))
+)
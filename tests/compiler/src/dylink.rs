@@ -0,0 +1,119 @@
+include!(concat!(env!("OUT_DIR"), "/dylink.rs"));
+
+#[derive(Debug)]
+pub struct Env {
+    memory: std::rc::Rc<wasm2rs_rt::memory::HeapMemory32>,
+}
+
+impl side::imports::env for Env {
+    fn memory(&self) -> &wasm2rs_rt::memory::HeapMemory32 {
+        &self.memory
+    }
+}
+
+#[derive(Debug)]
+pub struct Imports {
+    env: Env,
+}
+
+impl side::Imports for Imports {
+    type env = Env;
+
+    fn env(&self) -> &Env {
+        &self.env
+    }
+}
+
+wasm2rs_rt::embedder_with_import! {
+    pub mod embedder(Imports)
+}
+
+wasm!(pub mod side use super::embedder);
+
+#[cfg(test)]
+struct Loaded;
+
+#[cfg(test)]
+impl wasm2rs_rt::dylink::Symbols for Loaded {
+    fn memory_address(&self, name: &str) -> Option<u32> {
+        (name == "errno").then_some(4)
+    }
+
+    fn table_index(&self, name: &str) -> Option<u32> {
+        (name == "callback").then_some(7)
+    }
+}
+
+#[test]
+fn mem_info() {
+    assert_eq!(side::DYLINK_MEM_INFO.memory_size, 8);
+    assert_eq!(side::DYLINK_MEM_INFO.memory_alignment, 2);
+    assert_eq!(side::DYLINK_MEM_INFO.table_size, 1);
+    assert_eq!(side::DYLINK_MEM_INFO.table_alignment, 0);
+    assert_eq!(side::DYLINK_NEEDED, &["libc.so"]);
+}
+
+#[test]
+fn shared_memory() {
+    use wasm2rs_rt::dylink::Linking;
+    use wasm2rs_rt::memory::Memory32;
+
+    let memory = std::rc::Rc::new(wasm2rs_rt::memory::HeapMemory32::with_limits(1, 1).unwrap());
+    let instantiate = |memory_base, table_base| {
+        let env = Env {
+            memory: memory.clone(),
+        };
+
+        let linking = Linking {
+            memory_base: side::DYLINK_MEM_INFO.align_memory_base(memory_base),
+            table_base: side::DYLINK_MEM_INFO.align_table_base(table_base),
+            symbols: &Loaded,
+        };
+
+        side::Instance::instantiate(embedder::State::new(Imports { env }), linking).unwrap()
+    };
+
+    let first = instantiate(16, 1);
+    let second = instantiate(first.greeting_address().unwrap() as u32 + 9, 2);
+
+    assert_eq!(first.greeting_address(), Ok(16));
+    assert_eq!(second.greeting_address(), Ok(28));
+    assert_eq!(second.table_base(), Ok(2));
+    assert_eq!(second.callback_index(), Ok(7));
+
+    let mut greetings = [0u8; 20];
+    memory.copy_to_slice(16, &mut greetings).unwrap();
+    assert_eq!(&greetings, b"greeting\0\0\0\0greeting");
+
+    first.set_errno(42).unwrap();
+    assert_eq!(memory.i32_load::<2>(4).unwrap(), 42);
+}
+
+#[test]
+fn unresolved_symbol() {
+    let env = Env {
+        memory: std::rc::Rc::new(wasm2rs_rt::memory::HeapMemory32::with_limits(1, 1).unwrap()),
+    };
+
+    let linking = wasm2rs_rt::dylink::Linking {
+        memory_base: 0,
+        table_base: 0,
+        symbols: &(),
+    };
+
+    let error =
+        side::Instance::instantiate(embedder::State::new(Imports { env }), linking).unwrap_err();
+
+    let wasm2rs_rt::linker::InstantiationError::Link(error) = error else {
+        panic!("expected link error, but got {error}");
+    };
+
+    assert_eq!(
+        error,
+        wasm2rs_rt::linker::LinkError::new(
+            "GOT.mem",
+            "errno",
+            wasm2rs_rt::linker::LinkErrorKind::Undefined
+        )
+    );
+}
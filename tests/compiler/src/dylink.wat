@@ -0,0 +1,27 @@
+(module
+  (@dylink.0
+    (mem-info (memory 8 2) (table 1 0))
+    (needed "libc.so"))
+
+  (import "env" "memory" (memory 1))
+  (import "env" "__memory_base" (global $memory_base i32))
+  (import "env" "__table_base" (global $table_base i32))
+  (import "GOT.mem" "errno" (global $errno (mut i32)))
+  (import "GOT.func" "callback" (global $callback (mut i32)))
+
+  (data (global.get $memory_base) "greeting")
+
+  (func (export "greeting_address") (result i32)
+    global.get $memory_base)
+
+  (func (export "table_base") (result i32)
+    global.get $table_base)
+
+  (func (export "set_errno") (param i32)
+    global.get $errno
+    local.get 0
+    i32.store)
+
+  (func (export "callback_index") (result i32)
+    global.get $callback)
+)
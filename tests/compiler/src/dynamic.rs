@@ -0,0 +1,94 @@
+include!(concat!(env!("OUT_DIR"), "/dynamic.rs"));
+
+wasm!(pub mod dynamic);
+
+#[test]
+fn exports_are_listed() {
+    use wasm2rs_rt::dynamic::ExportKind;
+    use wasm2rs_rt::stack::trace::{WasmSymbolSignature, WasmValType};
+
+    let exports = dynamic::Instance::exports();
+    let names = exports.iter().map(|export| export.name).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "memory",
+            "counter",
+            "SCALE",
+            "add",
+            "swap",
+            "increment",
+            "fail"
+        ]
+    );
+
+    assert_eq!(exports[0].kind, ExportKind::Memory);
+    assert_eq!(
+        exports[1].kind,
+        ExportKind::Global {
+            ty: WasmValType::I64,
+            mutable: true
+        }
+    );
+    assert_eq!(
+        exports[4].kind,
+        ExportKind::Function(&WasmSymbolSignature {
+            parameters: &[WasmValType::F32, WasmValType::F64],
+            results: &[WasmValType::F64, WasmValType::F32],
+        })
+    );
+}
+
+#[test]
+fn call_by_name() {
+    use wasm2rs_rt::dynamic::Val;
+
+    let inst = dynamic::Instance::instantiate(Default::default()).unwrap();
+
+    let results = inst
+        .call_dynamic("add", &[Val::I32(2), Val::I32(3)])
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].i32(), Some(5));
+
+    let results = inst
+        .call_dynamic("swap", &[Val::F32(1.5), Val::F64(2.5)])
+        .unwrap();
+    assert_eq!(results[0].f64(), Some(2.5));
+    assert_eq!(results[1].f32(), Some(1.5));
+
+    assert!(inst.call_dynamic("increment", &[]).unwrap().is_empty());
+    assert_eq!(inst.global_dynamic("counter").unwrap().i64(), Some(11));
+    assert_eq!(inst.global_dynamic("SCALE").unwrap().f64(), Some(2.5));
+    assert!(inst.global_dynamic("add").is_none());
+}
+
+#[test]
+fn call_errors() {
+    use wasm2rs_rt::dynamic::{CallError, Val};
+
+    let inst = dynamic::Instance::instantiate(Default::default()).unwrap();
+
+    assert!(matches!(
+        inst.call_dynamic("missing", &[]),
+        Err(CallError::NotFound)
+    ));
+    assert!(matches!(
+        inst.call_dynamic("memory", &[]),
+        Err(CallError::NotAFunction)
+    ));
+    assert!(matches!(
+        inst.call_dynamic("add", &[Val::I32(1), Val::I64(2)]),
+        Err(CallError::ArgumentMismatch { .. })
+    ));
+    assert!(matches!(
+        inst.call_dynamic("add", &[Val::I32(1)]),
+        Err(CallError::ArgumentMismatch { .. })
+    ));
+
+    let Err(CallError::Trap(trap)) = inst.call_dynamic("fail", &[]) else {
+        panic!("expected trap");
+    };
+
+    assert_eq!(trap.code(), &wasm2rs_rt::trap::TrapCode::Unreachable);
+}
@@ -0,0 +1,23 @@
+(module
+  (memory (export "memory") 1)
+  (global $counter (export "counter") (mut i64) (i64.const 10))
+  (global (export "SCALE") f64 (f64.const 2.5))
+
+  (func (export "add") (param i32 i32) (result i32)
+    local.get 0
+    local.get 1
+    i32.add)
+
+  (func (export "swap") (param f32 f64) (result f64 f32)
+    local.get 1
+    local.get 0)
+
+  (func (export "increment")
+    global.get $counter
+    i64.const 1
+    i64.add
+    global.set $counter)
+
+  (func (export "fail") (result i32)
+    unreachable)
+)
@@ -0,0 +1,58 @@
+include!(concat!(env!("OUT_DIR"), "/epoch.rs"));
+
+#[derive(Debug, Default)]
+pub struct Imports;
+
+wasm2rs_rt::embedder_with_import! {
+    pub mod embedder(Imports) limits(wasm2rs_rt::embedder::ExecutionLimits)
+}
+
+wasm!(pub mod wasm use super::embedder);
+
+#[cfg(test)]
+use wasm2rs_rt::epoch::EpochCounter;
+#[cfg(test)]
+use wasm2rs_rt::trap::TrapCode;
+
+#[test]
+fn no_deadline() {
+    static EPOCH: EpochCounter = EpochCounter::new();
+
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    inst.embedder().set_epoch_counter(&EPOCH);
+    EPOCH.increment();
+    assert_eq!(inst.answer(), Ok(42));
+}
+
+#[test]
+fn deadline_reached_on_entry() {
+    static EPOCH: EpochCounter = EpochCounter::new();
+
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    inst.embedder().set_epoch_counter(&EPOCH);
+    inst.embedder().set_epoch_deadline(1);
+    assert_eq!(inst.answer(), Ok(42));
+
+    EPOCH.increment();
+    assert_eq!(inst.answer().unwrap_err().code(), TrapCode::Interrupted);
+
+    inst.embedder().set_epoch_deadline(1);
+    assert_eq!(inst.answer(), Ok(42));
+}
+
+#[test]
+fn watchdog_interrupts_loop() {
+    static EPOCH: EpochCounter = EpochCounter::new();
+
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    inst.embedder().set_epoch_counter(&EPOCH);
+    inst.embedder().set_epoch_deadline(1);
+
+    let watchdog = std::thread::spawn(|| {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        EPOCH.increment();
+    });
+
+    assert_eq!(inst.spin().unwrap_err().code(), TrapCode::Interrupted);
+    watchdog.join().unwrap();
+}
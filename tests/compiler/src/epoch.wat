@@ -0,0 +1,8 @@
+(module
+  (func (export "answer") (result i32)
+    i32.const 42)
+
+  (func (export "spin")
+    (loop $forever
+      (br $forever)))
+)
@@ -0,0 +1,51 @@
+include!(concat!(env!("OUT_DIR"), "/exclusive_access.rs"));
+
+wasm!(pub mod wasm);
+
+#[test]
+fn mutable_accesses() {
+    let mut inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    assert_eq!(inst.sum_bytes(16, 4), Ok(10));
+    assert_eq!(*inst.counter(), 1);
+
+    inst.write_i64(32, -2).unwrap();
+    assert_eq!(inst.read_i64(32), Ok(-2));
+    assert_eq!(inst.sum_bytes(40, 8), Ok(0xFE + 0xFF * 7));
+    assert_eq!(*inst.counter(), 2);
+}
+
+#[test]
+fn bounds_checking() {
+    let mut inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    assert_eq!(inst.read_last_int(), Ok(0));
+    assert!(inst.read_i64(65528).is_err());
+    assert!(inst.sum_bytes(65535, 2).is_err());
+    assert!(inst.write_i64(-8, 1).is_err());
+}
+
+#[test]
+fn growing() {
+    let mut inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    assert_eq!(inst.grow_then_write(), Ok(2.5f32.to_bits() as i32));
+    assert_eq!(inst.read_i64(65536 + 8), Ok(i64::from(2.5f32.to_bits())));
+}
+
+#[test]
+fn in_bounds_accesses() {
+    let mut inst = wasm::Instance::instantiate(Default::default()).unwrap();
+
+    // `read_last_int` loads from a constant address, so its bounds check is eliminated.
+    inst.write_i64(65520, 0x1234_5678_0000_0000).unwrap();
+    assert_eq!(inst.read_last_int(), Ok(0x1234_5678));
+}
+
+#[test]
+fn no_interior_mutability() {
+    // Exported functions require exclusive access, and mutable globals are plain fields.
+    let sum_bytes: fn(&mut wasm::Instance, i32, i32) -> _ = wasm::Instance::sum_bytes;
+    let mut inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    assert_eq!(sum_bytes(&mut inst, 16, 4), Ok(10));
+
+    let counter: &i32 = inst.counter();
+    assert_eq!(*counter, 1);
+}
@@ -0,0 +1,39 @@
+(module
+  (memory (export "mem") 1)
+  (global $counter (export "counter") (mut i32) (i32.const 0))
+
+  (data (memory 0) (offset i32.const 16) "\01\02\03\04")
+
+  (func $bump (result i32)
+    (global.set $counter (i32.add (global.get $counter) (i32.const 1)))
+    (global.get $counter))
+
+  (func (export "sum_bytes") (param $start i32) (param $len i32) (result i32)
+    (local $sum i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.eqz (local.get $len)))
+        (local.set $sum (i32.add (local.get $sum) (i32.load8_u (local.get $start))))
+        (local.set $start (i32.add (local.get $start) (i32.const 1)))
+        (local.set $len (i32.sub (local.get $len) (i32.const 1)))
+        (br $next)))
+    (drop (call $bump))
+    (local.get $sum))
+
+  (func (export "write_i64") (param i32 i64)
+    (i64.store offset=8 (local.get 0) (local.get 1)))
+
+  (func (export "read_i64") (param i32) (result i64)
+    (i64.load offset=8 (local.get 0)))
+
+  (func (export "read_last_int") (result i32)
+    i32.const 65528
+    i32.load offset=4)
+
+  (func (export "grow_then_write") (result i32)
+    (local $address i32)
+    (local.set $address (i32.add (i32.mul (memory.size) (i32.const 65536)) (i32.const 16)))
+    (drop (memory.grow (i32.const 1)))
+    (f32.store (local.get $address) (f32.const 2.5))
+    (i32.load (local.get $address)))
+)
@@ -0,0 +1,124 @@
+include!(concat!(env!("OUT_DIR"), "/extern_ref.rs"));
+
+use wasm2rs_rt::extern_ref::ExternRef;
+
+#[derive(Debug, Default)]
+pub struct Env;
+
+impl wasm::imports::env for Env {
+    fn name_length(&self, name: ExternRef) -> Result<i32, wasm2rs_rt::trap::TrapValue> {
+        Ok(name
+            .downcast_ref::<String>()
+            .map_or(-1, |name| name.len() as i32))
+    }
+
+    fn make_name(&self, length: i32) -> Result<ExternRef, wasm2rs_rt::trap::TrapValue> {
+        Ok(ExternRef::new("x".repeat(length as usize)))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Imports {
+    env: Env,
+}
+
+impl wasm::Imports for Imports {
+    type env = Env;
+
+    fn env(&self) -> &Env {
+        &self.env
+    }
+}
+
+wasm2rs_rt::embedder_with_import! {
+    pub mod embedder(Imports)
+}
+
+wasm!(pub mod wasm use super::embedder);
+
+#[test]
+fn host_objects() {
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+
+    let name = ExternRef::new(String::from("hello"));
+    assert_eq!(inst.roundtrip(name.clone()), Ok(name.clone()));
+    assert_eq!(inst.is_null(name.clone()), Ok(0));
+    assert_eq!(inst.total_length(name), Ok(10));
+    assert_eq!(inst.total_length(ExternRef::new(5u8)), Ok(-2));
+    assert_eq!(inst.made_name_length(3), Ok(3));
+}
+
+#[test]
+fn null_and_handles() {
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+
+    assert_eq!(inst.null(), Ok(ExternRef::NULL));
+    assert_eq!(inst.is_null(ExternRef::NULL), Ok(1));
+    assert_eq!(inst.is_null(ExternRef::from_handle(0)), Ok(0));
+    assert_eq!(
+        inst.roundtrip(ExternRef::from_handle(7)).unwrap().handle(),
+        Some(7)
+    );
+}
+
+#[test]
+fn dynamic_calls() {
+    use wasm2rs_rt::dynamic::Val;
+
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+
+    let name = ExternRef::new(String::from("abc"));
+    let results = inst
+        .call_dynamic("roundtrip", &[Val::from(name.clone())])
+        .unwrap();
+    assert_eq!(results[0].extern_ref(), Some(&name));
+
+    let results = inst.call_dynamic("null", &[]).unwrap();
+    assert_eq!(results[0].extern_ref(), Some(&ExternRef::NULL));
+
+    // An `externref` parameter does not accept other values
+    assert!(matches!(
+        inst.call_dynamic("is_null", &[Val::I32(0)]),
+        Err(wasm2rs_rt::dynamic::CallError::ArgumentMismatch { .. })
+    ));
+
+    let results = inst
+        .call_dynamic("is_null", &[Val::from(ExternRef::NULL)])
+        .unwrap();
+    assert_eq!(results[0].i32(), Some(1));
+}
+
+#[test]
+fn globals() {
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+
+    let first = ExternRef::new(String::from("first"));
+    let second = ExternRef::from_handle(2);
+    assert_eq!(inst.swap_last(first.clone()), Ok(ExternRef::NULL));
+    assert_eq!(inst.swap_last(second.clone()), Ok(first));
+    assert_eq!(inst.last().get_with(Clone::clone), second);
+    assert_eq!(inst.empty(), Ok(ExternRef::NULL));
+    assert_eq!(inst.empty_global(), &ExternRef::NULL);
+
+    let last = inst.global_dynamic("last").unwrap();
+    assert_eq!(last.extern_ref(), Some(&second));
+    let empty = inst.global_dynamic("empty_global").unwrap();
+    assert_eq!(empty.extern_ref(), Some(&ExternRef::NULL));
+}
+
+#[test]
+fn select() {
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+
+    let a = ExternRef::new(1u32);
+    let b = ExternRef::from_handle(1);
+    assert_eq!(inst.choose(a.clone(), b.clone(), 1), Ok(a.clone()));
+    assert_eq!(inst.choose(a, b.clone(), 0), Ok(b));
+}
+
+#[test]
+fn send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<ExternRef>();
+}
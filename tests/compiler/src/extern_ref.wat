@@ -0,0 +1,48 @@
+(module
+  (import "env" "name_length" (func $name_length (param externref) (result i32)))
+  (import "env" "make_name" (func $make_name (param i32) (result externref)))
+
+  (func (export "roundtrip") (param externref) (result externref)
+    local.get 0)
+
+  (func (export "is_null") (param externref) (result i32)
+    local.get 0
+    ref.is_null)
+
+  (func (export "null") (result externref)
+    ref.null extern)
+
+  (func (export "total_length") (param $name externref) (result i32)
+    local.get $name
+    call $name_length
+    local.get $name
+    call $name_length
+    i32.add)
+
+  (func (export "made_name_length") (param i32) (result i32)
+    (local $name externref)
+    local.get 0
+    call $make_name
+    local.tee $name
+    call $name_length
+    drop
+    local.get $name
+    call $name_length)
+
+  (global $last (export "last") (mut externref) (ref.null extern))
+  (global $empty (export "empty_global") externref (ref.null extern))
+
+  (func (export "swap_last") (param externref) (result externref)
+    global.get $last
+    local.get 0
+    global.set $last)
+
+  (func (export "empty") (result externref)
+    global.get $empty)
+
+  (func (export "choose") (param externref externref i32) (result externref)
+    local.get 0
+    local.get 1
+    local.get 2
+    select (result externref))
+)
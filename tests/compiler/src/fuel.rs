@@ -0,0 +1,42 @@
+include!(concat!(env!("OUT_DIR"), "/fuel.rs"));
+
+#[derive(Debug, Default)]
+pub struct Imports;
+
+wasm2rs_rt::embedder_with_import! {
+    pub mod embedder(Imports) limits(wasm2rs_rt::embedder::ExecutionLimits)
+}
+
+wasm!(pub mod wasm use super::embedder);
+
+#[test]
+fn fuel_is_consumed() {
+    let state = embedder::State::default();
+    state.set_fuel(10);
+
+    let inst = wasm::Instance::instantiate(state).unwrap();
+    assert_eq!(inst.add(1, 2), Ok(3));
+    assert_eq!(inst.embedder().remaining_fuel(), 6);
+}
+
+#[test]
+fn out_of_fuel() {
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    let error = inst.add(1, 2).unwrap_err();
+    assert_eq!(error.code(), wasm2rs_rt::trap::TrapCode::OutOfFuel);
+}
+
+#[test]
+fn loops_are_metered() {
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    inst.embedder().set_fuel(1000);
+    assert!(inst.spin().is_err());
+    assert_eq!(inst.embedder().remaining_fuel(), 0);
+
+    inst.embedder().add_fuel(100);
+    assert!(inst.sum(100).is_err());
+
+    inst.embedder().set_fuel(10_000);
+    assert_eq!(inst.sum(100), Ok(5050));
+    assert!(inst.embedder().remaining_fuel() < 10_000);
+}
@@ -0,0 +1,21 @@
+(module
+  (func (export "add") (param i32 i32) (result i32)
+    local.get 0
+    local.get 1
+    i32.add)
+
+  ;; Sums the integers from 1 to n
+  (func (export "sum") (param $n i32) (result i32)
+    (local $sum i32)
+    (block $exit
+      (loop $continue
+        (br_if $exit (i32.eqz (local.get $n)))
+        (local.set $sum (i32.add (local.get $sum) (local.get $n)))
+        (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+        (br $continue)))
+    local.get $sum)
+
+  (func (export "spin")
+    (loop $forever
+      (br $forever)))
+)
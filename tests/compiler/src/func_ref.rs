@@ -0,0 +1,30 @@
+include!(concat!(env!("OUT_DIR"), "/func_ref.rs"));
+
+wasm!(pub mod func_ref);
+
+#[test]
+fn exported_func_refs() {
+    let inst = func_ref::Instance::instantiate(Default::default()).unwrap();
+
+    let add = inst.func_ref("add").unwrap();
+    assert_eq!(add.call_2(2i32, 3i32, inst.embedder()), Ok(5i32));
+
+    let swap = inst.func_ref("swap").unwrap().clone();
+    assert_eq!(
+        swap.call_2(1.5f32, 2.5f64, inst.embedder()),
+        Ok((2.5f64, 1.5f32))
+    );
+
+    let increment = inst.func_ref("increment").unwrap();
+    assert_eq!(increment.call_0(inst.embedder()), Ok(()));
+    assert_eq!(inst.counter().get(), 11);
+
+    let trap = add.call_1::<i32, i32, _>(1, inst.embedder()).unwrap_err();
+    assert!(matches!(
+        trap.code(),
+        wasm2rs_rt::trap::TrapCode::IndirectCallSignatureMismatch(_)
+    ));
+
+    assert!(inst.func_ref("counter").is_none());
+    assert!(inst.func_ref("missing").is_none());
+}
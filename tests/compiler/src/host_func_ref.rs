@@ -0,0 +1,88 @@
+include!(concat!(env!("OUT_DIR"), "/host_func_ref.rs"));
+
+use wasm2rs_rt::func_ref::FuncRef;
+use wasm2rs_rt::trap::TrapValue;
+
+#[derive(Debug, Default)]
+pub struct Env {
+    trap: wasm2rs_rt::embedder::State,
+}
+
+impl wasm::imports::env for Env {
+    fn apply(&self, f: FuncRef<'_, TrapValue>, x: i32) -> Result<i32, TrapValue> {
+        f.call_1(x, &self.trap)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Imports {
+    env: Env,
+}
+
+impl wasm::Imports for Imports {
+    type env = Env;
+
+    fn env(&self) -> &Env {
+        &self.env
+    }
+}
+
+wasm2rs_rt::embedder_with_import! {
+    pub mod embedder(Imports)
+}
+
+wasm!(pub mod wasm use super::embedder);
+
+#[test]
+fn host_closures() {
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+
+    let calls = std::cell::Cell::new(0);
+    let double = FuncRef::<TrapValue>::from_closure_1(|x: i32| {
+        calls.set(calls.get() + 1);
+        Ok(x * 2)
+    });
+
+    assert_eq!(inst.apply_twice(double.clone(), 3), Ok(12));
+    assert_eq!(calls.get(), 2);
+
+    let negate = FuncRef::<TrapValue>::from_closure_1(|x: i32| Ok(-x));
+    assert_eq!(
+        inst.apply_either(double.clone(), negate.clone(), 5, 1),
+        Ok(10)
+    );
+    assert_eq!(inst.apply_either(double, negate, 5, 0), Ok(-5));
+    assert_eq!(calls.get(), 3);
+
+    // Functions with `funcref` parameters can't be called through a `FuncRef`
+    assert!(inst.func_ref("apply_twice").is_none());
+    assert!(inst.func_ref("apply_null").is_some());
+}
+
+#[test]
+fn null_func_refs() {
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+
+    assert_eq!(inst.apply_or(FuncRef::NULL, 1, 42), Ok(42));
+
+    let increment = FuncRef::<TrapValue>::from_closure_1(|x: i32| Ok(x + 1));
+    assert_eq!(inst.apply_or(increment, 1, 42), Ok(2));
+
+    let trap = inst.apply_null(1).unwrap_err();
+    assert!(matches!(
+        trap.code(),
+        wasm2rs_rt::trap::TrapCode::NullFunctionReference { .. }
+    ));
+}
+
+#[test]
+fn signature_mismatch() {
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+
+    let wrong = FuncRef::<TrapValue>::from_closure_1(|x: i64| Ok(x));
+    let trap = inst.apply_twice(wrong, 1).unwrap_err();
+    assert!(matches!(
+        trap.code(),
+        wasm2rs_rt::trap::TrapCode::IndirectCallSignatureMismatch(_)
+    ));
+}
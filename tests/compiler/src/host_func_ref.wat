@@ -0,0 +1,24 @@
+(module
+  (import "env" "apply" (func $apply (param funcref i32) (result i32)))
+
+  (func (export "apply_twice") (param $f funcref) (param $x i32) (result i32)
+    local.get $f
+    local.get $f
+    local.get $x
+    call $apply
+    call $apply)
+
+  (func (export "apply_or") (param $f funcref) (param $x i32) (param $default i32) (result i32)
+    (if (result i32) (ref.is_null (local.get $f))
+      (then (local.get $default))
+      (else (call $apply (local.get $f) (local.get $x)))))
+
+  (func (export "apply_either") (param $f funcref) (param $g funcref) (param $x i32) (param $first i32) (result i32)
+    (local $chosen funcref)
+    (local.set $chosen
+      (select (result funcref) (local.get $f) (local.get $g) (local.get $first)))
+    (call $apply (local.get $chosen) (local.get $x)))
+
+  (func (export "apply_null") (param $x i32) (result i32)
+    (call $apply (ref.null func) (local.get $x)))
+)
@@ -38,6 +38,47 @@ wasm2rs_rt::embedder_with_import! {
 
 wasm!(pub mod imports_example use super::example_embedder);
 
+/// Provides the same imports as [`TestImports`], but through the generated import traits.
+#[derive(Debug)]
+pub struct TraitTestImports {
+    memory: wasm2rs_rt::memory::HeapMemory32,
+}
+
+#[allow(non_snake_case)]
+impl trait_imports_example::imports::tests for TraitTestImports {
+    fn memory(&self) -> &wasm2rs_rt::memory::HeapMemory32 {
+        &self.memory
+    }
+
+    fn FORTY(&self) -> &i32 {
+        &40
+    }
+
+    fn assert_equal(&self, a: i32, b: i32) -> Result<(), wasm2rs_rt::trap::TrapValue> {
+        assert_eq!(a, b, "WASM callee messed up");
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct TraitImports {
+    test_imports: TraitTestImports,
+}
+
+impl trait_imports_example::Imports for TraitImports {
+    type tests = TraitTestImports;
+
+    fn tests(&self) -> &TraitTestImports {
+        &self.test_imports
+    }
+}
+
+wasm2rs_rt::embedder_with_import! {
+    pub mod trait_embedder(TraitImports)
+}
+
+wasm!(pub mod trait_imports_example use super::trait_embedder);
+
 #[test]
 fn imports() {
     use wasm2rs_rt::memory::Memory32;
@@ -65,6 +106,40 @@ fn imports() {
     );
 }
 
+#[test]
+fn imports_through_traits() {
+    use wasm2rs_rt::memory::Memory32;
+
+    fn forty(imports: &impl trait_imports_example::Imports) -> i32 {
+        use trait_imports_example::imports::tests as _;
+
+        *imports.tests().FORTY()
+    }
+
+    let imports = TraitImports {
+        test_imports: TraitTestImports {
+            memory: wasm2rs_rt::memory::HeapMemory32::with_limits(1, 2).unwrap(),
+        },
+    };
+    assert_eq!(forty(&imports), 40);
+
+    let inst =
+        trait_imports_example::Instance::instantiate(trait_embedder::State::new(imports)).unwrap();
+
+    assert_eq!(inst.funny_life_number(), Ok(42));
+    assert_eq!(inst.two_equals_two(), Ok(()));
+
+    inst.write_5_to_5000().unwrap();
+    assert_eq!(
+        inst.embedder()
+            .imports()
+            .test_imports
+            .memory
+            .i32_load::<0>(5000),
+        Ok(5)
+    );
+}
+
 #[test]
 fn memory_import_limits_are_checked() {
     // This has the wrong minimum size.
@@ -74,8 +149,15 @@ fn memory_import_limits_are_checked() {
         test_imports: TestImports { memory },
     }));
 
-    assert!(
-        matches!(&result, Err(e) if e.code() == wasm2rs_rt::trap::TrapCode::MemoryLimitsCheck { memory: 0, limits: wasm2rs_rt::trap::LimitsCheck::Minimum { expected: 1, actual: 0 } }),
-        "expected instantiation to fail, got {result:?}",
-    )
+    let Err(wasm2rs_rt::linker::InstantiationError::Link(error)) = result else {
+        panic!("expected instantiation to fail, got {result:?}");
+    };
+
+    assert_eq!(
+        error.kind(),
+        &wasm2rs_rt::linker::LinkErrorKind::MemoryLimits(wasm2rs_rt::trap::LimitsCheck::Minimum {
+            expected: 1,
+            actual: 0
+        })
+    );
 }
@@ -1,5 +1,26 @@
 //! Test for using `wasm2rs` as a build dependency
 
+pub mod async_imports;
+pub mod bound_imports;
+pub mod call_depth;
+pub mod caller;
+pub mod combined;
+pub mod coroutine;
+pub mod dylink;
+pub mod dynamic;
+pub mod epoch;
+pub mod exclusive_access;
+pub mod exclusive_call_depth;
+pub mod extern_ref;
+pub mod fuel;
+pub mod func_ref;
+pub mod host_func_ref;
 pub mod imports;
+pub mod linked;
 pub mod memory;
+pub mod pre_init;
+pub mod runtime_linker;
 pub mod simple;
+pub mod snapshot;
+pub mod stack_usage;
+pub mod thread_safe;
@@ -0,0 +1,40 @@
+include!(concat!(env!("OUT_DIR"), "/linked.rs"));
+
+#[derive(Debug, Default)]
+pub struct Env;
+
+impl Env {
+    fn offset(&self) -> Result<i32, wasm2rs_rt::trap::TrapValue> {
+        Ok(1)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Host {
+    env: Env,
+}
+
+impl Host {
+    fn env(&self) -> &Env {
+        &self.env
+    }
+}
+
+linked!(pub mod linked use super::Host);
+
+#[test]
+fn cross_instance_calls() {
+    use wasm2rs_rt::memory::Memory32;
+
+    let instances = linked::Instances::instantiate(Host::default()).unwrap();
+    assert_eq!(instances.main.store_square(64, 3), Ok(()));
+    assert_eq!(instances.main.store_square(68, 4), Ok(()));
+
+    // The memory is shared between both instances
+    assert_eq!(instances.lib.memory().i32_load::<0>(64), Ok(10));
+    assert_eq!(instances.lib.memory().i32_load::<0>(68), Ok(17));
+
+    // So is the global
+    assert_eq!(instances.main.square_calls(), Ok(2));
+    assert_eq!(instances.lib.calls().get(), 2);
+}
@@ -0,0 +1,15 @@
+(module
+  (import "lib" "square" (func $square (param i32) (result i32)))
+  (import "lib" "memory" (memory 1))
+  (import "lib" "calls" (global $calls (mut i32)))
+  (import "env" "offset" (func $offset (result i32)))
+
+  ;; Stores the square of the argument plus the host's offset at the given address
+  (func (export "store_square") (param $address i32) (param $value i32)
+    (i32.store
+      (local.get $address)
+      (i32.add (call $square (local.get $value)) (call $offset))))
+
+  (func (export "square_calls") (result i32)
+    (global.get $calls))
+)
@@ -16,6 +16,37 @@ fn bounds_checking() {
     inst.out_of_bounds_read().unwrap();
 }
 
+#[test]
+fn constant_addresses_in_bounds() {
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    assert_eq!(inst.read_last_int(), Ok(0));
+    inst.write_last_bytes().unwrap();
+    assert_eq!(inst.read_last_bytes(), Ok(0xABCD));
+    assert_eq!(inst.read_last_int(), Ok(0xABCD_0000u32 as i32));
+    assert!(inst.out_of_bounds_offset_read().is_err());
+}
+
+#[test]
+fn constant_address_bounds_checks_eliminated() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/memory.rs"));
+    assert!(generated.contains("i32_load_in_bounds::<4, 65532, 1,"));
+    assert!(generated.contains("i16_store_in_bounds::<0, 65534, 1,"));
+    assert!(!generated.contains("i32_load_in_bounds::<0, 65533,"));
+}
+
+#[test]
+fn masked_addresses_in_bounds() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/memory.rs"));
+    assert!(generated.contains("i32_load_in_bounds::<0, 65532, 1,"));
+    assert!(generated.contains("i16_store_in_bounds::<2, 65533, 1,"));
+
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    inst.masked_write(65531, 0x1234).unwrap();
+    inst.masked_write(-1, 0x5678).unwrap();
+    assert_eq!(inst.masked_read(0x0001_FFFF), Ok(0x0012_3400));
+    assert_eq!(inst.masked_read(16), Ok(0x0056_7800));
+}
+
 #[test]
 fn growing() {
     let inst = wasm::Instance::instantiate(Default::default()).unwrap();
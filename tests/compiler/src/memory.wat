@@ -16,6 +16,32 @@
     i32.const 65536
     i32.load)
 
+  (func (export "read_last_int") (result i32)
+    i32.const 65528
+    i32.load offset=4)
+
+  (func (export "write_last_bytes")
+    i32.const 65532
+    f32.const 1.5
+    f32.store offset=0
+    i32.const 65534
+    i32.const 0xABCD
+    i32.store16)
+
+  (func (export "read_last_bytes") (result i64)
+    i32.const 65534
+    i64.load16_u)
+
+  (func (export "out_of_bounds_offset_read") (result i32)
+    i32.const 65533
+    i32.load)
+
+  (func (export "masked_read") (param i32) (result i32)
+    (i32.load (i32.and (local.get 0) (i32.const 0xFFFC))))
+
+  (func (export "masked_write") (param i32 i32)
+    (i32.store16 offset=2 (i32.rem_u (local.get 0) (i32.const 0xFFFC)) (local.get 1)))
+
   (func (export "grow_then_write") (result i32)
     (local $old_size i32)
     (local $address i32)
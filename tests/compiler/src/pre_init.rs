@@ -0,0 +1,41 @@
+include!(concat!(env!("OUT_DIR"), "/pre_init.rs"));
+
+wasm!(pub mod wasm);
+
+#[test]
+fn already_initialized() {
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    assert_eq!(inst.counter().get(), 1);
+    assert_eq!(inst.size(), Ok(2));
+    assert_eq!(inst.square(0), Ok(0));
+    assert_eq!(inst.square(12), Ok(144));
+    assert_eq!(inst.square(255), Ok(65025));
+    assert_eq!(inst.load(65544), Ok(0xCAFE));
+    assert_eq!(inst.seed(), Ok(0x7_0000_0007));
+    assert_eq!(inst.scale(), Ok(-3.0));
+
+    let hello = (16..21).map(|i| inst.load_byte(i).unwrap() as u8);
+    assert!(hello.eq(b"hello".iter().copied()));
+
+    let name = (4096..4103).map(|i| inst.load_byte(i).unwrap() as u8);
+    assert!(name.eq(b"wasm2rs".iter().copied()));
+}
+
+#[test]
+fn initialize_again() {
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    inst._initialize().unwrap();
+    assert_eq!(inst.counter().get(), 1);
+    assert_eq!(inst.size(), Ok(3));
+    assert_eq!(inst.seed(), Ok(0xE_0000_0007));
+}
+
+#[test]
+fn start_function_not_called() {
+    // The start function already ran during pre-initialization, so its increment of the counter
+    // is not applied again by each new instance.
+    for _ in 0..2 {
+        let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+        assert_eq!(inst.counter().get(), 1);
+    }
+}
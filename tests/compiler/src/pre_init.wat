@@ -0,0 +1,49 @@
+(module
+  (memory (export "mem") 1 4)
+  (global $counter (export "counter") (mut i32) (i32.const 0))
+  (global $seed (mut i64) (i64.const 7))
+  (global $scale (mut f64) (f64.const 0))
+
+  (data (memory 0) (offset i32.const 16) "hello")
+  (data $name "wasm2rs")
+
+  (func $start
+    (global.set $counter (i32.add (global.get $counter) (i32.const 1))))
+
+  (start $start)
+
+  (func $initialize (export "_initialize")
+    (local $i i32)
+    (loop $squares
+      (i32.store offset=1024
+        (i32.shl (local.get $i) (i32.const 2))
+        (i32.mul (local.get $i) (local.get $i)))
+      (br_if $squares
+        (i32.lt_u
+          (local.tee $i (i32.add (local.get $i) (i32.const 1)))
+          (i32.const 256))))
+
+    (memory.init $name (i32.const 4096) (i32.const 0) (i32.const 7))
+    (drop (memory.grow (i32.const 1)))
+    (i32.store (i32.const 65544) (i32.const 0xCAFE))
+    (global.set $seed (i64.mul (global.get $seed) (i64.const 0x1_0000_0001)))
+    (global.set $scale (f64.convert_i32_s (i32.const -3))))
+
+  (func (export "square") (param i32) (result i32)
+    (i32.load offset=1024 (i32.shl (local.get 0) (i32.const 2))))
+
+  (func (export "load") (param i32) (result i32)
+    (i32.load (local.get 0)))
+
+  (func (export "load_byte") (param i32) (result i32)
+    (i32.load8_u (local.get 0)))
+
+  (func (export "seed") (result i64)
+    (global.get $seed))
+
+  (func (export "scale") (result f64)
+    (global.get $scale))
+
+  (func (export "size") (result i32)
+    (memory.size))
+)
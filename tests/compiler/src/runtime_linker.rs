@@ -0,0 +1,123 @@
+include!(concat!(env!("OUT_DIR"), "/runtime_linker.rs"));
+
+pub use wasm2rs_rt::linker::Linker;
+
+wasm2rs_rt::embedder_with_import! {
+    pub mod embedder(Linker)
+}
+
+wasm!(pub mod wasm use super::embedder);
+
+#[cfg(test)]
+fn define_add_and_bias(linker: &mut Linker) {
+    linker
+        .define(
+            "env",
+            "add",
+            wasm2rs_rt::func_ref::FuncRef::from_closure_2(|a: i32, b: i32| Ok(a.wrapping_add(b))),
+        )
+        .define_global("env", "bias", 10i32);
+}
+
+#[cfg(test)]
+fn link_error(linker: Linker) -> wasm2rs_rt::linker::LinkError {
+    match wasm::Instance::instantiate(embedder::State::new(linker)) {
+        Err(wasm2rs_rt::linker::InstantiationError::Link(error)) => error,
+        Err(error) => panic!("expected link error, but got {error}"),
+        Ok(_) => panic!("expected link error"),
+    }
+}
+
+#[test]
+fn closures_are_called() {
+    use wasm2rs_rt::func_ref::FuncRef;
+
+    let logged = std::rc::Rc::new(std::cell::Cell::new(0i64));
+    let mut linker = Linker::new();
+    define_add_and_bias(&mut linker);
+    linker.define("env", "log", {
+        let logged = logged.clone();
+        FuncRef::from_closure_1(move |value: i64| {
+            logged.set(value);
+            Ok(())
+        })
+    });
+
+    let inst = wasm::Instance::instantiate(embedder::State::new(linker)).unwrap();
+    assert_eq!(inst.add_and_log(2, 3), Ok(15));
+    assert_eq!(logged.get(), 15);
+}
+
+#[test]
+fn undefined_import() {
+    use wasm2rs_rt::linker::LinkErrorKind;
+
+    let mut linker = Linker::new();
+    define_add_and_bias(&mut linker);
+
+    let error = link_error(linker);
+    assert_eq!((error.module(), error.name()), ("env", "log"));
+    assert_eq!(error.kind(), &LinkErrorKind::Undefined);
+}
+
+#[test]
+fn signature_mismatch() {
+    use wasm2rs_rt::func_ref::FuncRef;
+    use wasm2rs_rt::linker::LinkErrorKind;
+
+    let mut linker = Linker::new();
+    define_add_and_bias(&mut linker);
+    linker.define("env", "log", FuncRef::from_closure_1(|_: i32| Ok(())));
+
+    let error = link_error(linker);
+    assert_eq!(error.name(), "log");
+    assert!(matches!(error.kind(), LinkErrorKind::SignatureMismatch(_)));
+}
+
+#[test]
+fn global_type_mismatch() {
+    use wasm2rs_rt::func_ref::FuncRef;
+    use wasm2rs_rt::linker::{GlobalType, LinkErrorKind};
+    use wasm2rs_rt::stack::trace::WasmValType;
+
+    let mut linker = Linker::new();
+    define_add_and_bias(&mut linker);
+    linker
+        .define("env", "log", FuncRef::from_closure_1(|_: i64| Ok(())))
+        .define_global("env", "bias", 10i64);
+
+    let error = link_error(linker);
+    assert_eq!(error.name(), "bias");
+    assert_eq!(
+        error.kind(),
+        &LinkErrorKind::GlobalTypeMismatch {
+            expected: GlobalType {
+                ty: WasmValType::I32,
+                mutable: false
+            },
+            actual: GlobalType {
+                ty: WasmValType::I64,
+                mutable: false
+            },
+        }
+    );
+}
+
+#[test]
+fn kind_mismatch() {
+    use wasm2rs_rt::linker::{ImportKind, LinkErrorKind};
+
+    let mut linker = Linker::new();
+    define_add_and_bias(&mut linker);
+    linker.define_global("env", "log", 0i64);
+
+    let error = link_error(linker);
+    assert_eq!(error.name(), "log");
+    assert_eq!(
+        error.kind(),
+        &LinkErrorKind::KindMismatch {
+            expected: ImportKind::Function,
+            actual: ImportKind::Global,
+        }
+    );
+}
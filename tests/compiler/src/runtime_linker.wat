@@ -0,0 +1,19 @@
+(module
+  (import "env" "add" (func $add (param i32 i32) (result i32)))
+  (import "env" "log" (func $log (param i64)))
+  (import "env" "bias" (global $bias i32))
+
+  (global $biased i32 (global.get $bias))
+
+  (func (export "add_and_log") (param i32 i32) (result i32)
+    (local $sum i32)
+    local.get 0
+    local.get 1
+    call $add
+    global.get $biased
+    i32.add
+    local.tee $sum
+    i64.extend_i32_s
+    call $log
+    local.get $sum)
+)
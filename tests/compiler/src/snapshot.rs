@@ -0,0 +1,70 @@
+include!(concat!(env!("OUT_DIR"), "/snapshot.rs"));
+
+wasm!(pub mod wasm);
+
+#[test]
+fn round_trip() {
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    assert_eq!(inst.record(1.5), Ok(1));
+    assert_eq!(inst.record(2.0), Ok(2));
+    assert_eq!(inst.grow(), Ok(1));
+    inst.write(0, 0).unwrap();
+    inst.write(65536, 0x12345678).unwrap();
+
+    let snapshot = inst.snapshot();
+
+    let restored = wasm::Instance::instantiate(Default::default()).unwrap();
+    assert_eq!(restored.read(0), Ok(0x04030201));
+    restored.restore(&snapshot).unwrap();
+
+    assert_eq!(restored.counter().get(), 2);
+    assert_eq!(restored.last(), Ok(2.0));
+    assert_eq!(restored.read(0), Ok(0));
+    assert_eq!(restored.read(65536), Ok(0x12345678));
+    assert_eq!(restored.grow(), Ok(2));
+    assert_eq!(restored.snapshot().len(), snapshot.len());
+}
+
+#[test]
+fn invalid_snapshots() {
+    use wasm2rs_rt::snapshot::SnapshotError;
+
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    let snapshot = inst.snapshot();
+
+    let mut other_module = snapshot.clone();
+    other_module[8] ^= 1;
+    assert!(matches!(
+        inst.restore(&other_module),
+        Err(SnapshotError::FingerprintMismatch { .. })
+    ));
+
+    assert_eq!(
+        inst.restore(&snapshot[..snapshot.len() - 1]),
+        Err(SnapshotError::Malformed)
+    );
+
+    let mut trailing = snapshot.clone();
+    trailing.push(0);
+    assert_eq!(inst.restore(&trailing), Err(SnapshotError::Malformed));
+
+    assert_eq!(inst.restore(b"hello"), Err(SnapshotError::InvalidHeader));
+}
+
+#[test]
+fn memory_too_large() {
+    use wasm2rs_rt::snapshot::SnapshotError;
+
+    let inst = wasm::Instance::instantiate(Default::default()).unwrap();
+    assert_eq!(inst.grow(), Ok(1));
+    let snapshot = inst.snapshot();
+
+    assert_eq!(inst.grow(), Ok(2));
+    assert_eq!(
+        inst.restore(&snapshot),
+        Err(SnapshotError::MemorySize {
+            expected: 2,
+            actual: 3
+        })
+    );
+}
@@ -0,0 +1,25 @@
+(module
+  (memory (export "mem") 1 3)
+  (global $counter (export "counter") (mut i32) (i32.const 0))
+  (global $total (mut f64) (f64.const 0))
+  (global $base i32 (i32.const 16))
+
+  (data (memory 0) (offset i32.const 16) "\01\02\03\04")
+
+  (func (export "record") (param f64) (result i32)
+    (global.set $total (local.get 0))
+    (global.set $counter (i32.add (global.get $counter) (i32.const 1)))
+    (global.get $counter))
+
+  (func (export "last") (result f64)
+    (global.get $total))
+
+  (func (export "write") (param i32 i32)
+    (i32.store (i32.add (global.get $base) (local.get 0)) (local.get 1)))
+
+  (func (export "read") (param i32) (result i32)
+    (i32.load (i32.add (global.get $base) (local.get 0))))
+
+  (func (export "grow") (result i32)
+    (memory.grow (i32.const 1)))
+)
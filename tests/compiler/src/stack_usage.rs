@@ -0,0 +1,40 @@
+include!(concat!(env!("OUT_DIR"), "/stack_usage.rs"));
+
+#[derive(Debug)]
+pub struct Env;
+
+impl Env {
+    fn host(&self) -> Result<(), wasm2rs_rt::trap::TrapValue> {
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct Imports;
+
+impl Imports {
+    fn env(&self) -> &Env {
+        &Env
+    }
+}
+
+wasm2rs_rt::embedder_with_import! {
+    pub mod embedder(Imports)
+}
+
+wasm!(pub mod wasm use super::embedder);
+
+#[test]
+fn bounded_exports() {
+    let leaf = wasm::Instance::MAX_STACK_BYTES_leaf.unwrap();
+    let chain = wasm::Instance::MAX_STACK_BYTES_chain.unwrap();
+    assert!(leaf > 0);
+    assert!(chain > leaf);
+}
+
+#[test]
+fn unbounded_exports() {
+    assert_eq!(wasm::Instance::MAX_STACK_BYTES_recursive, None);
+    assert_eq!(wasm::Instance::MAX_STACK_BYTES_calls_recursive, None);
+    assert_eq!(wasm::Instance::MAX_STACK_BYTES_calls_import, None);
+}
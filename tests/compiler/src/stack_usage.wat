@@ -0,0 +1,19 @@
+(module
+  (import "env" "host" (func $host))
+
+  (func $leaf (export "leaf") (param i64 i64) (result i64)
+    (i64.add (local.get 0) (local.get 1)))
+
+  (func $chain (export "chain") (param i64) (result i64)
+    (call $leaf (local.get 0) (call $leaf (local.get 0) (local.get 0))))
+
+  (func $recursive (export "recursive") (param i32)
+    (if (local.get 0)
+      (then (call $recursive (i32.sub (local.get 0) (i32.const 1))))))
+
+  (func $calls_recursive (export "calls_recursive")
+    (call $recursive (i32.const 10)))
+
+  (func $calls_import (export "calls_import")
+    (call $host))
+)
@@ -0,0 +1,74 @@
+include!(concat!(env!("OUT_DIR"), "/thread_safe.rs"));
+
+#[derive(Debug, Default)]
+pub struct Env {
+    bias: wasm2rs_rt::global::AtomicGlobal<i32>,
+}
+
+impl thread_safe_example::imports::env for Env {
+    fn bias(&self) -> &wasm2rs_rt::global::AtomicGlobal<i32> {
+        &self.bias
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Imports {
+    env: Env,
+}
+
+impl thread_safe_example::Imports for Imports {
+    type env = Env;
+
+    fn env(&self) -> &Env {
+        &self.env
+    }
+}
+
+wasm2rs_rt::embedder_with_import! {
+    pub mod sync_embedder(Imports) memory(wasm2rs_rt::memory::SyncHeapMemory32)
+}
+
+wasm!(pub mod thread_safe_example use super::sync_embedder);
+
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<wasm2rs_rt::global::AtomicGlobal<i64>>();
+    assert_send_sync::<wasm2rs_rt::memory::SyncHeapMemory32>();
+    assert_send_sync::<sync_embedder::State>();
+    // Translation rejects thread-safe instances with execution limits, so the embedder's state
+    // doesn't need any `ExecutionLimits`
+    assert_send_sync::<
+        wasm2rs_rt::embedder::State<Imports, wasm2rs_rt::memory::SyncHeapMemory32, ()>,
+    >();
+    assert_send_sync::<thread_safe_example::Instance>();
+};
+
+#[test]
+fn shared_between_threads() {
+    let inst = thread_safe_example::Instance::instantiate(Default::default()).unwrap();
+    inst.set_bias(100).unwrap();
+
+    for value in 0..4 {
+        assert_eq!(inst.record(value), Ok(i64::from(value)));
+    }
+
+    std::thread::scope(|scope| {
+        let inst = &inst;
+        let workers = (0..4)
+            .map(|index| scope.spawn(move || inst.recorded(index)))
+            .collect::<Vec<_>>();
+
+        for (index, worker) in (0..).zip(workers) {
+            assert_eq!(worker.join().unwrap(), Ok(100 + index));
+        }
+    });
+
+    std::thread::spawn(move || {
+        assert_eq!(inst.record(7), Ok(4));
+        assert_eq!(inst.recorded(4), Ok(107));
+        assert_eq!(inst.counter().get(), 5);
+    })
+    .join()
+    .unwrap();
+}
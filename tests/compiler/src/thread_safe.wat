@@ -0,0 +1,21 @@
+(module
+  (import "env" "bias" (global $bias (mut i32)))
+
+  (memory (export "mem") 1)
+  (global $counter (export "counter") (mut i64) (i64.const 0))
+
+  (func (export "record") (param $value i32) (result i64)
+    (local $index i64)
+    (local.set $index (global.get $counter))
+    (global.set $counter (i64.add (local.get $index) (i64.const 1)))
+    (i32.store
+      (i32.wrap_i64 (i64.mul (local.get $index) (i64.const 4)))
+      (i32.add (local.get $value) (global.get $bias)))
+    (local.get $index))
+
+  (func (export "recorded") (param $index i32) (result i32)
+    (i32.load (i32.mul (local.get $index) (i32.const 4))))
+
+  (func (export "set_bias") (param i32)
+    (global.set $bias (local.get 0)))
+)